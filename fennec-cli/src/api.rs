@@ -1,14 +1,104 @@
-use std::sync::Arc;
-
+pub mod deye;
+pub mod dsmr;
+pub mod eastron;
+pub mod easy_energy;
 pub mod frank_energie;
 pub mod heartbeat;
 pub mod home_assistant;
 pub mod homewizard;
+pub mod http;
+pub mod inverter;
+pub mod meter;
 pub mod mini_qube;
+pub mod modbus;
+pub mod notify;
+pub mod real_time_price;
+pub mod shelly;
+#[cfg(test)]
+pub mod simulator;
+pub mod victron;
+pub mod webhook;
+pub mod zonneplan;
+
+use chrono::Local;
+
+use crate::{energy, prelude::*};
 
 pub struct Connections {
-    pub grid_measurement: homewizard::Client,
-    pub battery: Arc<mini_qube::Client>,
+    pub grid_measurement: meter::Meter,
+    pub topology: energy::Topology,
+    pub battery: inverter::Inverter,
     pub home_assistant_working_mode: home_assistant::StateClient,
+    pub battery_temperature: home_assistant::SensorClient,
+    pub home_assistant_heat_pump: home_assistant::StateClient,
+    pub home_assistant_entities: home_assistant::EntitiesClient,
     pub heartbeat: heartbeat::Client,
+    pub battery_heartbeat: heartbeat::Client,
+    pub grid_heartbeat: heartbeat::Client,
+    pub static_tariff: energy::StaticTariff,
+    pub frank_energie: frank_energie::Api,
+    pub easy_energy: easy_energy::Api,
+    pub zonneplan: zonneplan::Api,
+    pub real_time_price: real_time_price::Client,
+    pub ev_webhook: webhook::Client,
+    pub notify: notify::Client,
+}
+
+impl Connections {
+    /// Probe the configured devices and services once, so that the setup can be validated
+    /// before running the engine.
+    ///
+    /// Errors if any of the required connections fails, the optional ones only get reported.
+    pub async fn check(&self, energy_provider: energy::Provider) -> Result {
+        let mut n_failures = 0_usize;
+
+        match self.battery.read_metrics().await {
+            Ok(metrics) => info!(
+                state_of_charge = ?metrics.state_of_charge,
+                state_of_health = ?metrics.state_of_health,
+                design_capacity = ?metrics.design_capacity,
+                "battery is fine",
+            ),
+            Err(error) => {
+                n_failures += 1;
+                error!("battery check failed: {error:#}");
+            }
+        }
+
+        match self.grid_measurement.get_measurement().await {
+            Ok(metrics) => info!(active_power = ?metrics.active_power, "grid meter is fine"),
+            Err(error) => {
+                n_failures += 1;
+                error!("grid meter check failed: {error:#}");
+            }
+        }
+
+        match energy_provider.get_future_prices(self, Local::now()).await {
+            Ok(prices) => {
+                info!(len = prices.len(), end = ?prices.end_index(), "energy prices are fine");
+            }
+            Err(error) => {
+                n_failures += 1;
+                error!("energy prices check failed: {error:#}");
+            }
+        }
+
+        if self.battery_temperature.is_configured() {
+            if let Some(temperature) = self.battery_temperature.get().await {
+                info!(temperature, "battery temperature is fine");
+            } else {
+                warn!("battery temperature is unavailable");
+            }
+        }
+
+        match self.real_time_price.get_price().await {
+            Ok(Some(price)) => info!(?price.import, ?price.export, "real-time price is fine"),
+            Ok(None) => {}
+            Err(error) => warn!("real-time price check failed: {error:#}"),
+        }
+
+        ensure!(n_failures == 0, "{n_failures} required connection(s) failed");
+        info!("all required connections are fine");
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+//! Deye and Sunsynk hybrid inverter Modbus client.
+
+pub mod schedule;
+
+use std::{range::RangeInclusive, sync::Mutex};
+
+use fennec_modbus::{
+    contrib::deye::{
+        ReadBatteryPower,
+        ReadStateOfCharge,
+        ReadTimeOfUse,
+        ReadTotalBatteryChargeEnergy,
+        ReadTotalBatteryDischargeEnergy,
+        TimeOfUse,
+        UNIT_ID,
+        WriteTimeOfUse,
+    },
+    protocol::{address, function::write_multiple},
+};
+
+use crate::{
+    api::modbus,
+    battery::Metrics,
+    energy::Flow,
+    prelude::*,
+    quantity::{Quantity, Zero, energy::DecawattHours, power::Watts, ratios::Percentage},
+};
+
+/// Deye Modbus client.
+#[must_use]
+pub struct Client {
+    inner: modbus::Client,
+    design_capacity: DecawattHours,
+
+    /// Table confirmed by this client, used to detect changes made behind our back.
+    written_time_of_use: Mutex<Option<TimeOfUse>>,
+}
+
+impl Client {
+    pub fn new(inner: modbus::Client, design_capacity: DecawattHours) -> Self {
+        Self { inner, design_capacity, written_time_of_use: Mutex::default() }
+    }
+
+    /// Read the battery metrics.
+    ///
+    /// Deye only counts the energy on the battery side, so the estimated efficiency
+    /// does not account for the inverter losses.
+    #[instrument(skip_all)]
+    pub async fn read_metrics(&self) -> Result<Metrics> {
+        let state_of_charge = self
+            .inner
+            .call::<ReadStateOfCharge>(UNIT_ID, address::Const)
+            .await
+            .context("failed to read the SoC")?
+            .try_into()?;
+        let active_power = self
+            .inner
+            .call::<ReadBatteryPower>(UNIT_ID, address::Const)
+            .await
+            .context("failed to read the battery power")?
+            .into();
+        let total_charge_energy = self
+            .inner
+            .call::<ReadTotalBatteryChargeEnergy>(UNIT_ID, address::Const)
+            .await
+            .context("failed to read the total charged energy")?;
+        let total_discharge_energy = self
+            .inner
+            .call::<ReadTotalBatteryDischargeEnergy>(UNIT_ID, address::Const)
+            .await
+            .context("failed to read the total discharged energy")?;
+
+        Ok(Metrics {
+            state_of_charge,
+            state_of_health: Percentage::FULL, // not reported
+            design_capacity: self.design_capacity,
+            total_grid_flow: Flow {
+                import: Quantity(total_charge_energy.0 * 10),
+                export: Quantity(total_discharge_energy.0 * 10),
+            },
+            allowed_soc: RangeInclusive { start: Percentage::ZERO, last: Percentage::FULL },
+            active_power,
+            eps_active_power: Watts::ZERO,
+        })
+    }
+
+    /// Read the time-of-use table.
+    pub async fn read_time_of_use(&self) -> Result<TimeOfUse> {
+        self.inner
+            .call::<ReadTimeOfUse>(UNIT_ID, address::Const)
+            .await
+            .context("failed to read the time-of-use table")
+    }
+
+    /// Write the time-of-use table, if it differs from the current one, and verify it.
+    ///
+    /// Warns if the current table differs from the one confirmed on the previous call.
+    #[instrument(skip_all)]
+    pub async fn write_time_of_use(&self, current: &TimeOfUse, time_of_use: TimeOfUse) -> Result {
+        if let Some(written) = *self.written_time_of_use.lock().unwrap()
+            && written != *current
+        {
+            warn!(?written, ?current, "time-of-use table has drifted");
+        }
+        if *current != time_of_use {
+            info!(?time_of_use, "writing the time-of-use table…");
+            self.inner
+                .call::<WriteTimeOfUse>(
+                    UNIT_ID,
+                    write_multiple::Args::new(address::Const, time_of_use),
+                )
+                .await?;
+            ensure!(self.read_time_of_use().await? == time_of_use);
+        }
+        *self.written_time_of_use.lock().unwrap() = Some(time_of_use);
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use std::range::RangeInclusive;
+
+use chrono::{DateTime, DurationRound, Local, NaiveDateTime, TimeDelta, Timelike};
+use fennec_modbus::contrib::{
+    deye::{N_PROGRAMS, Time, TimeOfUse},
+    types,
+};
+
+use crate::{
+    battery,
+    prelude::*,
+    quantity::{Zero, power::Watts, ratios::Percentage},
+};
+
+/// Inverter-agnostic time-of-use program.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Program {
+    /// Maximum battery power.
+    pub power: Watts,
+
+    /// Minimum state-of-charge, also the charging target when grid charging is enabled.
+    pub state_of_charge: Percentage,
+
+    pub is_grid_charging: bool,
+}
+
+impl Program {
+    /// Approximate the working mode with a time-of-use program.
+    ///
+    /// Deye cannot force discharging nor forbid solar charging, so these are best-effort.
+    pub fn new(
+        working_mode: battery::WorkingMode,
+        power_level: Percentage,
+        allowed_soc: RangeInclusive<Percentage>,
+        current_soc: Percentage,
+        power_limits: battery::PowerLimits,
+    ) -> Self {
+        let power_ratio = power_level.to_ratio();
+        match working_mode {
+            battery::WorkingMode::Charge => Self {
+                power: power_limits.charging * power_ratio,
+                state_of_charge: allowed_soc.last,
+                is_grid_charging: true,
+            },
+            battery::WorkingMode::Idle => Self {
+                power: Watts::ZERO,
+                state_of_charge: current_soc.clamp(allowed_soc.start, allowed_soc.last),
+                is_grid_charging: false,
+            },
+            battery::WorkingMode::Harness => Self {
+                power: power_limits.discharging,
+                state_of_charge: current_soc.clamp(allowed_soc.start, allowed_soc.last),
+                is_grid_charging: false,
+            },
+            battery::WorkingMode::SelfUse
+            | battery::WorkingMode::Compensate
+            | battery::WorkingMode::Discharge => Self {
+                power: power_limits.discharging * power_ratio,
+                state_of_charge: allowed_soc.start,
+                is_grid_charging: false,
+            },
+        }
+    }
+}
+
+/// Fold the upcoming programs into the inverter's time-of-use table.
+///
+/// The table only has [`N_PROGRAMS`] programs for the next 24 hours, so we merge the repeating
+/// programs, let the last one run till the wrap-around, and split the longest ones if there are
+/// too few. The plan is expected to be compressed to fit beforehand, any extra programs get dropped.
+/// The voltage targets and generator flags are kept as they are.
+///
+/// The table follows the wall clock, so the hour which repeats when the DST ends only keeps
+/// the programs of its first occurrence, and the hour skipped when the DST starts is just absent.
+pub fn make_time_of_use(
+    current: &TimeOfUse,
+    programs: impl IntoIterator<Item = (DateTime<Local>, Program)>,
+) -> TimeOfUse {
+    const QUARTER: TimeDelta = TimeDelta::minutes(15);
+
+    let mut runs: Vec<(NaiveDateTime, Program)> = Vec::with_capacity(N_PROGRAMS);
+    let mut wall_clock = NaiveDateTime::MIN;
+    for (start, program) in programs {
+        let start = start.naive_local().duration_trunc(QUARTER).unwrap();
+        if runs.first().is_some_and(|(first_start, _)| start >= *first_start + TimeDelta::days(1)) {
+            break;
+        }
+        if start <= wall_clock {
+            // The wall clock went back as the DST ended:
+            continue;
+        }
+        wall_clock = start;
+        if runs.last().is_none_or(|(_, last_program)| *last_program != program) {
+            if runs.len() == N_PROGRAMS {
+                warn!(%start, "too many programs, dropping the rest");
+                break;
+            }
+            runs.push((start, program));
+        }
+    }
+    let Some(&(first_start, _)) = runs.first() else {
+        return *current;
+    };
+    let wrap_around = first_start + TimeDelta::days(1);
+
+    while runs.len() < N_PROGRAMS {
+        let (index, (start, end)) = runs
+            .iter()
+            .enumerate()
+            .map(|(index, (start, _))| {
+                let end = runs.get(index + 1).map_or(wrap_around, |(next_start, _)| *next_start);
+                (index, (*start, end))
+            })
+            .max_by_key(|(_, (start, end))| *end - *start)
+            .unwrap();
+        let middle = (start + (end - start) / 2).duration_trunc(QUARTER).unwrap();
+        runs.insert(index + 1, (middle, runs[index].1));
+    }
+
+    runs.sort_by_key(|(start, _)| start.time());
+
+    let mut time_of_use = *current;
+    for (index, (start, program)) in runs.into_iter().enumerate() {
+        #[expect(clippy::cast_possible_truncation)]
+        let time = Time { hour: start.hour() as u8, minute: start.minute() as u8 };
+        time_of_use.times[index] = time;
+        #[expect(clippy::cast_possible_truncation)]
+        #[expect(clippy::cast_sign_loss)]
+        let power = types::Watts(program.power.0 as u16);
+        time_of_use.powers[index] = power;
+        time_of_use.states_of_charge[index] = program.state_of_charge.into();
+        time_of_use.charge_flags[index] =
+            (time_of_use.charge_flags[index] & !1) | u16::from(program.is_grid_charging);
+    }
+    time_of_use
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    const CHARGE: Program =
+        Program { power: Quantity(1000.0), state_of_charge: Quantity(100), is_grid_charging: true };
+
+    const SELF_USE: Program =
+        Program { power: Quantity(800.0), state_of_charge: Quantity(10), is_grid_charging: false };
+
+    #[test]
+    fn make_time_of_use_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 5, 0).unwrap();
+        let programs = [
+            (start, SELF_USE),
+            (start + TimeDelta::hours(1), SELF_USE),
+            (start + TimeDelta::hours(2), CHARGE),
+            (start + TimeDelta::hours(3), SELF_USE),
+        ];
+        let time_of_use = make_time_of_use(&TimeOfUse::default(), programs);
+        assert_eq!(
+            time_of_use.times,
+            [
+                Time { hour: 2, minute: 30 },
+                Time { hour: 7, minute: 45 },
+                Time { hour: 13, minute: 0 },
+                Time { hour: 15, minute: 0 },
+                Time { hour: 16, minute: 0 },
+                Time { hour: 21, minute: 15 },
+            ],
+        );
+        assert_eq!(time_of_use.charge_flags, [0, 0, 0, 1, 0, 0]);
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn make_time_of_use_dst_end() {
+        let start = Local.with_ymd_and_hms(2026, 10, 25, 1, 0, 0).unwrap();
+        let programs = [
+            (start, SELF_USE),
+            (start + TimeDelta::hours(1), CHARGE),
+            // Repeated 02:00:
+            (start + TimeDelta::hours(2), SELF_USE),
+            (start + TimeDelta::hours(3), SELF_USE),
+            (start + TimeDelta::hours(4), CHARGE),
+        ];
+        let time_of_use = make_time_of_use(&TimeOfUse::default(), programs);
+        assert_eq!(
+            time_of_use.times,
+            [
+                Time { hour: 1, minute: 0 },
+                Time { hour: 2, minute: 0 },
+                Time { hour: 3, minute: 0 },
+                Time { hour: 4, minute: 0 },
+                Time { hour: 14, minute: 30 },
+                Time { hour: 19, minute: 45 },
+            ],
+        );
+        assert_eq!(time_of_use.charge_flags, [0, 1, 0, 1, 1, 1]);
+    }
+}
@@ -0,0 +1,170 @@
+//! [DSMR][1] 4 and 5 P1 telegram reader.
+//!
+//! The smart meter pushes a telegram every second (DSMR 5) or every ten seconds (DSMR 4)
+//! over its serial P1 port. The port is expected to be exposed over TCP, for example,
+//! by `ser2net` or by a network P1 dongle.
+//!
+//! [1]: https://www.netbeheernederland.nl/publicatie/dsmr-505-p1-companion-standard
+
+use std::time::Duration;
+
+use tokio::{
+    io::{AsyncBufReadExt, BufReader},
+    net::TcpStream,
+    time::timeout,
+};
+
+use crate::{
+    api::homewizard::EnergyMetrics,
+    prelude::*,
+    quantity::{
+        Quantity,
+        power::{Kilowatts, Watts},
+    },
+};
+
+#[must_use]
+pub struct Client {
+    address: String,
+}
+
+impl Client {
+    /// DSMR 4 meters push the telegrams every ten seconds.
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    pub const fn new(address: String) -> Self {
+        Self { address }
+    }
+
+    #[instrument(skip_all, fields(address = %self.address))]
+    pub async fn get_measurement(&self) -> Result<EnergyMetrics> {
+        let telegram =
+            timeout(Self::TIMEOUT, self.read_telegram()).await.with_context(|| {
+                format!("timed out waiting for a telegram from `{}`", self.address)
+            })??;
+        let measurement = parse_telegram(&telegram)?;
+        debug!(import = ?measurement.import, export = ?measurement.export);
+        Ok(measurement)
+    }
+
+    /// Read the next complete telegram, from the `/` header till the `!` CRC line.
+    async fn read_telegram(&self) -> Result<String> {
+        let stream = TcpStream::connect(&self.address)
+            .await
+            .with_context(|| format!("failed to connect to `{}`", self.address))?;
+        let mut lines = BufReader::new(stream).lines();
+        let mut telegram: Option<String> = None;
+        while let Some(line) = lines.next_line().await? {
+            if line.starts_with('/') {
+                telegram = Some(String::new());
+            }
+            if let Some(telegram) = &mut telegram {
+                telegram.push_str(&line);
+                telegram.push_str("\r\n");
+                if line.starts_with('!') {
+                    return Ok(telegram.clone());
+                }
+            }
+        }
+        bail!("connection closed before a complete telegram")
+    }
+}
+
+/// Parse the telegram and verify its CRC.
+fn parse_telegram(telegram: &str) -> Result<EnergyMetrics> {
+    let crc_index = telegram.find('!').context("the telegram has no CRC line")?;
+    let (data, crc) = telegram.split_at(crc_index + 1);
+    let expected_crc =
+        u16::from_str_radix(crc.trim(), 16).with_context(|| format!("invalid CRC: `{crc}`"))?;
+    let actual_crc = crc16(data.as_bytes());
+    ensure!(
+        actual_crc == expected_crc,
+        "CRC mismatch: expected {expected_crc:04X}, actual {actual_crc:04X}",
+    );
+
+    let mut import = None;
+    let mut export = None;
+    let mut import_power = None;
+    let mut export_power = None;
+    for line in data.lines() {
+        let Some((obis, rest)) = line.split_once('(') else {
+            continue;
+        };
+        let target = match obis {
+            "1-0:1.8.1" | "1-0:1.8.2" => &mut import,
+            "1-0:2.8.1" | "1-0:2.8.2" => &mut export,
+            "1-0:1.7.0" => &mut import_power,
+            "1-0:2.7.0" => &mut export_power,
+            _ => continue,
+        };
+        let value = rest.split(['*', ')']).next().unwrap_or_default();
+        let value: f64 = value.parse().with_context(|| format!("invalid value in `{line}`"))?;
+        *target = Some(target.unwrap_or_default() + value);
+    }
+
+    let power = |value: Option<f64>, name: &str| -> Result<Watts> {
+        Ok(Kilowatts::new(value.with_context(|| format!("missing {name}"))?).rescale())
+    };
+    Ok(EnergyMetrics {
+        active_power: power(import_power, "import power")? - power(export_power, "export power")?,
+        import: Quantity(import.context("missing total import")?),
+        export: Quantity(export.context("missing total export")?),
+    })
+}
+
+/// [CRC-16/ARC][1] as required by DSMR 4 and 5.
+///
+/// [1]: https://reveng.sourceforge.io/crc-catalogue/16.htm#crc.cat.crc-16-arc
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ u16::from(*byte), |crc, _| {
+            if crc & 1 == 0 { crc >> 1 } else { (crc >> 1) ^ 0xA001 }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DSMR 5 telegram of a single-phase Iskra meter, trimmed to the relevant lines.
+    fn telegram() -> String {
+        let data = "/ISK5\\2M550E-1012\r\n\r\n\
+            1-3:0.2.8(50)\r\n\
+            0-0:1.0.0(260119145509W)\r\n\
+            1-0:1.8.1(018070.244*kWh)\r\n\
+            1-0:1.8.2(017194.565*kWh)\r\n\
+            1-0:2.8.1(002425.682*kWh)\r\n\
+            1-0:2.8.2(005442.131*kWh)\r\n\
+            0-0:96.14.0(0002)\r\n\
+            1-0:1.7.0(00.000*kW)\r\n\
+            1-0:2.7.0(00.011*kW)\r\n\
+            !";
+        format!("{data}{:04X}\r\n", crc16(data.as_bytes()))
+    }
+
+    #[test]
+    fn crc16_ok() {
+        assert_eq!(crc16(b"123456789"), 0xBB3D);
+    }
+
+    #[test]
+    fn parse_telegram_ok() -> Result {
+        let measurement = parse_telegram(&telegram())?;
+        assert!((measurement.active_power.0 + 11.0).abs() < 1e-9);
+        assert!((measurement.import.0 - 35264.809).abs() < 1e-9);
+        assert!((measurement.export.0 - 7867.813).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_telegram_crc_mismatch() {
+        let telegram = telegram().replace("018070.244", "018070.245");
+        assert!(parse_telegram(&telegram).is_err());
+    }
+
+    #[test]
+    fn parse_telegram_missing_crc() {
+        assert!(parse_telegram("/ISK5\\2M550E-1012\r\n1-0:1.7.0(00.000*kW)\r\n").is_err());
+    }
+}
@@ -0,0 +1,106 @@
+//! Eastron SDM energy meter Modbus client, see [`fennec_modbus::contrib::eastron`].
+
+use fennec_modbus::{
+    contrib::eastron::{ReadFloat, RegisterMap, SDM120, SDM630, UNIT_ID},
+    tcp::UnitId,
+};
+
+use crate::{api::homewizard::EnergyMetrics, prelude::*, quantity::Quantity};
+
+#[derive(clap::Args)]
+#[group(id = "eastron")]
+pub struct Args {
+    /// Eastron meter Modbus TCP address, for example, of an RS485 gateway.
+    #[clap(name = "eastron_address", long = "eastron-address", env = "EASTRON_ADDRESS")]
+    pub address: Option<String>,
+
+    /// Eastron meter model, defines the default register map.
+    #[clap(long = "eastron-model", env = "EASTRON_MODEL", default_value = "sdm630")]
+    pub model: Model,
+
+    /// Eastron meter Modbus unit ID.
+    #[clap(long = "eastron-unit-id", env = "EASTRON_UNIT_ID")]
+    pub unit_id: Option<u8>,
+
+    /// Override the total active power input register, in watts.
+    #[clap(long = "eastron-power-register", env = "EASTRON_POWER_REGISTER")]
+    pub power_register: Option<u16>,
+
+    /// Override the total import energy input register, in kilowatt-hours.
+    #[clap(long = "eastron-import-register", env = "EASTRON_IMPORT_REGISTER")]
+    pub import_register: Option<u16>,
+
+    /// Override the total export energy input register, in kilowatt-hours.
+    #[clap(long = "eastron-export-register", env = "EASTRON_EXPORT_REGISTER")]
+    pub export_register: Option<u16>,
+}
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Model {
+    /// Single-phase SDM120 or SDM230.
+    Sdm120,
+
+    /// Three-phase SDM630 or SDM72.
+    Sdm630,
+}
+
+impl Args {
+    fn register_map(&self) -> RegisterMap {
+        let defaults = match self.model {
+            Model::Sdm120 => SDM120,
+            Model::Sdm630 => SDM630,
+        };
+        RegisterMap {
+            total_power: self.power_register.unwrap_or(defaults.total_power),
+            import_energy: self.import_register.unwrap_or(defaults.import_energy),
+            export_energy: self.export_register.unwrap_or(defaults.export_energy),
+        }
+    }
+}
+
+/// Eastron Modbus client.
+#[must_use]
+pub struct Client {
+    inner: fennec_modbus::tcp::tokio::Client<String>,
+    unit_id: UnitId,
+    registers: RegisterMap,
+}
+
+impl Client {
+    pub fn new(args: Args) -> Result<Self> {
+        let registers = args.register_map();
+        Ok(Self {
+            inner: fennec_modbus::tcp::tokio::Client::new(
+                args.address.context("Eastron requires the Modbus address")?,
+            ),
+            unit_id: args.unit_id.map_or(UNIT_ID, UnitId::from),
+            registers,
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub async fn get_measurement(&self) -> Result<EnergyMetrics> {
+        let active_power = self
+            .inner
+            .call::<ReadFloat>(self.unit_id, self.registers.total_power)
+            .await
+            .context("failed to read the active power")?;
+        let import = self
+            .inner
+            .call::<ReadFloat>(self.unit_id, self.registers.import_energy)
+            .await
+            .context("failed to read the total import")?;
+        let export = self
+            .inner
+            .call::<ReadFloat>(self.unit_id, self.registers.export_energy)
+            .await
+            .context("failed to read the total export")?;
+        let measurement = EnergyMetrics {
+            active_power: Quantity(f64::from(active_power)),
+            import: Quantity(f64::from(import)),
+            export: Quantity(f64::from(export)),
+        };
+        debug!(import = ?measurement.import, export = ?measurement.export);
+        Ok(measurement)
+    }
+}
@@ -0,0 +1,153 @@
+//! [easyEnergy][1] public price API, which ANWB Energie uses as well.
+//!
+//! [1]: https://www.easyenergy.com/nl/energietarieven
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat, TimeDelta, Utc};
+use serde::Deserialize;
+
+use crate::{
+    Schedule,
+    energy::Flow,
+    ops::interval::Interval,
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+#[derive(Copy, Clone, clap::Args)]
+#[group(id = "easy-energy")]
+pub struct Args {
+    /// Energy tax in ¤/kWh excluding VAT, which the easyEnergy prices do not include.
+    ///
+    /// See <https://www.belastingdienst.nl/wps/wcm/connect/bldcontentnl/belastingdienst/zakelijk/overige_belastingen/belastingen_op_milieugrondslag/tarieven_milieubelastingen/tabellen_tarieven_milieubelastingen>.
+    #[clap(
+        long = "easy-energy-energy-tax",
+        env = "EASY_ENERGY_ENERGY_TAX",
+        default_value = "0.0916"
+    )]
+    pub energy_tax: KilowattHourPrice,
+}
+
+/// easyEnergy API client.
+///
+/// It is meant to be created once, so that the underlying connection pool gets reused.
+pub struct Api {
+    client: reqwest::Client,
+    energy_tax: KilowattHourPrice,
+}
+
+impl Api {
+    const URL: &str = "https://mijn.easyenergy.com/nl/api/tariff/getapxtariffs";
+
+    /// The API returns the hourly APX prices including VAT, but without the energy tax.
+    const VAT: f64 = 1.21;
+
+    pub fn new(args: Args, builder: reqwest::ClientBuilder) -> Result<Self> {
+        let client = builder
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(15))
+            .build()?;
+        Ok(Self { client, energy_tax: args.energy_tax })
+    }
+
+    #[instrument(skip_all, fields(on = ?on))]
+    pub async fn get_prices(&self, on: NaiveDate) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+        debug!(?on, "fetching…");
+        let day = Interval::local_day(on)?;
+        let timestamp = |timestamp: DateTime<Local>| {
+            timestamp.with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Millis, true)
+        };
+        let mut url = reqwest::Url::parse(Self::URL)?;
+        url.query_pairs_mut()
+            .append_pair("startTimestamp", &timestamp(day.start()))
+            .append_pair("endTimestamp", &timestamp(day.end()))
+            .append_pair("includeVat", "true");
+        let tariffs =
+            self.client.get(url).send().await?.error_for_status()?.json::<Vec<Tariff>>().await?;
+        into_schedule(day, tariffs, self.energy_tax)
+    }
+}
+
+/// Convert the hourly tariffs within the day into the price schedule.
+///
+/// Errors on unordered or duplicate slots rather than trusting the API blindly.
+fn into_schedule(
+    day: Interval<DateTime<Local>>,
+    tariffs: Vec<Tariff>,
+    energy_tax: KilowattHourPrice,
+) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+    let mut schedule = Schedule::new();
+    for tariff in tariffs {
+        let interval = Interval::new(tariff.timestamp, tariff.timestamp + TimeDelta::hours(1));
+        if !day.contains(interval) {
+            continue;
+        }
+        let flow = Flow { import: tariff.usage + energy_tax * Api::VAT, export: tariff.feed_in };
+        schedule.extend_from_iter([(interval, flow)])?;
+    }
+    Ok(schedule)
+}
+
+/// Hourly tariff, the prices include VAT.
+#[derive(Deserialize)]
+struct Tariff {
+    /// Start of the hour.
+    #[serde(rename = "Timestamp")]
+    timestamp: DateTime<Local>,
+
+    #[serde(rename = "TariffUsage")]
+    usage: KilowattHourPrice,
+
+    #[serde(rename = "TariffReturn")]
+    feed_in: KilowattHourPrice,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[tokio::test]
+    #[ignore = "makes the API request"]
+    async fn get_prices_ok() -> Result {
+        let args = Args { energy_tax: Quantity(0.0916) };
+        let series = Api::new(args, reqwest::Client::builder())?
+            .get_prices(Local::now().date_naive())
+            .await?;
+        assert!(series.len() != 0);
+        assert!(series.len() <= 25);
+        assert_eq!(series.get(0).interval.start().hour(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_ok() -> Result {
+        let day = Interval::local_day(NaiveDate::from_ymd_opt(2026, 4, 8).unwrap())?;
+        let response = serde_json::json!([
+            // The day before is filtered out:
+            {
+                "Timestamp": day.start() - TimeDelta::hours(1),
+                "SupplierId": 0,
+                "TariffUsage": 0.1,
+                "TariffReturn": 0.08,
+            },
+            { "Timestamp": day.start(), "SupplierId": 0, "TariffUsage": 0.1, "TariffReturn": 0.08 },
+            {
+                "Timestamp": day.start() + TimeDelta::hours(1),
+                "SupplierId": 0,
+                "TariffUsage": 0.12,
+                "TariffReturn": 0.1,
+            },
+        ]);
+        let schedule = into_schedule(day, serde_json::from_value(response)?, Quantity(0.0916))?;
+        assert_eq!(schedule.len(), 2);
+        let slot = schedule.get(1);
+        assert_eq!(slot.interval.end() - slot.interval.start(), TimeDelta::hours(1));
+        assert!((slot.value.import.0 - 0.0916f64.mul_add(1.21, 0.12)).abs() < 1e-9);
+        assert!((slot.value.export.0 - 0.1).abs() < 1e-9);
+        Ok(())
+    }
+}
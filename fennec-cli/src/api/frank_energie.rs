@@ -1,6 +1,11 @@
-use std::time::Duration;
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 use chrono::{DateTime, Local, NaiveDate};
+use reqwest::{StatusCode, header::RETRY_AFTER};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -11,9 +16,22 @@ use crate::{
     quantity::{Quantity, price::KilowattHourPrice},
 };
 
+/// Frank Energie API client.
+///
+/// It is meant to be created once, so that the underlying connection pool gets reused.
 pub struct Api {
     client: reqwest::Client,
-    resolution: Resolution,
+    budget: Mutex<CallBudget>,
+}
+
+/// Errors which retrying blindly would not help.
+#[derive(Debug, derive_more::Display, derive_more::Error)]
+pub enum Throttled {
+    #[display("rate limited by Frank Energie (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+
+    #[display("hourly budget of {max_calls} calls is exhausted")]
+    BudgetExhausted { max_calls: usize },
 }
 
 impl Api {
@@ -22,37 +40,70 @@ impl Api {
 
     const VAT: f64 = 1.21;
 
-    pub fn new(resolution: Resolution) -> Result<Self> {
-        let client = reqwest::Client::builder().timeout(Duration::from_secs(15)).build()?;
-        Ok(Self { client, resolution })
+    /// Prices are published once a day, so there is no point in hammering the API.
+    const MAX_CALLS_PER_HOUR: usize = 60;
+
+    pub fn new(builder: reqwest::ClientBuilder) -> Result<Self> {
+        let client = builder
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(15))
+            .build()?;
+        Ok(Self { client, budget: Mutex::new(CallBudget::new(Self::MAX_CALLS_PER_HOUR)) })
     }
 
     #[instrument(skip_all, fields(on = ?on))]
-    pub async fn get_prices(&self, on: NaiveDate) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+    pub async fn get_prices(
+        &self,
+        on: NaiveDate,
+        resolution: Resolution,
+    ) -> Result<Schedule<Flow<KilowattHourPrice>>> {
         debug!(?on, "fetching…");
-        let mut schedule = Schedule::new();
-        if let Some(data) = self
+        if !self.budget.lock().unwrap().try_acquire(Instant::now()) {
+            return Err(Throttled::BudgetExhausted { max_calls: Self::MAX_CALLS_PER_HOUR }.into());
+        }
+        let response = self
             .client
             .post("https://www.frankenergie.nl/graphql")
-            .json(&Request::new(on, self.resolution))
+            .json(&Request::new(on, resolution))
             .send()
-            .await?
-            .error_for_status()?
-            .json::<Response>()
-            .await?
-            .data
-        {
-            let slots = data.market_prices.electricity.into_iter().map(|item| {
-                let flow = Flow {
-                    import: item.all_in,
-                    // TODO: from 2027, this becomes just `item.market + Self::PURCHASE_FEE`:
-                    export: (item.market + Self::PURCHASE_FEE) * Self::VAT,
-                };
-                (Interval::new(item.from, item.till), flow)
-            });
-            schedule.extend_from_iter(slots)?;
+            .await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse().ok())
+                .map(Duration::from_secs);
+            return Err(Throttled::RateLimited { retry_after }.into());
+        }
+        response.error_for_status()?.json::<Response>().await?.into_schedule()
+    }
+}
+
+/// Sliding one-hour window of the API calls.
+struct CallBudget {
+    max_calls: usize,
+    calls: VecDeque<Instant>,
+}
+
+impl CallBudget {
+    const WINDOW: Duration = Duration::from_hours(1);
+
+    const fn new(max_calls: usize) -> Self {
+        Self { max_calls, calls: VecDeque::new() }
+    }
+
+    /// Register the call if the budget allows it.
+    fn try_acquire(&mut self, now: Instant) -> bool {
+        while self.calls.front().is_some_and(|call| now.duration_since(*call) >= Self::WINDOW) {
+            self.calls.pop_front();
+        }
+        if self.calls.len() < self.max_calls {
+            self.calls.push_back(now);
+            true
+        } else {
+            false
         }
-        Ok(schedule)
     }
 }
 
@@ -97,6 +148,28 @@ struct Response {
     data: Option<Data>,
 }
 
+impl Response {
+    /// Convert the response into the price schedule.
+    ///
+    /// Errors on inverted, overlapping, or missing slots rather than trusting the API blindly.
+    fn into_schedule(self) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+        let mut schedule = Schedule::new();
+        let Some(data) = self.data else {
+            return Ok(schedule);
+        };
+        for item in data.market_prices.electricity {
+            ensure!(item.from < item.till, "invalid price slot `{}..{}`", item.from, item.till);
+            let flow = Flow {
+                import: item.all_in,
+                // TODO: from 2027, this becomes just `item.market + Api::PURCHASE_FEE`:
+                export: (item.market + Api::PURCHASE_FEE) * Api::VAT,
+            };
+            schedule.extend_from_iter([(Interval::new(item.from, item.till), flow)])?;
+        }
+        Ok(schedule)
+    }
+}
+
 #[derive(Deserialize)]
 struct Data {
     #[serde(rename = "marketPrices")]
@@ -123,14 +196,41 @@ struct ElectricityPrice {
 
 #[cfg(test)]
 mod tests {
-    use chrono::Timelike;
+    use chrono::{TimeDelta, TimeZone, Timelike, Utc};
 
     use super::*;
 
+    /// Build a response body with the specified slots.
+    fn body(slots: &[(DateTime<Utc>, DateTime<Utc>)]) -> serde_json::Value {
+        let electricity: Vec<_> = slots
+            .iter()
+            .map(|(from, till)| {
+                serde_json::json!({ "from": from, "till": till, "marketPrice": 0.1, "allInPrice": 0.25 })
+            })
+            .collect();
+        serde_json::json!({ "data": { "marketPrices": { "electricityPrices": electricity } } })
+    }
+
+    fn response(slots: &[(DateTime<Utc>, DateTime<Utc>)]) -> Result<Response> {
+        Ok(serde_json::from_value(body(slots))?)
+    }
+
+    /// Hourly slots for a day in Amsterdam, which starts at 22:00 or 23:00 UTC.
+    fn hourly(start: DateTime<Utc>, n_hours: i64) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        (0..n_hours)
+            .map(|hour| {
+                let from = start + TimeDelta::hours(hour);
+                (from, from + TimeDelta::hours(1))
+            })
+            .collect()
+    }
+
     #[tokio::test]
     #[ignore = "makes the API request"]
     async fn get_prices_ok() -> Result {
-        let series = Api::new(Resolution::Quarterly)?.get_prices(Local::now().date_naive()).await?;
+        let series = Api::new(reqwest::Client::builder())?
+            .get_prices(Local::now().date_naive(), Resolution::Quarterly)
+            .await?;
         assert!(series.len() != 0);
         assert!(series.len() <= 24 * 4);
         let first_slot = &series.get(0);
@@ -139,6 +239,17 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn call_budget() {
+        let mut budget = CallBudget::new(2);
+        let now = Instant::now();
+        assert!(budget.try_acquire(now));
+        assert!(budget.try_acquire(now + Duration::from_mins(1)));
+        assert!(!budget.try_acquire(now + Duration::from_mins(2)));
+        assert!(budget.try_acquire(now + CallBudget::WINDOW));
+        assert!(!budget.try_acquire(now + CallBudget::WINDOW));
+    }
+
     #[test]
     #[expect(clippy::too_many_lines)]
     fn parse_ok() -> Result {
@@ -465,4 +576,68 @@ mod tests {
         let _ = serde_json::from_str::<Response>(RESPONSE)?;
         Ok(())
     }
+
+    #[test]
+    fn into_schedule_no_data() -> Result {
+        let response: Response = serde_json::from_str(r#"{"data": null}"#)?;
+        assert_eq!(response.into_schedule()?.len(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn into_schedule_short_dst_day() -> Result {
+        let start = Utc.with_ymd_and_hms(2026, 3, 28, 23, 0, 0).unwrap();
+        assert_eq!(response(&hourly(start, 23))?.into_schedule()?.len(), 23);
+        Ok(())
+    }
+
+    #[test]
+    fn into_schedule_long_dst_day() -> Result {
+        let start = Utc.with_ymd_and_hms(2026, 10, 24, 22, 0, 0).unwrap();
+        assert_eq!(response(&hourly(start, 25))?.into_schedule()?.len(), 25);
+        Ok(())
+    }
+
+    #[test]
+    fn into_schedule_missing_hour() -> Result {
+        let start = Utc.with_ymd_and_hms(2026, 4, 7, 22, 0, 0).unwrap();
+        let mut slots = hourly(start, 24);
+        slots.remove(12);
+        assert!(response(&slots)?.into_schedule().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn into_schedule_duplicated_slot() -> Result {
+        let start = Utc.with_ymd_and_hms(2026, 4, 7, 22, 0, 0).unwrap();
+        let mut slots = hourly(start, 24);
+        slots.insert(12, slots[12]);
+        assert!(response(&slots)?.into_schedule().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn into_schedule_inverted_slot() -> Result {
+        let start = Utc.with_ymd_and_hms(2026, 4, 7, 22, 0, 0).unwrap();
+        let slots = [(start + TimeDelta::hours(1), start)];
+        assert!(response(&slots)?.into_schedule().is_err());
+        Ok(())
+    }
+
+    /// Truncated and corrupted responses must fail gracefully, never panic.
+    #[test]
+    fn parse_corrupted_never_panics() {
+        let start = Utc.with_ymd_and_hms(2026, 4, 7, 22, 0, 0).unwrap();
+        let valid = body(&hourly(start, 3)).to_string();
+        for (index, _) in valid.char_indices() {
+            if let Ok(response) = serde_json::from_str::<Response>(&valid[..index]) {
+                let _ = response.into_schedule();
+            }
+            let mut corrupted = valid.clone().into_bytes();
+            corrupted[index] = corrupted[index].wrapping_add(1);
+            if let Ok(response) = serde_json::from_slice::<Response>(&corrupted) {
+                let _ = response.into_schedule();
+            }
+        }
+    }
 }
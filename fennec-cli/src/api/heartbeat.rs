@@ -2,31 +2,80 @@ use std::time::Duration;
 
 use crate::prelude::*;
 
+/// Healthchecks.io-style ping, so that the monitoring tells a slow iteration from a failed one.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Signal {
+    /// The job has started, pinged at `<url>/start`.
+    Start,
+
+    /// The job has succeeded, pinged at the URL itself.
+    Success,
+
+    /// The job has failed, pinged at `<url>/fail`.
+    Failure,
+}
+
+impl Signal {
+    fn url(self, url: &reqwest::Url) -> reqwest::Url {
+        let suffix = match self {
+            Self::Start => "start",
+            Self::Success => return url.clone(),
+            Self::Failure => "fail",
+        };
+        let mut url = url.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().push(suffix);
+        }
+        url
+    }
+}
+
 pub struct Client(Option<(reqwest::Url, reqwest::Client)>);
 
 impl Client {
     #[instrument(skip_all)]
-    pub fn new(url: Option<reqwest::Url>) -> Result<Self> {
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
         let inner = match url {
-            Some(url) => {
-                Some((url, reqwest::Client::builder().timeout(Duration::from_secs(1)).build()?))
-            }
+            Some(url) => Some((url, builder.timeout(Duration::from_secs(1)).build()?)),
             None => None,
         };
         Ok(Self(inner))
     }
 
-    #[instrument(skip_all)]
+    /// Ping the success without a payload.
     pub async fn send(&self) {
+        self.signal(Signal::Success, String::new()).await;
+    }
+
+    /// Ping the signal, attaching the payload as the request body.
+    #[instrument(skip_all, fields(signal = ?signal))]
+    pub async fn signal(&self, signal: Signal, payload: String) {
         if let Some((url, client)) = &self.0
-            && let Err(error) = Self::inner_send(url, client).await
+            && let Err(error) = Self::inner_send(&signal.url(url), client, payload).await
         {
             warn!("failed heartbeat: {error:#}");
         }
     }
 
-    async fn inner_send(url: &reqwest::Url, client: &reqwest::Client) -> Result {
-        client.post(url.clone()).send().await?.error_for_status()?;
+    async fn inner_send(url: &reqwest::Url, client: &reqwest::Client, payload: String) -> Result {
+        client.post(url.clone()).body(payload).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signal_url_ok() -> Result {
+        let url = reqwest::Url::parse("https://hc-ping.com/0123-4567")?;
+        assert_eq!(Signal::Start.url(&url).as_str(), "https://hc-ping.com/0123-4567/start");
+        assert_eq!(Signal::Success.url(&url).as_str(), "https://hc-ping.com/0123-4567");
+        assert_eq!(Signal::Failure.url(&url).as_str(), "https://hc-ping.com/0123-4567/fail");
+
+        let url = reqwest::Url::parse("https://example.com/ping/")?;
+        assert_eq!(Signal::Failure.url(&url).as_str(), "https://example.com/ping/fail");
         Ok(())
     }
 }
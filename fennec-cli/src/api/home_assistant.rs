@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use http::{HeaderMap, HeaderValue, header};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::prelude::*;
 
@@ -10,21 +10,8 @@ pub struct StateClient(Option<(reqwest::Client, reqwest::Url)>);
 
 impl StateClient {
     #[instrument(skip_all)]
-    pub fn new(url: Option<reqwest::Url>) -> Result<Self> {
-        let Some(mut url) = url else { return Ok(Self(None)) };
-
-        let bearer_token = url.fragment().context("URL fragment must contain the bearer token")?;
-        let mut headers = HeaderMap::new();
-        headers.insert(header::AUTHORIZATION, format!("Bearer {bearer_token}").try_into()?);
-        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
-        url.set_fragment(None);
-
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
-            .timeout(Duration::from_secs(1))
-            .build()?;
-
-        Ok(Self(Some((client, url))))
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
+        Ok(Self(url.map(|url| authorized_client(url, builder)).transpose()?))
     }
 
     pub async fn post<T: Serialize>(&self, value: &T) {
@@ -46,8 +33,98 @@ impl StateClient {
     }
 }
 
-#[derive(Serialize)]
+/// Client for the entities which Fennec creates itself, such as `sensor.fennec_working_mode`.
+///
+/// Home Assistant creates the entity on the first post, and it is not persisted across
+/// Home Assistant restarts – the next iteration posts it again.
+pub struct EntitiesClient(Option<(reqwest::Client, reqwest::Url)>);
+
+impl EntitiesClient {
+    #[instrument(skip_all)]
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
+        Ok(Self(url.map(|url| authorized_client(url, builder)).transpose()?))
+    }
+
+    pub const fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    pub async fn post<T: Serialize, A: Serialize>(&self, entity_id: &str, value: T, attributes: A) {
+        if let Some((client, url)) = &self.0
+            && let Err(error) = Self::inner_post(client, url, entity_id, value, attributes).await
+        {
+            warn!(entity_id, "failed to update the entity: {error:#}");
+        }
+    }
+
+    async fn inner_post<T: Serialize, A: Serialize>(
+        client: &reqwest::Client,
+        url: &reqwest::Url,
+        entity_id: &str,
+        value: T,
+        attributes: A,
+    ) -> Result {
+        let state = EntityState { value, attributes };
+        client.post(url.join(entity_id)?).json(&state).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Client for a single numeric sensor in Home Assistant.
+pub struct SensorClient(Option<(reqwest::Client, reqwest::Url)>);
+
+impl SensorClient {
+    #[instrument(skip_all)]
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
+        Ok(Self(url.map(|url| authorized_client(url, builder)).transpose()?))
+    }
+
+    pub const fn is_configured(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Get the sensor value, or [`None`] if the sensor is not configured or unavailable.
+    pub async fn get(&self) -> Option<f64> {
+        let (client, url) = self.0.as_ref()?;
+        Self::inner_get(client, url)
+            .await
+            .inspect_err(|error| warn!("failed to get the sensor state: {error:#}"))
+            .ok()
+    }
+
+    async fn inner_get(client: &reqwest::Client, url: &reqwest::Url) -> Result<f64> {
+        let state: State<String> =
+            client.get(url.clone()).send().await?.error_for_status()?.json().await?;
+        state.value.parse().with_context(|| format!("unexpected state: `{}`", state.value))
+    }
+}
+
+/// Build the client with the bearer token taken from the URL fragment.
+fn authorized_client(
+    mut url: reqwest::Url,
+    builder: reqwest::ClientBuilder,
+) -> Result<(reqwest::Client, reqwest::Url)> {
+    let bearer_token = url.fragment().context("URL fragment must contain the bearer token")?;
+    let mut headers = HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, format!("Bearer {bearer_token}").try_into()?);
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    url.set_fragment(None);
+
+    let client = builder.default_headers(headers).timeout(Duration::from_secs(1)).build()?;
+
+    Ok((client, url))
+}
+
+#[derive(Serialize, Deserialize)]
 struct State<T> {
     #[serde(rename = "state")]
     value: T,
 }
+
+#[derive(Serialize)]
+struct EntityState<T, A> {
+    #[serde(rename = "state")]
+    value: T,
+
+    attributes: A,
+}
@@ -1,9 +1,11 @@
+pub mod battery;
+
 use std::time::Duration;
 
 use bon::Builder;
 use derive_more::FromStr;
-use http::{HeaderMap, HeaderName, HeaderValue};
-use serde::{Deserialize, Serialize};
+use http::{HeaderMap, HeaderName, HeaderValue, header};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 
 use crate::{
     prelude::*,
@@ -15,12 +17,34 @@ pub struct Url(reqwest::Url);
 
 impl Url {
     #[instrument(skip_all, fields(url = %self.0))]
-    pub fn client(self) -> Result<Client> {
+    pub fn client(self, builder: reqwest::ClientBuilder) -> Result<Client> {
         let headers = HeaderMap::from_iter([(
             HeaderName::from_static("connection"),
             HeaderValue::from_static("close"),
         )]);
-        let inner = reqwest::Client::builder()
+        let inner = builder
+            .timeout(Duration::from_secs(10))
+            .default_headers(headers)
+            .pool_max_idle_per_host(0)
+            .build()?;
+        Ok(Client { inner, url: self.0 })
+    }
+
+    /// Make a client for the [v2 API][1], which requires the bearer token.
+    ///
+    /// The URL must have the fragment set to the token.
+    ///
+    /// [1]: https://api-documentation.homewizard.com/docs/v2/authorization
+    #[instrument(skip_all, fields(host = self.0.host_str()))]
+    pub fn authorized_client(mut self, builder: reqwest::ClientBuilder) -> Result<Client> {
+        let token = self.0.fragment().context("URL fragment must contain the bearer token")?;
+        let headers = HeaderMap::from_iter([
+            (HeaderName::from_static("connection"), HeaderValue::from_static("close")),
+            (HeaderName::from_static("x-api-version"), HeaderValue::from_static("2")),
+            (header::AUTHORIZATION, format!("Bearer {token}").try_into()?),
+        ]);
+        self.0.set_fragment(None);
+        let inner = builder
             .timeout(Duration::from_secs(10))
             .default_headers(headers)
             .pool_max_idle_per_host(0)
@@ -50,6 +74,33 @@ impl Client {
         debug!(import = ?measurement.import, export = ?measurement.export);
         Ok(measurement)
     }
+
+    /// Get the JSON resource at the path relative to the base URL.
+    async fn get<R: DeserializeOwned>(&self, path: &str) -> Result<R> {
+        let url = self.url.join(path)?;
+        self.inner
+            .get(url.clone())
+            .send()
+            .await
+            .with_context(|| format!("failed to request `{url}`"))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to deserialize the response from `{url}`"))
+    }
+
+    /// Update the JSON resource at the path relative to the base URL.
+    async fn put<T: Serialize>(&self, path: &str, body: &T) -> Result {
+        let url = self.url.join(path)?;
+        self.inner
+            .put(url.clone())
+            .json(body)
+            .send()
+            .await
+            .with_context(|| format!("failed to update `{url}`"))?
+            .error_for_status()?;
+        Ok(())
+    }
 }
 
 #[must_use]
@@ -0,0 +1,198 @@
+//! HomeWizard [Plug-In Battery][1] client.
+//!
+//! The battery reports its own measurements, whereas the P1 meter it is paired with
+//! [controls][2] the batteries. Both speak the v2 API over HTTPS only – the HomeWizard root
+//! certificate should be passed via `--ca-certificates`.
+//!
+//! [1]: https://api-documentation.homewizard.com/docs/v2/measurement
+//! [2]: https://api-documentation.homewizard.com/docs/v2/batteries
+
+use std::range::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{homewizard, http},
+    battery::{Metrics, WorkingMode},
+    energy::Flow,
+    prelude::*,
+    quantity::{Quantity, Zero, energy::DecawattHours, power::Watts, ratios::Percentage},
+};
+
+#[derive(clap::Args)]
+#[group(id = "homewizard-battery")]
+pub struct Args {
+    /// HomeWizard Plug-In Battery base URL, for example, `https://192.168.1.43#0123...6789`.
+    ///
+    /// The URL must have the fragment set to the bearer token.
+    #[clap(long = "homewizard-battery-url", env = "HOMEWIZARD_BATTERY_URL")]
+    pub battery_url: Option<homewizard::Url>,
+
+    /// Base URL of the HomeWizard P1 meter which controls the batteries.
+    ///
+    /// The URL must have the fragment set to the bearer token.
+    #[clap(long = "homewizard-p1-url", env = "HOMEWIZARD_P1_URL")]
+    pub p1_url: Option<homewizard::Url>,
+}
+
+#[must_use]
+pub struct Client {
+    battery: homewizard::Client,
+    p1: homewizard::Client,
+    design_capacity: DecawattHours,
+}
+
+impl Client {
+    pub fn new(args: Args, design_capacity: DecawattHours, http: &http::Args) -> Result<Self> {
+        Ok(Self {
+            battery: args
+                .battery_url
+                .context("HomeWizard requires the battery URL")?
+                .authorized_client(http.client_builder()?)?,
+            p1: args
+                .p1_url
+                .context("HomeWizard requires the P1 meter URL")?
+                .authorized_client(http.client_builder()?)?,
+            design_capacity,
+        })
+    }
+
+    #[instrument(skip_all)]
+    pub async fn read_metrics(&self) -> Result<Metrics> {
+        let measurement: Measurement = self
+            .battery
+            .get("api/measurement")
+            .await
+            .context("failed to read the battery measurement")?;
+        measurement.into_metrics(self.design_capacity)
+    }
+
+    /// Switch the batteries into the mode executing the working mode.
+    #[instrument(skip_all, fields(working_mode = ?working_mode))]
+    pub async fn write_working_mode(&self, working_mode: WorkingMode) -> Result {
+        let control = Control::try_from(working_mode)?;
+        self.p1.put("api/batteries", &control).await.context("failed to set the battery mode")
+    }
+}
+
+#[derive(Deserialize)]
+struct Measurement {
+    #[serde(rename = "state_of_charge_pct")]
+    state_of_charge: f64,
+
+    /// Positive means charging.
+    #[serde(rename = "power_w")]
+    power: f64,
+
+    #[serde(rename = "energy_import_kwh")]
+    import: f64,
+
+    #[serde(rename = "energy_export_kwh")]
+    export: f64,
+}
+
+impl Measurement {
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn into_metrics(self, design_capacity: DecawattHours) -> Result<Metrics> {
+        ensure!(
+            (0.0..=100.0).contains(&self.state_of_charge),
+            "invalid SoC: {}",
+            self.state_of_charge,
+        );
+        Ok(Metrics {
+            state_of_charge: Quantity(self.state_of_charge.round() as u8),
+            state_of_health: Percentage::FULL, // not reported
+            design_capacity,
+            total_grid_flow: Flow {
+                import: Quantity((self.import * 100.0).round() as u32),
+                export: Quantity((self.export * 100.0).round() as u32),
+            },
+            allowed_soc: RangeInclusive { start: Percentage::ZERO, last: Percentage::FULL },
+            active_power: Quantity(-self.power),
+            eps_active_power: Watts::ZERO,
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct Control {
+    mode: Mode,
+    permissions: &'static [Permission],
+}
+
+impl TryFrom<WorkingMode> for Control {
+    type Error = Error;
+
+    fn try_from(working_mode: WorkingMode) -> Result<Self> {
+        let (mode, permissions): (_, &[_]) = match working_mode {
+            WorkingMode::Idle => (Mode::Standby, &[]),
+            WorkingMode::Harness => (Mode::Zero, &[Permission::ChargeAllowed]),
+            WorkingMode::Compensate => (Mode::Zero, &[Permission::DischargeAllowed]),
+            WorkingMode::SelfUse => {
+                (Mode::Zero, &[Permission::ChargeAllowed, Permission::DischargeAllowed])
+            }
+            WorkingMode::Charge => (Mode::ToFull, &[]),
+            WorkingMode::Discharge => bail!("HomeWizard batteries cannot force discharging"),
+        };
+        Ok(Self { mode, permissions })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Mode {
+    /// Keep the grid power at zero.
+    Zero,
+
+    /// Charge at full power regardless of the consumption.
+    ToFull,
+
+    Standby,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Permission {
+    ChargeAllowed,
+    DischargeAllowed,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measurement_ok() -> Result {
+        // language=json
+        let body = r#"{
+            "energy_import_kwh": 123.456,
+            "energy_export_kwh": 111.111,
+            "power_w": -404,
+            "voltage_l1_v": 230.1,
+            "current_a": 1.76,
+            "frequency_hz": 50.01,
+            "state_of_charge_pct": 72.4,
+            "cycles": 42
+        }"#;
+        let metrics = serde_json::from_str::<Measurement>(body)?.into_metrics(Quantity(269))?;
+        assert_eq!(metrics.state_of_charge, Quantity(72));
+        assert_eq!(metrics.total_grid_flow.import, Quantity(12346));
+        assert_eq!(metrics.total_grid_flow.export, Quantity(11111));
+        assert!((metrics.active_power.0 - 404.0).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn control_ok() -> Result {
+        assert_eq!(
+            serde_json::to_value(Control::try_from(WorkingMode::Harness)?)?,
+            serde_json::json!({"mode": "zero", "permissions": ["charge_allowed"]}),
+        );
+        assert_eq!(
+            serde_json::to_value(Control::try_from(WorkingMode::Charge)?)?,
+            serde_json::json!({"mode": "to_full", "permissions": []}),
+        );
+        assert!(Control::try_from(WorkingMode::Discharge).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+
+use http::StatusCode;
+
+use crate::{prelude::*, quantity::ratios::Percentage};
+
+/// Common outbound HTTP settings.
+#[derive(clap::Args)]
+#[group(id = "http")]
+pub struct Args {
+    /// Proxy URL for all outbound HTTP requests, for example: `socks5://proxy.home.arpa:1080`.
+    ///
+    /// If not set, the standard `HTTP_PROXY`, `HTTPS_PROXY`, and `NO_PROXY` are respected.
+    #[clap(long = "proxy-url", env = "PROXY_URL")]
+    pub proxy_url: Option<reqwest::Url>,
+
+    /// Path to extra PEM-encoded root certificates to trust, for example, a private CA.
+    #[clap(long = "ca-certificates", env = "CA_CERTIFICATES")]
+    pub ca_certificates: Option<PathBuf>,
+
+    /// Maximum number of retries per idempotent request on connection errors, throttling,
+    /// and gateway errors. The per-client timeouts cover the retries as well.
+    #[clap(long = "http-max-retries", env = "HTTP_MAX_RETRIES", default_value = "2")]
+    pub max_retries: u32,
+
+    /// Extra load the retries may put on a host, percentage of the recent requests to it.
+    ///
+    /// Once the host is down for long, this stops hammering it until it is back.
+    #[clap(long = "http-retry-budget", env = "HTTP_RETRY_BUDGET", default_value = "20")]
+    pub retry_budget: Percentage,
+}
+
+/// Retry scope matching any host.
+///
+/// Each client gets its own retry budget, and each client talks to its own host,
+/// so that a flaky host does not exhaust the budget for the others.
+struct AnyHost;
+
+impl PartialEq<&str> for AnyHost {
+    fn eq(&self, _host: &&str) -> bool {
+        true
+    }
+}
+
+impl Args {
+    /// Make a new client builder with the proxy, certificates, and retries applied.
+    pub fn client_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder().retry(self.retry_policy());
+        if let Some(proxy_url) = &self.proxy_url {
+            builder = builder.proxy(reqwest::Proxy::all(proxy_url.clone())?);
+        }
+        if let Some(path) = &self.ca_certificates {
+            let bundle = std::fs::read(path)
+                .with_context(|| format!("failed to read `{}`", path.display()))?;
+            for certificate in reqwest::Certificate::from_pem_bundle(&bundle)? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        Ok(builder)
+    }
+
+    fn retry_policy(&self) -> reqwest::retry::Builder {
+        reqwest::retry::for_host(AnyHost)
+            .max_retries_per_request(self.max_retries)
+            .max_extra_load(f32::from(self.retry_budget.0) / 100.0)
+            .classify_fn(|attempt| {
+                let is_failed =
+                    attempt.error().is_some() || attempt.status().is_some_and(is_transient);
+                if is_failed && attempt.method().is_idempotent() {
+                    attempt.retryable()
+                } else {
+                    attempt.success()
+                }
+            })
+    }
+}
+
+/// Whether the response status is worth retrying.
+const fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::TOO_MANY_REQUESTS
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT,
+    )
+}
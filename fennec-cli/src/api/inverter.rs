@@ -0,0 +1,57 @@
+use crate::{
+    api::{deye, homewizard, mini_qube, victron},
+    battery,
+    battery::WorkingMode,
+    prelude::*,
+};
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Kind {
+    /// Fox ESS MiniQube, steered via its built-in schedule.
+    MiniQube,
+
+    /// Victron GX, steered via the ESS power setpoint.
+    Victron,
+
+    /// Deye or Sunsynk, steered via the time-of-use programs.
+    Deye,
+
+    /// HomeWizard Plug-In Battery, steered via the battery mode of its P1 meter.
+    HomeWizard,
+}
+
+impl Kind {
+    /// Check whether the inverter executes the working mode faithfully, rather than approximates it.
+    ///
+    /// The optimizer should not plan the modes which the inverter is going to execute differently.
+    pub const fn supports(self, working_mode: WorkingMode) -> bool {
+        match self {
+            Self::MiniQube | Self::Victron => true,
+
+            // Deye cannot force discharging nor forbid solar charging:
+            Self::Deye => !matches!(working_mode, WorkingMode::Compensate | WorkingMode::Discharge),
+
+            // HomeWizard only charges at full power or follows the consumption:
+            Self::HomeWizard => !matches!(working_mode, WorkingMode::Discharge),
+        }
+    }
+}
+
+/// Supported inverter backends.
+pub enum Inverter {
+    MiniQube(mini_qube::Client),
+    Victron(victron::Client),
+    Deye(deye::Client),
+    HomeWizard(homewizard::battery::Client),
+}
+
+impl Inverter {
+    pub async fn read_metrics(&self) -> Result<battery::Metrics> {
+        match self {
+            Self::MiniQube(client) => client.read_metrics().await,
+            Self::Victron(client) => client.read_metrics().await,
+            Self::Deye(client) => client.read_metrics().await,
+            Self::HomeWizard(client) => client.read_metrics().await,
+        }
+    }
+}
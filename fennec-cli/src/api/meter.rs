@@ -0,0 +1,38 @@
+use crate::{
+    api::{dsmr, eastron, homewizard, homewizard::EnergyMetrics, shelly},
+    prelude::*,
+};
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Kind {
+    /// HomeWizard P1 meter or energy socket, polled over its local HTTP API.
+    HomeWizard,
+
+    /// DSMR 4 or 5 smart meter, read from its P1 port exposed over TCP.
+    Dsmr,
+
+    /// Shelly Gen2+ energy meter with current clamps, polled over its local RPC API.
+    Shelly,
+
+    /// Eastron SDM energy meter, read over Modbus TCP.
+    Eastron,
+}
+
+/// Supported grid meter backends.
+pub enum Meter {
+    HomeWizard(homewizard::Client),
+    Dsmr(dsmr::Client),
+    Shelly(shelly::Client),
+    Eastron(eastron::Client),
+}
+
+impl Meter {
+    pub async fn get_measurement(&self) -> Result<EnergyMetrics> {
+        match self {
+            Self::HomeWizard(client) => client.get_measurement().await,
+            Self::Dsmr(client) => client.get_measurement().await,
+            Self::Shelly(client) => client.get_measurement().await,
+            Self::Eastron(client) => client.get_measurement().await,
+        }
+    }
+}
@@ -2,10 +2,9 @@
 //!
 //! [1]: https://fox-ess.uk/miniqube/
 
-mod metrics;
 pub mod schedule;
 
-use std::range::RangeInclusive;
+use std::{collections::HashMap, range::RangeInclusive, sync::Mutex};
 
 use fennec_modbus::{
     contrib::mini_qube::{
@@ -18,70 +17,74 @@ use fennec_modbus::{
         ReadTotalGridExportEnergy,
         ReadTotalGridImportEnergy,
         UNIT_ID,
-        schedule::{ReadSlot, Slot, WriteSlot},
+        schedule::{Block, BlockIndex, N_SLOTS_PER_BLOCK, ReadBlock, ReadSlot, Slot, WriteSlot},
     },
     protocol::{address, function::write_multiple},
 };
 
-pub use self::metrics::Metrics;
-use crate::{energy::Flow, prelude::*};
+use crate::{api::modbus, battery::Metrics, energy::Flow, prelude::*};
 
 /// FoxESS MQ2200 Modbus client.
 #[must_use]
-pub struct Client(fennec_modbus::tcp::tokio::Client<String>);
+pub struct Client {
+    inner: modbus::Client,
+
+    /// Slots confirmed by this client, used to detect changes made behind our back.
+    written_slots: Mutex<HashMap<u8, Slot>>,
+}
 
 impl Client {
-    pub fn new(address: String) -> Self {
-        Self(fennec_modbus::tcp::tokio::Client::new(address))
+    pub fn new(inner: modbus::Client) -> Self {
+        Self { inner, written_slots: Mutex::default() }
     }
 
     #[instrument(skip_all)]
     pub async fn read_metrics(&self) -> Result<Metrics> {
         let design_capacity = self
-            .0
+            .inner
             .call::<ReadDesignCapacity>(UNIT_ID, address::Const)
             .await
             .context("failed to read the design capacity")?
             .into();
         let state_of_health = self
-            .0
+            .inner
             .call::<ReadStateOfHealth>(UNIT_ID, address::Const)
             .await
             .context("failed to read the SoH")?
             .try_into()?;
         let state_of_charge = self
-            .0
+            .inner
             .call::<ReadStateOfCharge>(UNIT_ID, address::Const)
             .await
             .context("failed to read the SoC")?
             .try_into()?;
         let total_grid_export_energy = self
-            .0
+            .inner
             .call::<ReadTotalGridExportEnergy>(UNIT_ID, address::Const)
             .await
             .context("failed to read the total exported energy")?
             .into();
         let total_grid_import_energy = self
-            .0
+            .inner
             .call::<ReadTotalGridImportEnergy>(UNIT_ID, address::Const)
             .await
             .context("failed to read the total exported energy")?
             .into();
         let active_power = self
-            .0
+            .inner
             .call::<ReadTotalActivePower>(UNIT_ID, address::Const)
             .await
             .context("failed to read the active power")?
             .into();
         let eps_active_power = self
-            .0
+            .inner
             .call::<ReadEpsActivePower>(UNIT_ID, address::Const)
             .await
             .context("failed to read the EPS active power")?
             .into();
         // TODO: this wastes "minimum system SoC", introduce a custom type with just the two registers?
         let state_of_charge_settings = self
-            .0
+            .inner
             .call::<ReadStateOfChargeSettings>(UNIT_ID, address::Const)
             .await
             .context("failed to read the state-of-charge settings")?;
@@ -103,29 +106,172 @@ impl Client {
         })
     }
 
+    /// Write the schedule slots to the battery, skipping those which are already up-to-date.
+    ///
+    /// The slots must be ordered as they appear in the plan. Every affected schedule block is read
+    /// only once, and only the differing slots get written. The other slots are left intact
+    /// byte-for-byte, so that the battery does not reset their state.
+    ///
+    /// Returns the number of written slots.
+    #[instrument(skip_all, fields(n_slots = slots.len()))]
+    pub async fn write_schedule(&self, slots: &[(u8, Slot)]) -> Result<usize> {
+        let mut n_written = 0;
+        let mut current_block: Option<(u16, Block)> = None;
+        for &(index, slot) in slots {
+            let block_index = u16::from(index) / N_SLOTS_PER_BLOCK;
+            let block = match current_block {
+                Some((current_index, block)) if current_index == block_index => block,
+                _ => {
+                    let block = self
+                        .inner
+                        .call::<ReadBlock>(UNIT_ID, BlockIndex(block_index))
+                        .await
+                        .with_context(|| {
+                            format!("failed to read the schedule block #{block_index}")
+                        })?;
+                    current_block = Some((block_index, block));
+                    block
+                }
+            };
+            let current_slot = block[usize::from(u16::from(index) % N_SLOTS_PER_BLOCK)];
+            self.check_drift(index, current_slot);
+            if current_slot != slot {
+                self.write_schedule_slot(index, slot, current_slot).await?;
+                n_written += 1;
+            }
+            self.written_slots.lock().unwrap().insert(index, slot);
+        }
+        Ok(n_written)
+    }
+
+    /// Warn if the slot no longer matches the one we wrote earlier.
+    ///
+    /// That means that the schedule got altered outside Fennec, for example, from the FoxESS app,
+    /// or the battery silently dropped the slot.
+    fn check_drift(&self, index: u8, current_slot: Slot) {
+        if let Some(written_slot) = self.written_slots.lock().unwrap().get(&index)
+            && *written_slot != current_slot
+        {
+            warn!(
+                index,
+                written.start_time = %written_slot.start_time,
+                written.end_time = %written_slot.end_time,
+                written.working_mode = ?written_slot.working_mode,
+                actual.start_time = %current_slot.start_time,
+                actual.end_time = %current_slot.end_time,
+                actual.working_mode = ?current_slot.working_mode,
+                "schedule slot has drifted",
+            );
+        }
+    }
+
     /// Write the schedule slot to the battery and verify it.
     ///
     /// Note that MQ2200 does not support the "read/write multiple registers" operation,
-    /// so this function actually performs three steps non-atomically:
+    /// so this function actually performs two steps non-atomically:
     ///
-    /// 1. Read the current slot.
-    /// 2. If the current slot differs from the expected slot:
-    ///     1. Write the expected slot.
-    ///     2. Read the slot back and verify it matches the expected slot.
+    /// 1. Write the expected slot.
+    /// 2. Read the slot back and verify it matches the expected slot.
     #[instrument(skip_all, fields(index = index))]
-    pub async fn write_schedule_slot(&self, index: u8, slot: Slot) -> Result {
+    async fn write_schedule_slot(&self, index: u8, slot: Slot, current_slot: Slot) -> Result {
         let address = address::Stride::new(index.into());
-        let current_slot = self.0.call::<ReadSlot>(UNIT_ID, address).await?;
-        if current_slot != slot {
-            info!(
-                start_time = %slot.start_time,
-                end_time = %slot.end_time,
-                to = ?slot.working_mode,
-                from = ?current_slot.working_mode,
-            );
-            self.0.call::<WriteSlot>(UNIT_ID, write_multiple::Args::new(address, slot)).await?;
-            ensure!(self.0.call::<ReadSlot>(UNIT_ID, address).await? == slot);
+        info!(
+            start_time = %slot.start_time,
+            end_time = %slot.end_time,
+            to = ?slot.working_mode,
+            from = ?current_slot.working_mode,
+        );
+        self.inner.call::<WriteSlot>(UNIT_ID, write_multiple::Args::new(address, slot)).await?;
+        ensure!(self.inner.call::<ReadSlot>(UNIT_ID, address).await? == slot);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fennec_modbus::{contrib::mini_qube::schedule::START_ADDRESS, protocol::codec::Encode};
+
+    use super::*;
+    use crate::{
+        api::{modbus::Transport, simulator::Simulator},
+        battery::{PowerLimits, WorkingMode},
+        quantity::{Quantity, Zero, power::Watts, ratios::Percentage},
+    };
+
+    const UNIT: u8 = 1;
+
+    /// Slot size in registers.
+    const SLOT_WORDS: u16 = 10;
+
+    fn encode(slot: Slot) -> Vec<u16> {
+        let mut bytes = Vec::new();
+        slot.encode_to(&mut bytes);
+        bytes.chunks_exact(2).map(|word| u16::from_be_bytes([word[0], word[1]])).collect()
+    }
+
+    fn make_slot(index: u8, working_mode: WorkingMode) -> (u8, Slot) {
+        let power_limits = PowerLimits {
+            charging: Quantity(1200.0),
+            discharging: Quantity(800.0),
+            max_inverter_power: Quantity(1200.0),
+            min_charging: Watts::ZERO,
+            min_discharging: Watts::ZERO,
+        };
+        let allowed_soc = RangeInclusive { start: Quantity(10), last: Quantity(100) };
+        let planned_power = power_limits.max_effective_flow(Watts::ZERO);
+        let slot = schedule::make_slot(
+            index,
+            working_mode,
+            Percentage::FULL,
+            planned_power,
+            allowed_soc,
+            power_limits,
+        );
+        (index, slot)
+    }
+
+    #[tokio::test]
+    async fn read_metrics_ok() -> Result {
+        let simulator = Simulator::start().await?;
+        simulator.set(UNIT, 37624, &[95]);
+        simulator.set(UNIT, 37635, &[220]);
+        simulator.set(UNIT, 39134, &[0xFFFF, 0xFE0C]);
+        simulator.set(UNIT, 39424, &[40]);
+        simulator.set(UNIT, 46609, &[10, 100, 20]);
+        let client = Client::new(modbus::Client::new(simulator.address(), Transport::Tcp));
+
+        let metrics = client.read_metrics().await?;
+        assert_eq!(metrics.state_of_charge, Quantity(40));
+        assert_eq!(metrics.state_of_health, Quantity(95));
+        assert_eq!(metrics.active_power, Quantity(-500.0));
+        assert_eq!(metrics.allowed_soc.start, Quantity(20));
+        assert_eq!(metrics.allowed_soc.last, Quantity(100));
+
+        // The battery keeps charging:
+        simulator.set(UNIT, 39424, &[41]);
+        assert_eq!(client.read_metrics().await?.state_of_charge, Quantity(41));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_schedule_ok() -> Result {
+        let simulator = Simulator::start().await?;
+        for index in 0..12 {
+            let (_, slot) = make_slot(index, WorkingMode::Idle);
+            simulator.set(UNIT, START_ADDRESS + u16::from(index) * SLOT_WORDS, &encode(slot));
         }
+        let client = Client::new(modbus::Client::new(simulator.address(), Transport::Tcp));
+        let slots = [make_slot(0, WorkingMode::Charge), make_slot(1, WorkingMode::Idle)];
+
+        assert_eq!(client.write_schedule(&slots).await?, 1);
+        assert_eq!(simulator.get(UNIT, START_ADDRESS, SLOT_WORDS), encode(slots[0].1));
+
+        // Nothing to do on the next tick:
+        assert_eq!(client.write_schedule(&slots).await?, 0);
+
+        // Until the slot gets altered behind our back:
+        simulator.set(UNIT, START_ADDRESS, &encode(make_slot(0, WorkingMode::SelfUse).1));
+        assert_eq!(client.write_schedule(&slots).await?, 1);
         Ok(())
     }
 }
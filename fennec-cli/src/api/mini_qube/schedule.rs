@@ -1,22 +1,55 @@
 use std::range::RangeInclusive;
 
-use chrono::{DateTime, Local, Timelike};
+use chrono::{DateTime, DurationRound, Local, TimeDelta, Timelike};
 use fennec_modbus::contrib::{
     mini_qube::{schedule, schedule::NaiveTime},
     types,
 };
+use itertools::Itertools;
 
 use crate::{
     battery,
+    energy,
     ops::interval::Interval,
     quantity::{Zero, power::Watts, ratios::Percentage},
 };
 
-/// Get the schedule slot index corresponding to the interval.
+/// Duration of a single schedule slot.
+const SLOT_DURATION: TimeDelta = TimeDelta::minutes(15);
+
+/// Get the schedule slot index corresponding to the timestamp.
 #[must_use]
-pub fn index_of(interval: Interval<DateTime<Local>>) -> u8 {
-    let start = interval.start();
-    (start.hour() * 4 + start.minute() / 15).try_into().unwrap()
+fn index_at(timestamp: DateTime<Local>) -> u8 {
+    (timestamp.hour() * 4 + timestamp.minute() / 15).try_into().unwrap()
+}
+
+/// Get the schedule slot indices covered by the interval, in chronological order.
+///
+/// Quarterly intervals map onto a single slot, hourly intervals span up to four slots.
+pub fn indices_of(interval: Interval<DateTime<Local>>) -> impl Iterator<Item = u8> {
+    let first_slot_start = interval.start().duration_trunc(SLOT_DURATION).unwrap();
+    std::iter::successors(Some(first_slot_start), |start| Some(*start + SLOT_DURATION))
+        .take_while(move |start| *start < interval.end())
+        .map(index_at)
+}
+
+/// Get the schedule slot indices covered by the consecutive intervals, paired with their values.
+///
+/// The slots follow the wall clock, so the hour which repeats when the DST ends maps onto
+/// the same slots twice. Only the first occurrence is kept – the repeated one gets written
+/// once the first one has passed. The hour which is skipped when the DST starts has no slots.
+pub fn indices_over<T: Copy>(
+    intervals: impl IntoIterator<Item = (Interval<DateTime<Local>>, T)>,
+) -> impl Iterator<Item = (u8, T)> {
+    intervals
+        .into_iter()
+        .flat_map(|(interval, value)| indices_of(interval).map(move |index| (index, value)))
+        .unique_by(|(index, _)| *index)
+}
+
+/// Local time the schedule slot starts at.
+pub fn slot_start_time(index: u8) -> chrono::NaiveTime {
+    chrono::NaiveTime::from_hms_opt(u32::from(index / 4), u32::from(index % 4) * 15, 0).unwrap()
 }
 
 pub fn slot_interval(index: u8) -> (NaiveTime, NaiveTime) {
@@ -31,9 +64,14 @@ pub fn slot_interval(index: u8) -> (NaiveTime, NaiveTime) {
 }
 
 /// Make the battery schedule entry according the working mode and schedule limits.
+///
+/// The forced power is capped at the planned mean battery power, so that the slot delivers
+/// the same energy as the plan, when the battery is expected to get full or empty mid-interval.
 pub fn make_slot(
     slot_index: u8,
     working_mode: battery::WorkingMode,
+    power_level: Percentage,
+    planned_power: energy::Flow<Watts>,
     allowed_soc: RangeInclusive<Percentage>,
     power_limits: battery::PowerLimits,
 ) -> schedule::Slot {
@@ -46,13 +84,17 @@ pub fn make_slot(
             (schedule::WorkingMode::BackUp, allowed_soc.last, power_limits.charging)
         }
         battery::WorkingMode::Charge => {
-            (schedule::WorkingMode::ForceCharge, allowed_soc.last, power_limits.charging)
+            let power = (power_limits.charging * power_level.to_ratio())
+                .min(planned_power.import.max(power_limits.min_charging));
+            (schedule::WorkingMode::ForceCharge, allowed_soc.last, power)
         }
         battery::WorkingMode::SelfUse => {
             (schedule::WorkingMode::SelfUse, allowed_soc.start, power_limits.discharging)
         }
         battery::WorkingMode::Discharge => {
-            (schedule::WorkingMode::ForceDischarge, allowed_soc.start, power_limits.discharging)
+            let power = (power_limits.discharging * power_level.to_ratio())
+                .min(planned_power.export.max(power_limits.min_discharging));
+            (schedule::WorkingMode::ForceDischarge, allowed_soc.start, power)
         }
         battery::WorkingMode::Compensate => {
             (schedule::WorkingMode::FeedInPriority, allowed_soc.start, power_limits.discharging)
@@ -74,3 +116,92 @@ pub fn make_slot(
         reserved_3: 0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn make_slot_curtailed_charge() {
+        let power_limits = battery::PowerLimits {
+            charging: Watts::new(1200.0),
+            discharging: Watts::new(800.0),
+            max_inverter_power: Watts::new(1200.0),
+            min_charging: Watts::new(100.0),
+            min_discharging: Watts::ZERO,
+        };
+        let allowed_soc = RangeInclusive { start: Percentage::new(10), last: Percentage::FULL };
+        let slot_for = |planned_import| {
+            make_slot(
+                0,
+                battery::WorkingMode::Charge,
+                Percentage::FULL,
+                energy::Flow { import: Watts::new(planned_import), export: Watts::ZERO },
+                allowed_soc,
+                power_limits,
+            )
+        };
+        assert_eq!(slot_for(1200.0).power, types::Watts(1200));
+        assert_eq!(slot_for(600.0).power, types::Watts(600));
+        assert_eq!(slot_for(50.0).power, types::Watts(100));
+    }
+
+    #[test]
+    fn indices_of_hourly_interval() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let interval = Interval::new(start, start + TimeDelta::hours(1));
+        assert_eq!(indices_of(interval).collect_vec(), [52, 53, 54, 55]);
+    }
+
+    #[test]
+    fn indices_of_clamped_interval() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let interval = Interval::new(start, start + TimeDelta::hours(1))
+            .clamp_start_to(start + TimeDelta::minutes(20));
+        assert_eq!(indices_of(interval).collect_vec(), [53, 54, 55]);
+    }
+
+    /// Hourly prices followed by quarterly ones must map onto contiguous slots.
+    #[test]
+    fn indices_of_mixed_resolution() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let hourly = Interval::new(start, start + TimeDelta::hours(1));
+        let quarterly = Interval::new(hourly.end(), hourly.end() + SLOT_DURATION);
+        let indices = [hourly, quarterly].into_iter().flat_map(indices_of).collect_vec();
+        assert_eq!(indices, [52, 53, 54, 55, 56]);
+    }
+
+    #[test]
+    fn indices_of_last_quarter() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 23, 45, 0).unwrap();
+        let interval = Interval::new(start, start + SLOT_DURATION);
+        assert_eq!(indices_of(interval).collect_vec(), [95]);
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn indices_over_dst_start() {
+        let start = Local.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap();
+        let intervals = (0..2).map(|hour| {
+            let start = start + TimeDelta::hours(hour);
+            (Interval::new(start, start + TimeDelta::hours(1)), hour)
+        });
+        let indices = indices_over(intervals).collect_vec();
+        assert_eq!(indices, [(4, 0), (5, 0), (6, 0), (7, 0), (12, 1), (13, 1), (14, 1), (15, 1)]);
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn indices_over_dst_end() {
+        // The first 02:00, in summer time:
+        let start = Utc.with_ymd_and_hms(2026, 10, 25, 0, 0, 0).unwrap().with_timezone(&Local);
+        let intervals = (0..3).map(|hour| {
+            let start = start + TimeDelta::hours(hour);
+            (Interval::new(start, start + TimeDelta::hours(1)), hour)
+        });
+        let indices = indices_over(intervals).collect_vec();
+        assert_eq!(indices, [(8, 0), (9, 0), (10, 0), (11, 0), (12, 2), (13, 2), (14, 2), (15, 2)]);
+    }
+}
@@ -0,0 +1,45 @@
+//! Modbus client over the configurable transport.
+
+use fennec_modbus::{
+    protocol::{Function, function::IntoValue},
+    rtu,
+    tcp,
+    tcp::{UnitId, tokio::Error},
+};
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Transport {
+    /// Modbus TCP, for the devices with a network interface.
+    Tcp,
+
+    /// Modbus RTU tunnelled over TCP, for the RS485 devices behind a transparent gateway or `ser2net`.
+    ///
+    /// The baud rate and parity are configured on the gateway.
+    RtuOverTcp,
+}
+
+#[must_use]
+pub enum Client {
+    Tcp(tcp::tokio::Client<String>),
+    RtuOverTcp(rtu::tokio::Client<String>),
+}
+
+impl Client {
+    pub fn new(address: String, transport: Transport) -> Self {
+        match transport {
+            Transport::Tcp => Self::Tcp(tcp::tokio::Client::new(address)),
+            Transport::RtuOverTcp => Self::RtuOverTcp(rtu::tokio::Client::new(address)),
+        }
+    }
+
+    pub async fn call<F: Function>(
+        &self,
+        unit_id: UnitId,
+        args: impl Into<F::Args>,
+    ) -> Result<<F::Output as IntoValue>::Value, Error> {
+        match self {
+            Self::Tcp(client) => client.call::<F>(unit_id, args).await,
+            Self::RtuOverTcp(client) => client.call::<F>(unit_id, args).await,
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Best-effort notifications to [Telegram][1], [ntfy][2], [Slack][3], [Discord][4],
+//! and generic webhooks.
+//!
+//! [1]: https://core.telegram.org/bots/api#sendmessage
+//! [2]: https://docs.ntfy.sh/publish/
+//! [3]: https://api.slack.com/messaging/webhooks
+//! [4]: https://discord.com/developers/docs/resources/webhook#execute-webhook
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::prelude::*;
+
+#[derive(clap::Args)]
+#[group(id = "notify")]
+pub struct Args {
+    /// ntfy topic URL to publish the notifications to, for example, `https://ntfy.sh/my-fennec`.
+    #[clap(long = "ntfy-url", env = "NTFY_URL")]
+    pub ntfy_url: Option<reqwest::Url>,
+
+    /// Telegram bot token to send the notifications with, requires the chat ID.
+    #[clap(long = "telegram-bot-token", env = "TELEGRAM_BOT_TOKEN", requires = "telegram_chat_id")]
+    pub telegram_bot_token: Option<String>,
+
+    /// Telegram chat ID to send the notifications to.
+    #[clap(long = "telegram-chat-id", env = "TELEGRAM_CHAT_ID", requires = "telegram_bot_token")]
+    pub telegram_chat_id: Option<String>,
+
+    /// URL to post the notifications to as JSON: `{"title": "…", "message": "…"}`.
+    #[clap(long = "notification-webhook-url", env = "NOTIFICATION_WEBHOOK_URL")]
+    pub webhook_url: Option<reqwest::Url>,
+
+    /// Slack incoming webhook URL to post the notifications to.
+    #[clap(long = "slack-webhook-url", env = "SLACK_WEBHOOK_URL")]
+    pub slack_webhook_url: Option<reqwest::Url>,
+
+    /// Discord webhook URL to post the notifications to.
+    #[clap(long = "discord-webhook-url", env = "DISCORD_WEBHOOK_URL")]
+    pub discord_webhook_url: Option<reqwest::Url>,
+
+    /// Notify about failing iterations only once they have been failing for this long.
+    #[clap(
+        long = "notify-failures-after",
+        env = "NOTIFY_FAILURES_AFTER",
+        default_value = "5m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub notify_failures_after: Duration,
+
+    /// Notify about the grid meter or battery measurements which have not changed for this long,
+    /// as when the meter is offline but its gateway keeps serving the last known values.
+    #[clap(
+        long = "notify-stale-after",
+        env = "NOTIFY_STALE_AFTER",
+        default_value = "15m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub stale_after: Duration,
+
+    /// Notify about the tomorrow's prices as soon as they arrive, along with the cheapest and
+    /// the most expensive windows of this duration, for example, `3h` for the dishwasher.
+    #[clap(
+        long = "notify-rates-window",
+        env = "NOTIFY_RATES_WINDOW",
+        value_parser = humantime::parse_duration,
+    )]
+    pub rates_window: Option<Duration>,
+}
+
+#[must_use]
+#[derive(Serialize)]
+pub struct Notification {
+    pub title: String,
+    pub message: String,
+}
+
+enum Provider {
+    Ntfy(reqwest::Url),
+    Telegram { bot_token: String, chat_id: String },
+    Webhook(reqwest::Url),
+    Slack(reqwest::Url),
+    Discord(reqwest::Url),
+}
+
+/// Fans the notifications out to all the configured providers.
+pub struct Client {
+    inner: reqwest::Client,
+    providers: Vec<Provider>,
+
+    /// See [`Args::notify_failures_after`].
+    pub notify_failures_after: Duration,
+
+    /// See [`Args::stale_after`].
+    pub stale_after: Duration,
+
+    /// See [`Args::rates_window`].
+    pub rates_window: Option<Duration>,
+}
+
+impl Client {
+    #[instrument(skip_all)]
+    pub fn new(args: Args, builder: reqwest::ClientBuilder) -> Result<Self> {
+        let mut providers = Vec::new();
+        if let Some(url) = args.ntfy_url {
+            providers.push(Provider::Ntfy(url));
+        }
+        if let Some((bot_token, chat_id)) = args.telegram_bot_token.zip(args.telegram_chat_id) {
+            providers.push(Provider::Telegram { bot_token, chat_id });
+        }
+        if let Some(url) = args.webhook_url {
+            providers.push(Provider::Webhook(url));
+        }
+        if let Some(url) = args.slack_webhook_url {
+            providers.push(Provider::Slack(url));
+        }
+        if let Some(url) = args.discord_webhook_url {
+            providers.push(Provider::Discord(url));
+        }
+        Ok(Self {
+            inner: builder.timeout(Duration::from_secs(5)).build()?,
+            providers,
+            notify_failures_after: args.notify_failures_after,
+            stale_after: args.stale_after,
+            rates_window: args.rates_window,
+        })
+    }
+
+    #[instrument(skip_all, fields(title = %notification.title))]
+    pub async fn send(&self, notification: &Notification) {
+        for provider in &self.providers {
+            if let Err(error) = self.inner_send(provider, notification).await {
+                warn!("failed to send the notification: {error:#}");
+            }
+        }
+    }
+
+    async fn inner_send(&self, provider: &Provider, notification: &Notification) -> Result {
+        let request = match provider {
+            Provider::Ntfy(url) => self
+                .inner
+                .post(url.clone())
+                .header("Title", &notification.title)
+                .body(notification.message.clone()),
+            Provider::Telegram { bot_token, chat_id } => self
+                .inner
+                .post(format!("https://api.telegram.org/bot{bot_token}/sendMessage"))
+                .json(&serde_json::json!({
+                    "chat_id": chat_id,
+                    "text": format!("{}\n{}", notification.title, notification.message),
+                })),
+            Provider::Webhook(url) => self.inner.post(url.clone()).json(notification),
+            Provider::Slack(url) => self.inner.post(url.clone()).json(&serde_json::json!({
+                "text": format!("*{}*\n{}", notification.title, notification.message),
+            })),
+            Provider::Discord(url) => self.inner.post(url.clone()).json(&serde_json::json!({
+                "content": format!("**{}**\n{}", notification.title, notification.message),
+            })),
+        };
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
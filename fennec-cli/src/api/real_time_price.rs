@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::{
+    energy::Flow,
+    prelude::*,
+    quantity::{Quantity, price::KilowattHourPrice},
+};
+
+/// Near-real-time price feed, for example, of the imbalance prices.
+///
+/// The URL must respond with the current supplier prices per kilowatt-hour:
+/// `{"import": 0.31, "export": 0.12}`.
+///
+/// The billing and transport costs get added on top, as to the day-ahead prices.
+pub struct Client(Option<(reqwest::Url, reqwest::Client)>);
+
+impl Client {
+    #[instrument(skip_all)]
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
+        let inner = match url {
+            Some(url) => Some((url, builder.timeout(Duration::from_secs(5)).build()?)),
+            None => None,
+        };
+        Ok(Self(inner))
+    }
+
+    /// Fetch the current prices, returns [`None`] if the feed is not configured.
+    #[instrument(skip_all)]
+    pub async fn get_price(&self) -> Result<Option<Flow<KilowattHourPrice>>> {
+        let Some((url, client)) = &self.0 else {
+            return Ok(None);
+        };
+        let response: Response =
+            client.get(url.clone()).send().await?.error_for_status()?.json().await?;
+        Ok(Some(Flow { import: Quantity(response.import), export: Quantity(response.export) }))
+    }
+}
+
+#[derive(Deserialize)]
+struct Response {
+    import: f64,
+    export: f64,
+}
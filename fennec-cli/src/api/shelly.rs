@@ -0,0 +1,195 @@
+//! Shelly Gen2+ energy meter [RPC][1] client.
+//!
+//! [1]: https://shelly-api-docs.shelly.cloud/gen2/ComponentsAndServices/EM
+
+use std::time::Duration;
+
+use serde::{Deserialize, de::DeserializeOwned};
+use tokio::try_join;
+
+use crate::{
+    api::homewizard::EnergyMetrics,
+    prelude::*,
+    quantity::{Quantity, energy::WattHours},
+};
+
+#[derive(clap::Args)]
+#[group(id = "shelly")]
+pub struct Args {
+    /// Shelly base URL, for example, `http://192.168.1.42`.
+    #[clap(long = "shelly-url", env = "SHELLY_URL")]
+    pub url: Option<reqwest::Url>,
+
+    /// Shelly meter profile.
+    #[clap(long = "shelly-profile", env = "SHELLY_PROFILE", default_value = "triphase")]
+    pub profile: Profile,
+}
+
+#[derive(Copy, Clone, clap::ValueEnum)]
+pub enum Profile {
+    /// Three-phase meter, such as Shelly Pro 3EM or 3EM-63: the `EM` and `EMData` components.
+    Triphase,
+
+    /// Single-phase meter, such as Shelly Pro EM or EM Gen3: the `EM1` and `EM1Data` components.
+    Monophase,
+}
+
+#[must_use]
+pub struct Client {
+    inner: reqwest::Client,
+    url: reqwest::Url,
+    profile: Profile,
+}
+
+impl Client {
+    pub fn new(
+        url: reqwest::Url,
+        profile: Profile,
+        builder: reqwest::ClientBuilder,
+    ) -> Result<Self> {
+        Ok(Self { inner: builder.timeout(Duration::from_secs(10)).build()?, url, profile })
+    }
+
+    #[instrument(skip_all, fields(host = self.url.host_str()))]
+    pub async fn get_measurement(&self) -> Result<EnergyMetrics> {
+        let measurement = match self.profile {
+            Profile::Triphase => {
+                let (status, data) = try_join!(
+                    self.call::<TriphaseStatus>("EM.GetStatus"),
+                    self.call::<TriphaseData>("EMData.GetStatus"),
+                )?;
+                EnergyMetrics {
+                    active_power: Quantity(status.total_act_power),
+                    import: WattHours::new(data.total_act).rescale(),
+                    export: WattHours::new(data.total_act_ret).rescale(),
+                }
+            }
+            Profile::Monophase => {
+                let (status, data) = try_join!(
+                    self.call::<MonophaseStatus>("EM1.GetStatus"),
+                    self.call::<MonophaseData>("EM1Data.GetStatus"),
+                )?;
+                EnergyMetrics {
+                    active_power: Quantity(status.act_power),
+                    import: WattHours::new(data.total_act_energy).rescale(),
+                    export: WattHours::new(data.total_act_ret_energy).rescale(),
+                }
+            }
+        };
+        debug!(import = ?measurement.import, export = ?measurement.export);
+        Ok(measurement)
+    }
+
+    /// Call the RPC method on the first component instance.
+    async fn call<R: DeserializeOwned>(&self, method: &str) -> Result<R> {
+        let mut url = self.url.join(&format!("rpc/{method}"))?;
+        url.query_pairs_mut().append_pair("id", "0");
+        self.inner
+            .get(url)
+            .send()
+            .await
+            .with_context(|| format!("failed to call `{method}` on `{}`", self.url))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to deserialize the `{method}` response"))
+    }
+}
+
+/// Positive active power is consumption.
+#[derive(Deserialize)]
+struct TriphaseStatus {
+    total_act_power: f64,
+}
+
+/// Totals in watt-hours.
+#[derive(Deserialize)]
+struct TriphaseData {
+    total_act: f64,
+    total_act_ret: f64,
+}
+
+#[derive(Deserialize)]
+struct MonophaseStatus {
+    act_power: f64,
+}
+
+#[derive(Deserialize)]
+struct MonophaseData {
+    total_act_energy: f64,
+    total_act_ret_energy: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triphase_ok() -> Result {
+        // language=json
+        let status = r#"{
+            "id": 0,
+            "a_current": 4.029,
+            "a_voltage": 236.1,
+            "a_act_power": 951.2,
+            "a_aprt_power": 951.9,
+            "a_pf": 1,
+            "b_current": 4.027,
+            "b_voltage": 236.201,
+            "b_act_power": -951.1,
+            "b_aprt_power": 951.8,
+            "b_pf": 1,
+            "c_current": 3.03,
+            "c_voltage": 236.402,
+            "c_act_power": 715.4,
+            "c_aprt_power": 716.2,
+            "c_pf": 1,
+            "n_current": 11.029,
+            "total_current": 11.083,
+            "total_act_power": 715.5,
+            "total_aprt_power": 2619.9
+        }"#;
+        assert!(
+            (serde_json::from_str::<TriphaseStatus>(status)?.total_act_power - 715.5).abs() < 1e-9
+        );
+
+        // language=json
+        let data = r#"{
+            "id": 0,
+            "a_total_act_energy": 1520.67,
+            "a_total_act_ret_energy": 0,
+            "b_total_act_energy": 0,
+            "b_total_act_ret_energy": 1519.97,
+            "c_total_act_energy": 1137.36,
+            "c_total_act_ret_energy": 0,
+            "total_act": 2658.03,
+            "total_act_ret": 1519.97
+        }"#;
+        let data = serde_json::from_str::<TriphaseData>(data)?;
+        assert!((data.total_act - 2658.03).abs() < 1e-9);
+        assert!((data.total_act_ret - 1519.97).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn monophase_ok() -> Result {
+        // language=json
+        let status = r#"{
+            "id": 0,
+            "current": 2.981,
+            "voltage": 235.6,
+            "act_power": -702.2,
+            "aprt_power": 703.4,
+            "pf": 1,
+            "freq": 50
+        }"#;
+        assert!((serde_json::from_str::<MonophaseStatus>(status)?.act_power + 702.2).abs() < 1e-9);
+
+        // language=json
+        let data = r#"{"id": 0, "total_act_energy": 3415.12, "total_act_ret_energy": 987.65}"#;
+        let data = serde_json::from_str::<MonophaseData>(data)?;
+        assert!((data.total_act_energy - 3415.12).abs() < 1e-9);
+        assert!((data.total_act_ret_energy - 987.65).abs() < 1e-9);
+        Ok(())
+    }
+}
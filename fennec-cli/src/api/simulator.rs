@@ -0,0 +1,123 @@
+//! In-process Modbus TCP simulator, so that the inverter clients can be tested without hardware.
+//!
+//! It emulates a flat holding register map per unit ID. Unset registers read as zero.
+//! Tests script the battery evolution by poking the registers between the calls.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+use crate::prelude::*;
+
+type Registers = Arc<Mutex<HashMap<(u8, u16), u16>>>;
+
+pub struct Simulator {
+    address: String,
+    registers: Registers,
+}
+
+impl Simulator {
+    /// Listen on a random local port and serve the connections in background.
+    pub async fn start() -> Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?.to_string();
+        let registers = Registers::default();
+        tokio::spawn({
+            let registers = registers.clone();
+            async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(serve(stream, registers.clone()));
+                }
+            }
+        });
+        Ok(Self { address, registers })
+    }
+
+    pub fn address(&self) -> String {
+        self.address.clone()
+    }
+
+    /// Set the consecutive registers starting at the address.
+    pub fn set(&self, unit_id: u8, address: u16, words: &[u16]) {
+        let mut registers = self.registers.lock().unwrap();
+        for (address, word) in (address..).zip(words) {
+            registers.insert((unit_id, address), *word);
+        }
+    }
+
+    /// Get the consecutive registers starting at the address.
+    pub fn get(&self, unit_id: u8, address: u16, n_words: u16) -> Vec<u16> {
+        let registers = self.registers.lock().unwrap();
+        (address..address + n_words)
+            .map(|address| registers.get(&(unit_id, address)).copied().unwrap_or_default())
+            .collect()
+    }
+}
+
+/// Serve a single client connection until it gets closed.
+async fn serve(mut stream: TcpStream, registers: Registers) -> Result {
+    loop {
+        let mut header = [0; 7];
+        if stream.read_exact(&mut header).await.is_err() {
+            return Ok(());
+        }
+        let unit_id = header[6];
+        let mut request = vec![0; usize::from(u16::from_be_bytes([header[4], header[5]])) - 1];
+        stream.read_exact(&mut request).await?;
+
+        let response = handle(unit_id, &request, &registers);
+        let mut frame = Vec::with_capacity(7 + response.len());
+        frame.extend_from_slice(&header[..4]);
+        frame.extend_from_slice(&u16::try_from(response.len() + 1)?.to_be_bytes());
+        frame.push(unit_id);
+        frame.extend_from_slice(&response);
+        stream.write_all(&frame).await?;
+    }
+}
+
+/// Handle the request PDU and produce the response PDU.
+fn handle(unit_id: u8, request: &[u8], registers: &Registers) -> Vec<u8> {
+    const ILLEGAL_FUNCTION: u8 = 0x01;
+
+    let word_at = |offset: usize| u16::from_be_bytes([request[offset], request[offset + 1]]);
+    let read = |address: u16, n_words: u16| {
+        let mut response = vec![request[0], u8::try_from(n_words * 2).unwrap()];
+        let registers = registers.lock().unwrap();
+        response.extend((address..address + n_words).flat_map(|address| {
+            registers.get(&(unit_id, address)).copied().unwrap_or_default().to_be_bytes()
+        }));
+        drop(registers);
+        response
+    };
+    let write = |address: u16, data: &[u8]| {
+        let mut registers = registers.lock().unwrap();
+        for (address, word) in (address..).zip(data.chunks_exact(2)) {
+            registers.insert((unit_id, address), u16::from_be_bytes([word[0], word[1]]));
+        }
+    };
+
+    match request[0] {
+        // Read holding registers, read input registers:
+        3 | 4 => read(word_at(1), word_at(3)),
+
+        // Write multiple registers:
+        16 => {
+            write(word_at(1), &request[6..]);
+            request[..5].to_vec()
+        }
+
+        // Read/write multiple registers, the write goes first:
+        23 => {
+            write(word_at(5), &request[10..]);
+            read(word_at(1), word_at(3))
+        }
+
+        code => vec![code | 0x80, ILLEGAL_FUNCTION],
+    }
+}
@@ -0,0 +1,117 @@
+//! Victron Energy [GX][1] Modbus client.
+//!
+//! Unlike MiniQube, Victron does not have a built-in schedule. Instead, Fennec steers the ESS power
+//! setpoint on every tick, following the current plan step.
+//!
+//! [1]: https://www.victronenergy.com/live/ccgx:modbustcp_faq
+
+use std::range::RangeInclusive;
+
+use fennec_modbus::{
+    contrib::{
+        types,
+        victron::{
+            ReadBatteryPower,
+            ReadMinimumStateOfCharge,
+            ReadStateOfCharge,
+            ReadTotalGridExportEnergy,
+            ReadTotalGridImportEnergy,
+            SYSTEM_UNIT_ID,
+            WriteEssPowerSetpoint,
+        },
+    },
+    protocol::{address, function::write_multiple},
+    tcp::UnitId,
+};
+
+use crate::{
+    api::modbus,
+    battery::Metrics,
+    energy::Flow,
+    prelude::*,
+    quantity::{Zero, energy::DecawattHours, power::Watts, ratios::Percentage},
+};
+
+#[derive(clap::Args)]
+#[group(id = "victron")]
+pub struct Args {
+    /// Victron VE.Bus inverter/charger Modbus unit ID.
+    #[clap(long = "victron-vebus-unit-id", env = "VICTRON_VEBUS_UNIT_ID", default_value = "227")]
+    pub vebus_unit_id: u8,
+}
+
+/// Victron GX Modbus client.
+#[must_use]
+pub struct Client {
+    inner: modbus::Client,
+    vebus_unit_id: UnitId,
+    design_capacity: DecawattHours,
+}
+
+impl Client {
+    pub const fn new(inner: modbus::Client, args: &Args, design_capacity: DecawattHours) -> Self {
+        Self { inner, vebus_unit_id: UnitId::Significant(args.vebus_unit_id), design_capacity }
+    }
+
+    #[instrument(skip_all)]
+    pub async fn read_metrics(&self) -> Result<Metrics> {
+        let state_of_charge = self
+            .inner
+            .call::<ReadStateOfCharge>(SYSTEM_UNIT_ID, address::Const)
+            .await
+            .context("failed to read the SoC")?
+            .try_into()?;
+        let battery_power = self
+            .inner
+            .call::<ReadBatteryPower>(SYSTEM_UNIT_ID, address::Const)
+            .await
+            .context("failed to read the battery power")?;
+        let min_state_of_charge = self
+            .inner
+            .call::<ReadMinimumStateOfCharge>(SYSTEM_UNIT_ID, address::Const)
+            .await
+            .context("failed to read the minimum SoC")?
+            .try_into()?;
+        let total_grid_import_energy = self
+            .inner
+            .call::<ReadTotalGridImportEnergy>(self.vebus_unit_id, address::Const)
+            .await
+            .context("failed to read the total imported energy")?
+            .into();
+        let total_grid_export_energy = self
+            .inner
+            .call::<ReadTotalGridExportEnergy>(self.vebus_unit_id, address::Const)
+            .await
+            .context("failed to read the total exported energy")?
+            .into();
+
+        Ok(Metrics {
+            state_of_charge,
+            state_of_health: Percentage::FULL, // not reported by GX
+            design_capacity: self.design_capacity,
+            total_grid_flow: Flow {
+                import: total_grid_import_energy,
+                export: total_grid_export_energy,
+            },
+            allowed_soc: RangeInclusive { start: min_state_of_charge, last: Percentage::FULL },
+            active_power: -Watts::from(battery_power),
+            eps_active_power: Watts::ZERO,
+        })
+    }
+
+    /// Write the ESS grid power setpoint: positive means importing, negative means feeding in.
+    ///
+    /// The inverter then charges or discharges the battery to keep the grid power at the setpoint.
+    #[instrument(skip_all, fields(setpoint = ?setpoint))]
+    pub async fn write_setpoint(&self, setpoint: Watts) -> Result {
+        #[expect(clippy::cast_possible_truncation)]
+        let setpoint = types::Watts(setpoint.0.round() as i16);
+        self.inner
+            .call::<WriteEssPowerSetpoint>(
+                self.vebus_unit_id,
+                write_multiple::Args::new(address::Const, setpoint),
+            )
+            .await?;
+        Ok(())
+    }
+}
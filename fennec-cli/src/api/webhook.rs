@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::prelude::*;
+
+/// Best-effort JSON webhook.
+pub struct Client(Option<(reqwest::Url, reqwest::Client)>);
+
+impl Client {
+    #[instrument(skip_all)]
+    pub fn new(url: Option<reqwest::Url>, builder: reqwest::ClientBuilder) -> Result<Self> {
+        let inner = match url {
+            Some(url) => Some((url, builder.timeout(Duration::from_secs(5)).build()?)),
+            None => None,
+        };
+        Ok(Self(inner))
+    }
+
+    #[instrument(skip_all)]
+    pub async fn post<T: Serialize>(&self, payload: &T) {
+        if let Some((url, client)) = &self.0
+            && let Err(error) = Self::inner_post(url, client, payload).await
+        {
+            warn!("failed to call the webhook: {error:#}");
+        }
+    }
+
+    async fn inner_post<T: Serialize>(
+        url: &reqwest::Url,
+        client: &reqwest::Client,
+        payload: &T,
+    ) -> Result {
+        client.post(url.clone()).json(payload).send().await?.error_for_status()?;
+        Ok(())
+    }
+}
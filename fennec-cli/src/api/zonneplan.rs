@@ -0,0 +1,414 @@
+//! [Zonneplan][1] app API client.
+//!
+//! There is no developer portal, so the client follows the app, as the [Home Assistant
+//! integration][2] does: the login link gets sent to the account e-mail, and once the link
+//! is opened, the login request turns into the OAuth token. The one-off login persists
+//! the token in the file, and the engine then only refreshes it as needed.
+//!
+//! [1]: https://www.zonneplan.nl
+//! [2]: https://github.com/fsaris/home-assistant-zonneplan-one
+
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use musli::{Decode, Encode, wire};
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use tokio::{fs::OpenOptions, io::AsyncWriteExt, sync::Mutex, time::sleep};
+
+use crate::{
+    Schedule,
+    energy::Flow,
+    ops::interval::Interval,
+    prelude::*,
+    quantity::{Quantity, price::KilowattHourPrice},
+};
+
+#[derive(clap::Args)]
+#[group(id = "zonneplan")]
+pub struct Args {
+    /// Zonneplan account e-mail, to which `--zonneplan-login` sends the login link.
+    #[clap(long = "zonneplan-email", env = "ZONNEPLAN_EMAIL")]
+    pub email: Option<String>,
+
+    /// File to keep the Zonneplan token in, only readable by the owner.
+    ///
+    /// A relative path gets resolved against the working directory at the start.
+    #[clap(
+        long = "zonneplan-token-path",
+        env = "ZONNEPLAN_TOKEN_PATH",
+        value_parser = parse_absolute_path,
+    )]
+    pub token_path: Option<PathBuf>,
+}
+
+fn parse_absolute_path(value: &str) -> Result<PathBuf> {
+    Ok(std::path::absolute(value)?)
+}
+
+/// Zonneplan API client.
+///
+/// It is meant to be created once, so that the underlying connection pool gets reused.
+pub struct Api {
+    client: reqwest::Client,
+    email: Option<String>,
+    token_path: Option<PathBuf>,
+
+    /// Lazily loaded from the file.
+    token: Mutex<Option<Token>>,
+}
+
+impl Api {
+    const BASE_URL: &str = "https://app-api.zonneplan.nl";
+
+    /// The API refuses the requests without the app headers.
+    const APP_VERSION: &str = "4.16.0";
+
+    /// How long to wait for the login link to be opened.
+    const LOGIN_TIMEOUT: Duration = Duration::from_mins(10);
+
+    const LOGIN_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Prices are in 10⁻⁷ €/kWh, including the surcharge and taxes.
+    const PRICE_SCALE: f64 = 1e7;
+
+    pub fn new(args: Args, builder: reqwest::ClientBuilder) -> Result<Self> {
+        let client = builder
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(15))
+            .build()?;
+        Ok(Self { client, email: args.email, token_path: args.token_path, token: Mutex::new(None) })
+    }
+
+    /// Send the login link, wait for it to be opened, and store the token.
+    #[instrument(skip_all)]
+    pub async fn log_in(&self) -> Result {
+        let token = self.request_login().await?;
+        token.write_to_file(self.token_path()?).await?;
+        *self.token.lock().await = Some(token);
+        Ok(())
+    }
+
+    /// Make sure that the stored token is still valid, refreshing it if needed.
+    pub async fn ensure_logged_in(&self) -> Result {
+        self.access_token().await.map(drop)
+    }
+
+    /// Hourly all-in prices of the first electricity connection.
+    ///
+    /// The export price equals the import price, as long as the net metering is in place.
+    #[instrument(skip_all, fields(on = ?on))]
+    pub async fn get_prices(&self, on: NaiveDate) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+        debug!(?on, "fetching…");
+        let account: Response<Account> = self.get("user-accounts/me").await?;
+        let connection = account
+            .data
+            .address_groups
+            .iter()
+            .flat_map(|group| &group.connections)
+            .find(|connection| connection.market_segment == "electricity")
+            .context("the Zonneplan account has no electricity connection")?;
+        let summary: Response<Summary> =
+            self.get(&format!("connections/{}/summary", connection.uuid)).await?;
+        summary.data.into_schedule(Interval::local_day(on)?)
+    }
+
+    async fn get<R: DeserializeOwned>(&self, path: &str) -> Result<R> {
+        let access_token = self.access_token().await?;
+        self.client
+            .get(format!("{}/{path}", Self::BASE_URL))
+            .headers(Self::app_headers())
+            .bearer_auth(access_token)
+            .send()
+            .await
+            .with_context(|| format!("failed to call `{path}`"))?
+            .error_for_status()?
+            .json()
+            .await
+            .with_context(|| format!("failed to deserialize the `{path}` response"))
+    }
+
+    fn token_path(&self) -> Result<&Path> {
+        self.token_path.as_deref().context("Zonneplan requires the token path")
+    }
+
+    /// Valid access token, refreshing the stored one when needed.
+    ///
+    /// It never falls back to logging in, since nobody may be there to open the login link.
+    async fn access_token(&self) -> Result<String> {
+        let mut token = self.token.lock().await;
+        let current_token = match token.take() {
+            Some(current_token) => current_token,
+            None => Token::read_from_file(self.token_path()?)
+                .await?
+                .context("not logged into Zonneplan, run with `--zonneplan-login` first")?,
+        };
+        let current_token = if current_token.expires_soon(Local::now()) {
+            let refreshed_token = self
+                .request_token(&TokenRequest::refresh(&current_token.refresh))
+                .await
+                .context("failed to refresh the token, log in again with `--zonneplan-login`")?;
+            refreshed_token.write_to_file(self.token_path()?).await?;
+            refreshed_token
+        } else {
+            current_token
+        };
+        let access_token = current_token.access.clone();
+        *token = Some(current_token);
+        drop(token);
+        Ok(access_token)
+    }
+
+    /// Send the login link and wait for it to be opened.
+    async fn request_login(&self) -> Result<Token> {
+        let email = self.email.as_deref().context("Zonneplan requires the account e-mail")?;
+        let login: Response<LoginRequest> = self
+            .client
+            .post(format!("{}/auth/request", Self::BASE_URL))
+            .headers(Self::app_headers())
+            .json(&serde_json::json!({ "email": email }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("failed to request the login link")?;
+        warn!(email, "open the login link sent to the e-mail to authorize Fennec");
+
+        let password = tokio::time::timeout(Self::LOGIN_TIMEOUT, async {
+            loop {
+                sleep(Self::LOGIN_POLL_INTERVAL).await;
+                let status: Response<LoginStatus> = self
+                    .client
+                    .get(format!("{}/auth/request/{}", Self::BASE_URL, login.data.uuid))
+                    .headers(Self::app_headers())
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json()
+                    .await?;
+                if status.data.is_activated {
+                    break status.data.password.context("the activated login has no password");
+                }
+            }
+        })
+        .await
+        .context("timed out waiting for the login link to be opened")??;
+
+        let token = self.request_token(&TokenRequest::one_time_password(email, &password)).await?;
+        info!("logged in");
+        Ok(token)
+    }
+
+    async fn request_token(&self, request: &TokenRequest<'_>) -> Result<Token> {
+        let response: TokenResponse = self
+            .client
+            .post(format!("{}/oauth/token", Self::BASE_URL))
+            .headers(Self::app_headers())
+            .json(request)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("failed to request the token")?;
+        Ok(Token {
+            access: response.access_token,
+            refresh: response.refresh_token,
+            expires_at: Local::now() + TimeDelta::seconds(response.expires_in),
+        })
+    }
+
+    fn app_headers() -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-app-version", HeaderValue::from_static(Self::APP_VERSION));
+        headers.insert("x-app-environment", HeaderValue::from_static("production"));
+        headers
+    }
+}
+
+/// OAuth token, persisted so that a restart does not require logging in again.
+#[derive(Encode, Decode)]
+struct Token {
+    #[musli(Binary, name = 1)]
+    access: String,
+
+    #[musli(Binary, name = 2)]
+    refresh: String,
+
+    #[musli(Binary, name = 3)]
+    #[musli(with = crate::ops::musli::chrono)]
+    expires_at: DateTime<Local>,
+}
+
+impl Token {
+    fn expires_soon(&self, now: DateTime<Local>) -> bool {
+        self.expires_at - now < TimeDelta::minutes(5)
+    }
+
+    #[instrument(fields(path = %path.display()))]
+    async fn read_from_file(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = tokio::fs::read(path).await.context("failed to read the file")?;
+        Ok(Some(wire::decode(bytes.as_slice()).context("failed to decode the file")?))
+    }
+
+    /// Write the token, so that only the owner may read it.
+    #[instrument(skip_all, fields(path = %path.display()))]
+    async fn write_to_file(&self, path: &Path) -> Result {
+        let temporary_path = path.with_added_extension("temporary");
+        let bytes = wire::to_vec(self).context("failed to encode the token")?;
+        let _ = tokio::fs::remove_file(&temporary_path).await;
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temporary_path)
+            .await
+            .context("failed to create the token file")?;
+        file.write_all(bytes.as_slice()).await.context("failed to write the token")?;
+        file.sync_all().await.context("failed to flush the token")?;
+        drop(file);
+        tokio::fs::rename(&temporary_path, path)
+            .await
+            .context("failed to rename the temporary file")?;
+        Ok(())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "grant_type", rename_all = "snake_case")]
+enum TokenRequest<'a> {
+    OneTimePassword { email: &'a str, password: &'a str },
+    RefreshToken { refresh_token: &'a str },
+}
+
+impl<'a> TokenRequest<'a> {
+    const fn one_time_password(email: &'a str, password: &'a str) -> Self {
+        Self::OneTimePassword { email, password }
+    }
+
+    const fn refresh(refresh_token: &'a str) -> Self {
+        Self::RefreshToken { refresh_token }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct LoginRequest {
+    uuid: String,
+}
+
+#[derive(Deserialize)]
+struct LoginStatus {
+    is_activated: bool,
+    password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Account {
+    address_groups: Vec<AddressGroup>,
+}
+
+#[derive(Deserialize)]
+struct AddressGroup {
+    connections: Vec<Connection>,
+}
+
+#[derive(Deserialize)]
+struct Connection {
+    uuid: String,
+    market_segment: String,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    price_per_hour: Vec<HourlyPrice>,
+}
+
+impl Summary {
+    /// Convert the hourly prices within the day into the price schedule.
+    #[expect(clippy::cast_precision_loss)]
+    fn into_schedule(
+        self,
+        day: Interval<DateTime<Local>>,
+    ) -> Result<Schedule<Flow<KilowattHourPrice>>> {
+        let mut schedule = Schedule::new();
+        for price in self.price_per_hour {
+            let interval = Interval::new(price.datetime, price.datetime + TimeDelta::hours(1));
+            if !day.contains(interval) {
+                continue;
+            }
+            let price = Quantity(price.electricity_price as f64 / Api::PRICE_SCALE);
+            schedule.extend_from_iter([(interval, Flow { import: price, export: price })])?;
+        }
+        Ok(schedule)
+    }
+}
+
+#[derive(Deserialize)]
+struct HourlyPrice {
+    /// Start of the hour.
+    datetime: DateTime<Local>,
+
+    electricity_price: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_request_ok() -> Result {
+        assert_eq!(
+            serde_json::to_value(TokenRequest::refresh("token"))?,
+            serde_json::json!({"grant_type": "refresh_token", "refresh_token": "token"}),
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn summary_ok() -> Result {
+        let day = Interval::local_day(NaiveDate::from_ymd_opt(2026, 4, 8).unwrap())?;
+        let response = serde_json::json!({
+            "data": {
+                "live_data": [],
+                "price_per_hour": [
+                    {
+                        "electricity_price": 2_117_807,
+                        "tariff_group": "normal",
+                        "solar_percentage": 0,
+                        "datetime": day.start(),
+                    },
+                    {
+                        "electricity_price": 1_917_807,
+                        "tariff_group": "low",
+                        "solar_percentage": 42,
+                        "datetime": day.end(),
+                    },
+                ],
+            },
+        });
+        let summary: Response<Summary> = serde_json::from_value(response)?;
+        let schedule = summary.data.into_schedule(day)?;
+        assert_eq!(schedule.len(), 1);
+        assert!((schedule.get(0).value.import.0 - 0.211_780_7).abs() < 1e-9);
+        Ok(())
+    }
+}
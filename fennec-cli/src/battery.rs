@@ -1,11 +1,25 @@
 mod args;
+mod coupling;
+pub mod derating;
+mod health;
+mod history;
+mod metrics;
 mod power_limits;
+mod reading;
+pub mod reserve;
 mod simulator;
+mod throughput;
 mod working_mode;
 
 pub use self::{
     args::Args,
+    coupling::PvCoupling,
+    health::{CapacityTrend, Health},
+    history::History,
+    metrics::Metrics,
     power_limits::PowerLimits,
-    simulator::Simulator,
+    reading::Reading,
+    simulator::{Flows, Simulator},
+    throughput::{Throughput, YearToDate},
     working_mode::WorkingMode,
 };
@@ -1,6 +1,20 @@
-use crate::{battery, battery::WorkingMode, quantity::price::KilowattHourPrice};
+use crate::{
+    api::inverter,
+    battery,
+    battery::{WorkingMode, derating},
+    energy,
+    prelude::*,
+    quantity::{
+        Zero,
+        currency::Mills,
+        energy::KilowattHours,
+        power::Watts,
+        price::KilowattHourPrice,
+        ratios::Percentage,
+    },
+};
 
-#[derive(clap::Args)]
+#[derive(Clone, clap::Args, serde::Serialize)]
 #[group(id = "battery")]
 pub struct Args {
     #[clap(
@@ -12,9 +26,60 @@ pub struct Args {
     )]
     pub working_modes: Vec<WorkingMode>,
 
+    /// Power levels for the forced charging and discharging, in percents of the power limits.
+    ///
+    /// Each level is an extra optimizer action: charging slower over a few mildly cheap intervals
+    /// may beat charging at full power during a single one.
+    #[clap(
+        long = "battery-power-levels",
+        env = "POWER_LEVELS",
+        value_delimiter = ',',
+        num_args = 1..,
+        default_value = "100",
+    )]
+    pub power_levels: Vec<Percentage>,
+
+    /// Power levels for the forced charging, in percents of the charging power limit.
+    ///
+    /// Overrides `--battery-power-levels` for the charging, for example, `25,50,75,100`.
+    #[clap(
+        long = "battery-charging-power-levels",
+        env = "CHARGING_POWER_LEVELS",
+        value_delimiter = ','
+    )]
+    pub charging_power_levels: Vec<Percentage>,
+
+    /// Power levels for the forced discharging, in percents of the discharging power limit.
+    ///
+    /// Overrides `--battery-power-levels` for the discharging.
+    #[clap(
+        long = "battery-discharging-power-levels",
+        env = "DISCHARGING_POWER_LEVELS",
+        value_delimiter = ','
+    )]
+    pub discharging_power_levels: Vec<Percentage>,
+
     #[clap(flatten)]
     pub power_limits: battery::PowerLimits,
 
+    /// Charging power derating by state-of-charge as `SOC:LEVEL` points in percents,
+    /// for example, `90:100,100:30` for the charging slowing down linearly above 90%.
+    #[clap(
+        long = "battery-charging-derating",
+        env = "BATTERY_CHARGING_DERATING",
+        value_delimiter = ','
+    )]
+    pub charging_derating: Vec<derating::Point>,
+
+    /// Discharging power derating by state-of-charge as `SOC:LEVEL` points in percents,
+    /// for example, `0:30,10:100` for the discharging slowing down linearly below 10%.
+    #[clap(
+        long = "battery-discharging-derating",
+        env = "BATTERY_DISCHARGING_DERATING",
+        value_delimiter = ','
+    )]
+    pub discharging_derating: Vec<derating::Point>,
+
     /// Battery health costs lost to the cycling, in ¤/kWh.
     #[clap(
         long = "battery-degradation-cost",
@@ -22,4 +87,134 @@ pub struct Args {
         default_value = "0.03"
     )]
     pub degradation_cost: KilowattHourPrice,
+
+    /// Depth of discharge that counts as one full cycle, percentage of the design capacity.
+    ///
+    /// Set it to match the BMS: some count a cycle per 80% of the capacity charged and discharged.
+    #[clap(
+        long = "battery-cycle-depth",
+        env = "BATTERY_CYCLE_DEPTH",
+        default_value = "100",
+        value_parser = parse_cycle_depth,
+    )]
+    pub cycle_depth: Percentage,
+
+    /// Warranty throughput budget per calendar year, in kilowatt-hours discharged.
+    ///
+    /// When the battery is discharging faster than the budget allows, the degradation cost
+    /// gets scaled up by the same factor, so that only the more profitable cycles remain.
+    #[clap(long = "battery-yearly-throughput", env = "BATTERY_YEARLY_THROUGHPUT")]
+    pub yearly_throughput: Option<KilowattHours>,
+
+    /// State-of-health below which the battery is considered worn out, for example, per the warranty.
+    ///
+    /// Only used to project the capacity trend, it does not affect the planning.
+    #[clap(
+        long = "battery-health-threshold",
+        env = "BATTERY_HEALTH_THRESHOLD",
+        default_value = "70"
+    )]
+    pub health_threshold: Percentage,
+
+    /// Margin to keep away from the minimum and maximum state-of-charge, percentage of the capacity.
+    ///
+    /// Plans hovering exactly at a bound make the inverter chatter between its working modes.
+    /// Residual energy within the margin is penalized, so the plan only goes there when it pays off.
+    #[clap(long = "battery-soc-hysteresis", env = "BATTERY_SOC_HYSTERESIS", default_value = "0")]
+    pub soc_hysteresis: Percentage,
+
+    /// Cost of switching the working mode, in ₥ (thousandths of ¤) per switch.
+    ///
+    /// Frequent switches wear the relays and confuse some inverters.
+    #[clap(long = "battery-switching-cost", env = "BATTERY_SWITCHING_COST", default_value = "0")]
+    pub switching_cost: Mills,
+
+    /// Avoid feeding into the grid when the export price is negative:
+    /// the battery then absorbs the excess solar power and never discharges into the grid.
+    #[clap(long = "no-export-when-negative", env = "NO_EXPORT_WHEN_NEGATIVE")]
+    pub no_export_when_negative: bool,
+
+    /// Forbid charging from the grid, as some installations are not allowed to:
+    /// the battery then only charges from the excess solar power.
+    #[clap(long = "no-grid-charge", env = "NO_GRID_CHARGE")]
+    pub no_grid_charge: bool,
+
+    /// How the PV panels are connected to the battery: DC-coupled solar charging skips
+    /// the inverter conversion losses, which the grid charging still has.
+    #[clap(long = "pv-coupling", env = "PV_COUPLING", default_value = "ac")]
+    pub pv_coupling: battery::PvCoupling,
+
+    /// Inverter DC-to-AC conversion efficiency, which the DC-coupled solar charging skips.
+    #[clap(long = "inverter-efficiency", env = "INVERTER_EFFICIENCY", default_value = "97")]
+    pub inverter_efficiency: Percentage,
+
+    /// Inverter stand-by consumption in watts, which the meters do not see as the household load.
+    ///
+    /// It is an extra load in every step: paid from the grid when idle or charging,
+    /// and from the battery otherwise.
+    #[clap(
+        long = "inverter-standby-power-watts",
+        env = "INVERTER_STANDBY_POWER_WATTS",
+        default_value = "0"
+    )]
+    pub inverter_standby_power: Watts,
+
+    /// Minimum state-of-charge to keep during the daily windows, formatted as `HH:MM-HH:MM=SOC`,
+    /// for example, `17:00-22:00=40%` to have a backup reserve in the outage-prone evening hours.
+    ///
+    /// The plan only goes below the reserve when it cannot be reached in time.
+    #[clap(
+        long = "battery-reserve-soc-window",
+        env = "BATTERY_RESERVE_SOC_WINDOWS",
+        value_delimiter = ','
+    )]
+    pub reserve_windows: Vec<battery::reserve::ReserveWindow>,
+
+    /// Penalty for the residual energy within the hysteresis margin, in ¤/kWh per hour.
+    #[clap(
+        long = "battery-soc-hysteresis-cost",
+        env = "BATTERY_SOC_HYSTERESIS_COST",
+        default_value = "0.01"
+    )]
+    pub soc_hysteresis_cost: KilowattHourPrice,
+}
+
+impl Args {
+    /// Power levels the optimizer may choose from for the forced charging and discharging.
+    pub fn forced_power_levels(&self) -> energy::Flow<Vec<Percentage>> {
+        let or_common = |levels: &Vec<Percentage>| {
+            if levels.is_empty() { self.power_levels.clone() } else { levels.clone() }
+        };
+        energy::Flow {
+            import: or_common(&self.charging_power_levels),
+            export: or_common(&self.discharging_power_levels),
+        }
+    }
+
+    /// Drop the working modes which the inverter cannot execute faithfully,
+    /// and the forced charging if the grid charging is forbidden.
+    pub fn retain_supported_working_modes(&mut self, inverter: inverter::Kind) -> Result {
+        self.working_modes.retain(|working_mode| {
+            let is_supported = inverter.supports(*working_mode);
+            if !is_supported {
+                warn!(?working_mode, "the inverter does not support the working mode, ignoring");
+            }
+            is_supported
+        });
+        if self.no_grid_charge && self.working_modes.contains(&WorkingMode::Charge) {
+            info!("the grid charging is forbidden, ignoring the forced charging");
+            self.working_modes.retain(|working_mode| *working_mode != WorkingMode::Charge);
+        }
+        ensure!(!self.working_modes.is_empty(), "none of the working modes are supported");
+        Ok(())
+    }
+}
+
+fn parse_cycle_depth(value: &str) -> Result<Percentage> {
+    let cycle_depth: Percentage = value.parse()?;
+    ensure!(
+        cycle_depth > Percentage::ZERO && cycle_depth <= Percentage::FULL,
+        "cycle depth must be within 1…100%",
+    );
+    Ok(cycle_depth)
 }
@@ -0,0 +1,48 @@
+use crate::quantity::ratios::Percentage;
+
+/// How the PV panels are connected to the battery.
+#[derive(Copy, Clone, Debug, Default, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PvCoupling {
+    /// The PV panels have their own inverter, so the solar power charges the battery
+    /// through the same AC path as the grid power.
+    #[default]
+    Ac,
+
+    /// The PV panels share the hybrid inverter with the battery, so the solar power
+    /// charges the battery directly, skipping the DC-to-AC conversion.
+    Dc,
+}
+
+impl PvCoupling {
+    /// Efficiency of charging the battery from the excess solar power.
+    ///
+    /// The learned charging efficiency is treated as the grid path. DC-coupled PV skips
+    /// the inverter conversion, so its efficiency gets divided out, but never above 100%.
+    pub fn solar_charging_efficiency(
+        self,
+        grid_charging_efficiency: f64,
+        inverter_efficiency: Percentage,
+    ) -> f64 {
+        match self {
+            Self::Ac => grid_charging_efficiency,
+            Self::Dc => (grid_charging_efficiency / inverter_efficiency.to_ratio()).min(1.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn solar_charging_efficiency_ok() {
+        let efficiency = PvCoupling::Ac.solar_charging_efficiency(0.9, Quantity(96));
+        assert!((efficiency - 0.9).abs() < 1e-9);
+        let efficiency = PvCoupling::Dc.solar_charging_efficiency(0.9, Quantity(96));
+        assert!((efficiency - 0.9375).abs() < 1e-9);
+        let efficiency = PvCoupling::Dc.solar_charging_efficiency(0.99, Quantity(96));
+        assert!((efficiency - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,83 @@
+//! State-of-charge dependent power limits: many inverters slow the charging down near the top
+//! and the discharging near the bottom.
+
+use std::str::FromStr;
+
+use crate::{prelude::*, quantity::ratios::Percentage};
+
+/// Power level at the state-of-charge, formatted as `SOC:LEVEL` in percents, for example: `90:50`.
+#[derive(Copy, Clone, Debug, serde::Serialize)]
+pub struct Point {
+    pub state_of_charge: Percentage,
+    pub power_level: Percentage,
+}
+
+impl FromStr for Point {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (state_of_charge, power_level) =
+            text.split_once(':').context("expected `SOC:LEVEL`")?;
+        let this = Self {
+            state_of_charge: state_of_charge.trim().parse()?,
+            power_level: power_level.trim().parse()?,
+        };
+        ensure!(this.state_of_charge <= Percentage::FULL, "state-of-charge is over 100%");
+        ensure!(this.power_level <= Percentage::FULL, "power level is over 100%");
+        Ok(this)
+    }
+}
+
+/// Piecewise linear derating curve, the power level stays flat beyond the outermost points.
+///
+/// The points must be sorted by the state-of-charge. An empty curve means no derating.
+#[derive(Copy, Clone)]
+pub struct Curve<'a>(pub &'a [Point]);
+
+impl Curve<'_> {
+    /// Power ratio, `0.0..=1.0`, at the state-of-charge ratio.
+    #[must_use]
+    pub fn at(self, state_of_charge: f64) -> f64 {
+        let state_of_charge = state_of_charge * 100.0;
+        let index =
+            self.0.partition_point(|point| f64::from(point.state_of_charge.0) <= state_of_charge);
+        match (index.checked_sub(1).map(|index| self.0[index]), self.0.get(index).copied()) {
+            (None, None) => 1.0,
+            (Some(point), None) | (None, Some(point)) => point.power_level.to_ratio(),
+            (Some(left), Some(right)) => {
+                let (left_soc, right_soc) =
+                    (f64::from(left.state_of_charge.0), f64::from(right.state_of_charge.0));
+                let weight = (state_of_charge - left_soc) / (right_soc - left_soc);
+                (right.power_level.to_ratio() - left.power_level.to_ratio())
+                    .mul_add(weight, left.power_level.to_ratio())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn at_ok() -> Result {
+        let points: Vec<Point> = vec!["90:100".parse()?, "100:20".parse()?];
+        let curve = Curve(&points);
+        assert!((curve.at(0.5) - 1.0).abs() < 1e-9);
+        assert!((curve.at(0.9) - 1.0).abs() < 1e-9);
+        assert!((curve.at(0.95) - 0.6).abs() < 1e-9);
+        assert!((curve.at(1.0) - 0.2).abs() < 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn empty_ok() {
+        assert!((Curve(&[]).at(0.95) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!("90".parse::<Point>().is_err());
+        assert!("90:150".parse::<Point>().is_err());
+    }
+}
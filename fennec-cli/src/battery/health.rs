@@ -0,0 +1,163 @@
+use chrono::{DateTime, Local, TimeDelta};
+use musli::{Decode, Encode};
+use serde::Serialize;
+
+use crate::quantity::{
+    energy::{DecawattHours, WattHours},
+    ratios::Percentage,
+};
+
+/// Long-term state-of-health history, persisted along with the energy profile.
+#[must_use]
+#[derive(Clone, Default, Encode, Decode)]
+pub struct Health {
+    #[musli(Binary, name = 1)]
+    records: Vec<HealthRecord>,
+}
+
+#[derive(Copy, Clone, Encode, Decode)]
+struct HealthRecord {
+    #[musli(Binary, name = 1)]
+    #[musli(with = crate::ops::musli::chrono)]
+    timestamp: DateTime<Local>,
+
+    #[musli(Binary, name = 2)]
+    state_of_health: Percentage,
+
+    #[musli(Binary, name = 3)]
+    design_capacity: DecawattHours,
+}
+
+/// Linear state-of-health fade fitted over the history.
+#[must_use]
+#[derive(Copy, Clone, Serialize)]
+pub struct CapacityTrend {
+    /// Fitted state-of-health by the last record, percentage.
+    pub state_of_health: f64,
+
+    /// State-of-health change per 30 days, percentage points. Negative means fade.
+    pub change_per_month: f64,
+
+    /// Actual capacity change per 30 days.
+    pub capacity_change_per_month: WattHours,
+
+    /// When the fitted state-of-health drops below the threshold, if it is fading at all.
+    pub threshold_reached_at: Option<DateTime<Local>>,
+}
+
+impl Health {
+    const RECORD_INTERVAL: TimeDelta = TimeDelta::days(1);
+
+    /// Minimal history span to fit the trend over.
+    const MIN_SPAN: TimeDelta = TimeDelta::days(7);
+
+    /// Record the state-of-health daily, or whenever it changes.
+    ///
+    /// Returns [`true`] if the record has been added.
+    pub fn push(
+        &mut self,
+        timestamp: DateTime<Local>,
+        state_of_health: Percentage,
+        design_capacity: DecawattHours,
+    ) -> bool {
+        let should_push = self.records.last().is_none_or(|last| {
+            (timestamp - last.timestamp >= Self::RECORD_INTERVAL)
+                || (last.state_of_health != state_of_health)
+                || (last.design_capacity != design_capacity)
+        });
+        if should_push {
+            self.records.push(HealthRecord { timestamp, state_of_health, design_capacity });
+        }
+        should_push
+    }
+
+    /// Fit the [least squares][1] line through the state-of-health history, and project
+    /// when it drops below the threshold.
+    ///
+    /// Returns [`None`] if the history is too short.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Simple_linear_regression
+    #[expect(clippy::cast_possible_truncation)]
+    pub fn trend(&self, threshold: Percentage) -> Option<CapacityTrend> {
+        let first = self.records.first()?;
+        let last = self.records.last()?;
+        if last.timestamp - first.timestamp < Self::MIN_SPAN {
+            return None;
+        }
+
+        let days = |timestamp: DateTime<Local>| {
+            (timestamp - first.timestamp).as_seconds_f64() / TimeDelta::days(1).as_seconds_f64()
+        };
+        let points = self
+            .records
+            .iter()
+            .map(|record| (days(record.timestamp), f64::from(record.state_of_health.0)));
+        let (n, sum_days, sum_health, sum_days_squared, sum_product) = points.fold(
+            (0.0_f64, 0.0, 0.0, 0.0, 0.0),
+            |(n, sum_days, sum_health, sum_days_squared, sum_product), (days, health)| {
+                (
+                    n + 1.0,
+                    sum_days + days,
+                    sum_health + health,
+                    days.mul_add(days, sum_days_squared),
+                    days.mul_add(health, sum_product),
+                )
+            },
+        );
+        let slope = n.mul_add(sum_product, -sum_days * sum_health)
+            / n.mul_add(sum_days_squared, -sum_days * sum_days);
+        let intercept = slope.mul_add(-sum_days, sum_health) / n;
+
+        let last_days = days(last.timestamp);
+        let state_of_health = slope.mul_add(last_days, intercept);
+        let change_per_month = slope * 30.0;
+        let design_capacity: WattHours = last.design_capacity.rescale();
+        let threshold_reached_at = (slope < 0.0)
+            .then(|| (f64::from(threshold.0) - intercept) / slope)
+            .and_then(|days| TimeDelta::try_seconds((days * 86400.0) as i64))
+            .map(|offset| first.timestamp + offset);
+
+        Some(CapacityTrend {
+            state_of_health,
+            change_per_month,
+            capacity_change_per_month: design_capacity * (change_per_month / 100.0),
+            threshold_reached_at,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn trend_ok() {
+        let start = Local.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut health = Health::default();
+        for (months, state_of_health) in [(0, 100), (1, 99), (2, 98), (3, 97)] {
+            assert!(health.push(
+                start + TimeDelta::days(30 * months),
+                Quantity(state_of_health),
+                Quantity(1000),
+            ));
+        }
+
+        let trend = health.trend(Quantity(70)).unwrap();
+        assert!((trend.state_of_health - 97.0).abs() < 1e-9);
+        assert!((trend.change_per_month + 1.0).abs() < 1e-9);
+        assert!((trend.capacity_change_per_month.0 + 100.0).abs() < 1e-9);
+        assert_eq!(trend.threshold_reached_at, Some(start + TimeDelta::days(900)));
+    }
+
+    #[test]
+    fn trend_too_short() {
+        let start = Local.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        let mut health = Health::default();
+        assert!(health.push(start, Quantity(100), Quantity(1000)));
+        assert!(!health.push(start + TimeDelta::hours(1), Quantity(100), Quantity(1000)));
+        assert!(health.trend(Quantity(70)).is_none());
+    }
+}
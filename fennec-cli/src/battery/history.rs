@@ -0,0 +1,28 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::quantity::ratios::Percentage;
+
+/// Recent state-of-charge history, kept in memory for the web UI.
+#[must_use]
+#[derive(Default)]
+pub struct History(VecDeque<(DateTime<Local>, Percentage)>);
+
+impl History {
+    const RETENTION: TimeDelta = TimeDelta::days(1);
+
+    /// Record the state-of-charge, only if it has changed since the last record.
+    pub fn push(&mut self, timestamp: DateTime<Local>, state_of_charge: Percentage) {
+        if self.0.back().is_none_or(|(_, last)| *last != state_of_charge) {
+            self.0.push_back((timestamp, state_of_charge));
+        }
+        while self.0.front().is_some_and(|(oldest, _)| timestamp - *oldest > Self::RETENTION) {
+            self.0.pop_front();
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(DateTime<Local>, Percentage)> {
+        self.0.iter()
+    }
+}
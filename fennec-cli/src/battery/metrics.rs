@@ -9,7 +9,9 @@ use crate::{
     },
 };
 
+/// Inverter-agnostic battery metrics.
 #[must_use]
+#[derive(Clone)]
 pub struct Metrics {
     /// State-of-charge (SoC) percentage.
     pub state_of_charge: Percentage,
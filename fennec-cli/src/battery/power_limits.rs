@@ -4,7 +4,7 @@ use crate::{energy, quantity::power::Watts};
 ///
 /// TODO: we could use `Watts<u16>` here.
 #[must_use]
-#[derive(Copy, Clone, clap::Args)]
+#[derive(Copy, Clone, clap::Args, serde::Serialize)]
 pub struct PowerLimits {
     /// Charging power in watts.
     #[clap(
@@ -32,9 +32,32 @@ pub struct PowerLimits {
         env = "MAX_INVERTER_POWER_WATTS"
     )]
     pub max_inverter_power: Watts,
+
+    /// Minimal charging power in watts – the inverter does not charge at lower power at all.
+    #[clap(
+        name = "min_charging_power",
+        long = "min-charging-power-watts",
+        default_value = "0",
+        env = "MIN_CHARGING_POWER_WATTS"
+    )]
+    pub min_charging: Watts,
+
+    /// Minimal discharging power in watts – the inverter does not discharge at lower power at all.
+    #[clap(
+        name = "min_discharging_power",
+        long = "min-discharging-power-watts",
+        default_value = "0",
+        env = "MIN_DISCHARGING_POWER_WATTS"
+    )]
+    pub min_discharging: Watts,
 }
 
 impl PowerLimits {
+    /// Minimal operating power of the inverter.
+    pub const fn min_flow(self) -> energy::Flow<Watts> {
+        energy::Flow { import: self.min_charging, export: self.min_discharging }
+    }
+
     /// Calculate the effective power limits given the average EPS power.
     pub fn max_effective_flow(self, average_eps_power: Watts) -> energy::Flow<Watts> {
         energy::Flow {
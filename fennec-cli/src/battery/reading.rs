@@ -0,0 +1,48 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::{
+    battery::{Metrics, PowerLimits},
+    quantity::{energy::WattHours, power::Watts, ratios::Percentage},
+};
+
+/// Most recent battery reading, served to the API consumers instead of them querying the inverter.
+///
+/// Inverter Modbus stacks tend to be flaky under concurrent connections,
+/// so the engine remains the only one talking to the battery.
+#[must_use]
+#[derive(Copy, Clone, Serialize)]
+pub struct Reading {
+    pub measured_at: DateTime<Local>,
+    pub state_of_charge: Percentage,
+    pub state_of_health: Percentage,
+
+    /// Residual energy corrected on the state of health.
+    pub residual_energy: WattHours<usize>,
+
+    /// Minimal allowed state-of-charge per the battery settings.
+    pub min_state_of_charge: Percentage,
+
+    /// Maximal allowed state-of-charge per the battery settings.
+    pub max_state_of_charge: Percentage,
+
+    /// Positive means discharging, negative means charging.
+    pub active_power: Watts,
+
+    pub power_limits: PowerLimits,
+}
+
+impl Reading {
+    pub fn new(measured_at: DateTime<Local>, metrics: &Metrics, power_limits: PowerLimits) -> Self {
+        Self {
+            measured_at,
+            state_of_charge: metrics.state_of_charge,
+            state_of_health: metrics.state_of_health,
+            residual_energy: WattHours::from(metrics.residual_energy()).into(),
+            min_state_of_charge: metrics.allowed_soc.start,
+            max_state_of_charge: metrics.allowed_soc.last,
+            active_power: metrics.active_power,
+            power_limits,
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Backup reserve: minimum state-of-charge to keep during the daily windows,
+//! for example, in the outage-prone hours or on a storm warning.
+
+use std::str::FromStr;
+
+use chrono::NaiveTime;
+
+use crate::{
+    ops::daily_window::DailyWindow,
+    prelude::*,
+    quantity::{Zero, ratios::Percentage},
+};
+
+/// Reserve within a daily time window, formatted as `HH:MM-HH:MM=SOC`, for example: `17:00-22:00=40%`.
+#[derive(Copy, Clone, Debug, serde::Serialize)]
+pub struct ReserveWindow {
+    pub window: DailyWindow,
+    pub state_of_charge: Percentage,
+}
+
+impl FromStr for ReserveWindow {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (window, state_of_charge) =
+            text.split_once('=').context("expected `HH:MM-HH:MM=SOC`")?;
+        let state_of_charge: Percentage = state_of_charge.trim().trim_end_matches('%').parse()?;
+        ensure!(state_of_charge <= Percentage::FULL, "reserve state-of-charge is over 100%");
+        Ok(Self { window: window.parse()?, state_of_charge })
+    }
+}
+
+/// Reserve state-of-charge at the time, the highest one of the overlapping windows.
+pub fn reserve_at(windows: &[ReserveWindow], time: NaiveTime) -> Percentage {
+    windows
+        .iter()
+        .filter(|reserve| reserve.window.contains(time))
+        .map(|reserve| reserve.state_of_charge)
+        .max()
+        .unwrap_or(Percentage::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reserve_at_ok() -> Result {
+        let windows = [
+            "17:00-22:00=40%".parse::<ReserveWindow>()?,
+            "21:00-06:00=20".parse::<ReserveWindow>()?,
+        ];
+        let at = |hour| reserve_at(&windows, NaiveTime::from_hms_opt(hour, 0, 0).unwrap());
+        assert_eq!(at(16), Percentage::ZERO);
+        assert_eq!(at(17), Percentage::new(40));
+        assert_eq!(at(21), Percentage::new(40));
+        assert_eq!(at(22), Percentage::new(20));
+        assert_eq!(at(5), Percentage::new(20));
+        assert_eq!(at(6), Percentage::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn parse_err() {
+        assert!("17:00-22:00".parse::<ReserveWindow>().is_err());
+        assert!("17:00-22:00=140%".parse::<ReserveWindow>().is_err());
+    }
+}
@@ -1,10 +1,13 @@
+use std::ops::Div;
+
 use crate::{
+    battery::derating,
     energy::Flow,
     quantity::{Zero, energy::WattHours, power::Watts, time::Hours},
 };
 
 #[derive(Copy, Clone)]
-pub struct Simulator {
+pub struct Simulator<'a> {
     pub efficiency: Flow<f64>,
 
     /// Battery capacity.
@@ -17,11 +20,38 @@ pub struct Simulator {
 
     /// Current residual energy.
     pub residual_energy: WattHours,
+
+    /// Minimal operating power of the inverter, lower requested power is rounded down to zero.
+    pub min_power: Flow<Watts>,
+
+    /// Maximal power of the inverter, before the derating.
+    pub max_power: Flow<Watts>,
+
+    /// State-of-charge dependent derating of the maximal power.
+    ///
+    /// It is taken at the initial state-of-charge, which is good enough for the short steps.
+    pub derating: Flow<derating::Curve<'a>>,
 }
 
-impl Simulator {
+impl Simulator<'_> {
     /// Apply the requested power, update the internal state and return actual billable energy flow.
     pub fn apply(&mut self, external_power: Flow<Watts>, for_: Hours) -> Flows {
+        let state_of_charge = self.residual_energy / self.capacity;
+        let external_power = Flow {
+            import: external_power
+                .import
+                .min(self.max_power.import * self.derating.import.at(state_of_charge)),
+            export: external_power
+                .export
+                .min(self.max_power.export * self.derating.export.at(state_of_charge)),
+        };
+
+        // The inverter does not operate below its minimal power, so neither do we:
+        let external_power = Flow {
+            import: Self::operating_power(external_power.import, self.min_power.import),
+            export: Self::operating_power(external_power.export, self.min_power.export),
+        };
+
         // Apply the efficiency corrections first – then, we can model everything in terms of residual energy:
         let internal_power = Flow {
             import: external_power.import * self.efficiency.import,
@@ -60,20 +90,46 @@ impl Simulator {
             internal: actual_flow,
         }
     }
+
+    fn operating_power(power: Watts, min_power: Watts) -> Watts {
+        if power < min_power { Watts::ZERO } else { power }
+    }
 }
 
+#[derive(Copy, Clone, derive_more::AddAssign)]
 pub struct Flows {
     pub external: Flow<WattHours>,
     pub internal: Flow<WattHours>,
 }
 
+impl Zero for Flows {
+    const ZERO: Self = Self { external: Flow::ZERO, internal: Flow::ZERO };
+}
+
+impl Div<f64> for Flows {
+    type Output = Self;
+
+    fn div(self, rhs: f64) -> Self::Output {
+        Self { external: self.external / rhs, internal: self.internal / rhs }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::quantity::{Quantity, Zero};
+    use crate::{
+        prelude::*,
+        quantity::{Quantity, Zero},
+    };
 
     const IDEAL_EFFICIENCY: Flow<f64> = Flow { import: 1.0, export: 1.0 };
 
+    const UNLIMITED_POWER: Flow<Watts> =
+        Flow { import: Quantity(f64::INFINITY), export: Quantity(f64::INFINITY) };
+
+    const NO_DERATING: Flow<derating::Curve> =
+        Flow { import: derating::Curve(&[]), export: derating::Curve(&[]) };
+
     /// Verify normal charging without overflowing.
     #[test]
     fn normal_operation() {
@@ -81,6 +137,9 @@ mod tests {
             residual_energy: Quantity(5000.0),
             capacity: Quantity(10000.0),
             efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows = simulator
             .apply(Flow { import: Quantity(1000.0), export: Quantity(700.0) }, Quantity(1.0));
@@ -96,6 +155,9 @@ mod tests {
             residual_energy: Quantity(5000.0),
             capacity: Quantity(10000.0),
             efficiency: Flow { import: 0.9, export: 0.5 },
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows = simulator
             .apply(Flow { import: Quantity(1000.0), export: Quantity(1000.0) }, Quantity(1.0));
@@ -117,6 +179,9 @@ mod tests {
             residual_energy: Quantity(9000.0),
             capacity: Quantity(10000.0),
             efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows =
             simulator.apply(Flow { import: Quantity(2000.0), export: Watts::ZERO }, Quantity(1.0));
@@ -132,6 +197,9 @@ mod tests {
             residual_energy: Quantity(1000.0),
             capacity: Quantity(10000.0),
             efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows =
             simulator.apply(Flow { import: Watts::ZERO, export: Quantity(2000.0) }, Quantity(1.0));
@@ -147,6 +215,9 @@ mod tests {
             residual_energy: Quantity(100.0),
             capacity: Quantity(10000.0),
             efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows = simulator
             .apply(Flow { import: Quantity(500.0), export: Quantity(1000.0) }, Quantity(1.0));
@@ -162,6 +233,9 @@ mod tests {
             residual_energy: Quantity(10000.0),
             capacity: Quantity(10000.0),
             efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
         };
         let flows = simulator
             .apply(Flow { import: Quantity(1000.0), export: Quantity(500.0) }, Quantity(1.0));
@@ -169,4 +243,41 @@ mod tests {
         assert_eq!(flows.external.export, Quantity(500.0));
         assert_eq!(simulator.residual_energy, Quantity(10000.0));
     }
+
+    /// Verify that the power under the minimal operating power is declined.
+    #[test]
+    fn min_operating_power() {
+        let mut simulator = Simulator {
+            residual_energy: Quantity(5000.0),
+            capacity: Quantity(10000.0),
+            efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow { import: Quantity(150.0), export: Quantity(50.0) },
+            max_power: UNLIMITED_POWER,
+            derating: NO_DERATING,
+        };
+        let flows =
+            simulator.apply(Flow { import: Quantity(60.0), export: Quantity(60.0) }, Quantity(1.0));
+        assert_eq!(flows.external.import, Quantity::ZERO);
+        assert_eq!(flows.external.export, Quantity(60.0));
+        assert_eq!(simulator.residual_energy, Quantity(4940.0));
+    }
+
+    /// Verify that the charging slows down near the top.
+    #[test]
+    fn charging_derating() -> Result {
+        let points: [derating::Point; 2] = ["90:100".parse()?, "100:20".parse()?];
+        let mut simulator = Simulator {
+            residual_energy: Quantity(9500.0),
+            capacity: Quantity(10000.0),
+            efficiency: IDEAL_EFFICIENCY,
+            min_power: Flow::ZERO,
+            max_power: Flow { import: Quantity(1000.0), export: Quantity(1000.0) },
+            derating: Flow { import: derating::Curve(&points), export: derating::Curve(&[]) },
+        };
+        let flows =
+            simulator.apply(Flow { import: Quantity(1000.0), export: Watts::ZERO }, Quantity(0.25));
+        assert!((flows.external.import.0 - 150.0).abs() < 1e-9);
+        assert!((simulator.residual_energy.0 - 9650.0).abs() < 1e-9);
+        Ok(())
+    }
 }
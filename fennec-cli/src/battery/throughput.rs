@@ -0,0 +1,131 @@
+use chrono::{DateTime, Datelike, Local, TimeDelta};
+use musli::{Decode, Encode};
+use serde::Serialize;
+
+use crate::{
+    energy::Flow,
+    quantity::energy::{DecawattHours, KilowattHours},
+};
+
+/// Calendar year-to-date battery throughput, counted from the inverter's lifetime totals.
+///
+/// Persisted along with the energy profile.
+#[must_use]
+#[derive(Clone, Default, Encode, Decode)]
+pub struct Throughput {
+    #[musli(Binary, name = 1)]
+    baseline: Option<Baseline>,
+}
+
+/// Lifetime totals at the start of the counting.
+#[derive(Copy, Clone, Encode, Decode)]
+struct Baseline {
+    #[musli(Binary, name = 1)]
+    #[musli(with = crate::ops::musli::chrono)]
+    since: DateTime<Local>,
+
+    #[musli(Binary, name = 2)]
+    total_grid_flow: Flow<DecawattHours>,
+}
+
+#[must_use]
+#[derive(Copy, Clone, Serialize)]
+pub struct YearToDate {
+    /// Since when the throughput is counted: the first reading in the year.
+    pub since: DateTime<Local>,
+
+    pub charged: KilowattHours,
+    pub discharged: KilowattHours,
+
+    /// Discharged energy extrapolated over a whole year at the current pace,
+    /// or [`None`] if it has not been counted for long enough.
+    pub annualized_discharged: Option<KilowattHours>,
+}
+
+impl Throughput {
+    const YEAR: TimeDelta = TimeDelta::days(365);
+
+    /// Minimal counting span to extrapolate over the year.
+    const MIN_SPAN: TimeDelta = TimeDelta::days(1);
+
+    /// Start counting anew on a new year, or when the totals went backwards,
+    /// for example, after the inverter got replaced.
+    pub fn update(&mut self, now: DateTime<Local>, total_grid_flow: Flow<DecawattHours>) {
+        let should_reset = self.baseline.is_none_or(|baseline| {
+            (baseline.since.year() != now.year())
+                || (total_grid_flow.import < baseline.total_grid_flow.import)
+                || (total_grid_flow.export < baseline.total_grid_flow.export)
+        });
+        if should_reset {
+            self.baseline = Some(Baseline { since: now, total_grid_flow });
+        }
+    }
+
+    /// Throughput since the baseline, expects the totals to have passed [`Throughput::update`].
+    pub fn year_to_date(
+        &self,
+        now: DateTime<Local>,
+        total_grid_flow: Flow<DecawattHours>,
+    ) -> Option<YearToDate> {
+        let baseline = self.baseline?;
+        let flow = total_grid_flow - baseline.total_grid_flow;
+        let discharged: KilowattHours = flow.export.rescale();
+        let span = now - baseline.since;
+        Some(YearToDate {
+            since: baseline.since,
+            charged: flow.import.rescale(),
+            discharged,
+            annualized_discharged: (span >= Self::MIN_SPAN)
+                .then(|| discharged * (Self::YEAR.as_seconds_f64() / span.as_seconds_f64())),
+        })
+    }
+}
+
+impl YearToDate {
+    /// How much faster than the yearly budget allows the battery is discharging.
+    ///
+    /// Returns [`None`] until the throughput has been counted for long enough.
+    pub fn pace(&self, yearly_budget: KilowattHours) -> Option<f64> {
+        Some(self.annualized_discharged? / yearly_budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn year_to_date_ok() {
+        let mut throughput = Throughput::default();
+        let since = Local.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+        throughput.update(since, Flow { import: Quantity(1000), export: Quantity(900) });
+
+        let now = since + TimeDelta::days(73);
+        let totals = Flow { import: Quantity(2100), export: Quantity(1900) };
+        throughput.update(now, totals);
+        let year_to_date = throughput.year_to_date(now, totals).unwrap();
+        assert_eq!(year_to_date.since, since);
+        assert!((year_to_date.charged.0 - 11.0).abs() < 1e-9);
+        assert!((year_to_date.discharged.0 - 10.0).abs() < 1e-9);
+        assert!((year_to_date.annualized_discharged.unwrap().0 - 50.0).abs() < 1e-9);
+        assert!((year_to_date.pace(Quantity(25.0)).unwrap() - 2.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn update_resets_on_new_year() {
+        let mut throughput = Throughput::default();
+        let since = Local.with_ymd_and_hms(2026, 12, 31, 23, 0, 0).unwrap();
+        throughput.update(since, Flow { import: Quantity(1000), export: Quantity(900) });
+
+        let now = since + TimeDelta::hours(2);
+        let totals = Flow { import: Quantity(1100), export: Quantity(1000) };
+        throughput.update(now, totals);
+        let year_to_date = throughput.year_to_date(now, totals).unwrap();
+        assert_eq!(year_to_date.since, now);
+        assert!(year_to_date.discharged.0.abs() < 1e-9);
+        assert!(year_to_date.annualized_discharged.is_none());
+    }
+}
@@ -1,8 +1,26 @@
-use std::fmt::{Display, Formatter};
+use std::{
+    fmt::{Display, Formatter},
+    range::RangeInclusive,
+};
+
+use crate::quantity::{Quantity, ratios::Percentage};
 
 /// Ordered by priority: least battery action first.
 /// It matters when the corresponding solution losses are similar.
-#[derive(Debug, Hash, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, clap::ValueEnum)]
+#[derive(
+    Debug,
+    Hash,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    clap::ValueEnum,
+    serde::Deserialize,
+    serde::Serialize,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum WorkingMode {
     /// Do not do anything.
     Idle,
@@ -23,6 +41,51 @@ pub enum WorkingMode {
     Discharge,
 }
 
+impl WorkingMode {
+    /// Forced modes run at a fixed power regardless of the actual consumption.
+    pub const fn is_forced(self) -> bool {
+        matches!(self, Self::Charge | Self::Discharge)
+    }
+
+    /// Modes which let the energy flow into the grid: forced discharging does so directly,
+    /// while idling and compensating leave the excess solar power to the grid.
+    pub const fn is_feeding_in(self) -> bool {
+        matches!(self, Self::Idle | Self::Compensate | Self::Discharge)
+    }
+
+    /// Narrow the allowed state-of-charge down to what the plan expects by the end of the step:
+    /// both discharging and forced charging stop at the planned state-of-charge.
+    ///
+    /// This way, the battery does not drain the energy the plan keeps for later,
+    /// nor buys more than planned, even if the actual consumption deviates.
+    /// Solar charging is not capped, since the excess energy would be fed in otherwise.
+    ///
+    /// The minimum never exceeds the current state-of-charge, though: many inverters
+    /// would force-charge from the grid to reach it, while the plan expects the solar to.
+    #[expect(clippy::cast_possible_truncation)]
+    #[expect(clippy::cast_sign_loss)]
+    pub fn planned_soc_limits(
+        self,
+        allowed_soc: RangeInclusive<Percentage>,
+        planned_soc: f64,
+        current_soc: Percentage,
+    ) -> RangeInclusive<Percentage> {
+        let clamp = |soc: f64| {
+            Quantity(soc.clamp(0.0, 100.0) as u8).clamp(allowed_soc.start, allowed_soc.last)
+        };
+        match self {
+            Self::Compensate | Self::SelfUse | Self::Discharge => RangeInclusive {
+                start: clamp(planned_soc.floor()).min(current_soc.max(allowed_soc.start)),
+                last: allowed_soc.last,
+            },
+            Self::Charge => {
+                RangeInclusive { start: allowed_soc.start, last: clamp(planned_soc.ceil()) }
+            }
+            Self::Idle | Self::Harness => allowed_soc,
+        }
+    }
+}
+
 impl Display for WorkingMode {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let text = match self {
@@ -36,3 +99,36 @@ impl Display for WorkingMode {
         text.fmt(f)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALLOWED_SOC: RangeInclusive<Percentage> =
+        RangeInclusive { start: Quantity(10), last: Quantity(100) };
+
+    #[test]
+    fn planned_soc_limits_ok() {
+        let limits = WorkingMode::SelfUse.planned_soc_limits(ALLOWED_SOC, 42.7, Quantity(50));
+        assert_eq!(limits, RangeInclusive { start: Quantity(42), last: Quantity(100) });
+        let limits = WorkingMode::Charge.planned_soc_limits(ALLOWED_SOC, 80.2, Quantity(50));
+        assert_eq!(limits, RangeInclusive { start: Quantity(10), last: Quantity(81) });
+        let limits = WorkingMode::Harness.planned_soc_limits(ALLOWED_SOC, 50.0, Quantity(50));
+        assert_eq!(limits, ALLOWED_SOC);
+    }
+
+    #[test]
+    fn planned_soc_limits_stay_allowed() {
+        let limits = WorkingMode::Discharge.planned_soc_limits(ALLOWED_SOC, 3.0, Quantity(5));
+        assert_eq!(limits, ALLOWED_SOC);
+        let limits = WorkingMode::Charge.planned_soc_limits(ALLOWED_SOC, 100.4, Quantity(50));
+        assert_eq!(limits, ALLOWED_SOC);
+    }
+
+    #[test]
+    fn planned_soc_limits_capped_at_current() {
+        // The plan expects the solar to charge the battery from 30% to 60%:
+        let limits = WorkingMode::SelfUse.planned_soc_limits(ALLOWED_SOC, 60.0, Quantity(30));
+        assert_eq!(limits, RangeInclusive { start: Quantity(30), last: Quantity(100) });
+    }
+}
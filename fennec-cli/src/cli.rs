@@ -1,12 +1,55 @@
-use std::{net::IpAddr, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    net::IpAddr,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::NaiveDate;
+use clap::{CommandFactory, FromArgMatches, Parser};
 
 use crate::{
-    api::{Connections, heartbeat, home_assistant, homewizard, mini_qube},
+    api::{
+        Connections,
+        deye,
+        dsmr,
+        eastron,
+        easy_energy,
+        frank_energie,
+        heartbeat,
+        home_assistant,
+        homewizard,
+        http,
+        inverter,
+        inverter::Inverter,
+        meter,
+        meter::Meter,
+        mini_qube,
+        modbus,
+        notify,
+        real_time_price,
+        shelly,
+        victron,
+        webhook,
+        zonneplan,
+    },
     battery,
     energy,
+    ev,
+    heat_pump,
     math::smoothing::HalfLife,
     prelude::*,
-    quantity::{Zero, ratios::Percentage, time::Hours},
+    quantity::{
+        Quantity,
+        Zero,
+        currency::{Currency, Mills},
+        energy::WattHours,
+        ratios::Percentage,
+        time::Hours,
+    },
 };
 
 /// Root CLI arguments.
@@ -17,6 +60,52 @@ pub struct Args {
     #[clap(long = "sentry-dsn", env = "SENTRY_DSN")]
     pub sentry_dsn: Option<String>,
 
+    /// Probe the configured connections once and exit, instead of running the engine.
+    #[clap(long)]
+    pub check: bool,
+
+    /// Simulate the working mode schedule from the JSON file under the current conditions,
+    /// log its costs next to the optimal plan, and exit – without touching the battery.
+    #[clap(long = "what-if", value_name = "SCENARIO_FILE", conflicts_with = "check")]
+    pub what_if: Option<PathBuf>,
+
+    /// Fetch the day prices from all the providers, print them side by side, and exit.
+    #[clap(
+        long = "compare-providers",
+        value_name = "YYYY-MM-DD",
+        conflicts_with_all = ["check", "what_if"],
+    )]
+    pub compare_providers: Option<NaiveDate>,
+
+    /// Export the journaled executions as CSV into the file, and exit –
+    /// to analyze the measurements offline with pandas or DuckDB.
+    #[clap(
+        long = "export-executions",
+        value_name = "CSV_FILE",
+        conflicts_with_all = ["check", "what_if", "compare_providers"],
+    )]
+    pub export_executions: Option<PathBuf>,
+
+    /// How far back to export the executions.
+    #[clap(
+        long = "export-period",
+        default_value = "30d",
+        value_parser = humantime::parse_duration,
+        requires = "export_executions",
+    )]
+    pub export_period: Duration,
+
+    /// Send the Zonneplan login link to the account e-mail, wait for it to be opened,
+    /// store the token, and exit.
+    #[clap(
+        long = "zonneplan-login",
+        conflicts_with_all = ["check", "what_if", "compare_providers", "export_executions"],
+    )]
+    pub zonneplan_login: bool,
+
+    #[clap(flatten)]
+    pub config_file: ConfigFileArgs,
+
     #[clap(flatten)]
     pub bind: BindArgs,
 
@@ -27,6 +116,152 @@ pub struct Args {
     pub engine: EngineArgs,
 }
 
+impl Args {
+    /// Load the config file, if any, and then parse the arguments on top of it.
+    ///
+    /// With a site selected, its overlay file gets loaded first, taking precedence over the base file.
+    pub fn parse_with_config_file() -> Result<Self> {
+        let environment = std::env::vars_os().map(|(key, _)| key).collect();
+        let matches = Self::command().ignore_errors(true).get_matches();
+        let config_file = ConfigFileArgs::from_arg_matches(&matches)?;
+        if let Some(path) = &config_file.path {
+            if let Some(site) = &config_file.site {
+                Self::load_config_file(&ConfigFileArgs::site_path(path, site))?;
+            }
+            Self::load_config_file(path)?;
+        }
+        let mut args = Self::parse();
+        args.config_file.environment = Arc::new(environment);
+        if let Some(path) = &mut args.config_file.path {
+            // Entering the site directory must not break the reload:
+            *path = std::path::absolute(&*path)?;
+        }
+        Ok(args)
+    }
+
+    /// Parse the arguments anew with the config file re-read, so that the settings can be reloaded
+    /// without restarting.
+    ///
+    /// The values loaded from the file at the start get replaced with its current contents,
+    /// while the flags and the actual environment still take precedence over the file.
+    #[instrument(skip_all)]
+    pub fn reparse_with_config_file(config_file: &ConfigFileArgs) -> Result<Self> {
+        let mut settings = HashMap::new();
+        if let Some(path) = &config_file.path {
+            settings.extend(Self::read_config_file(path)?);
+            if let Some(site) = &config_file.site {
+                settings.extend(Self::read_config_file(&ConfigFileArgs::site_path(path, site))?);
+            }
+        }
+        let command = Self::command().mut_args(|arg| {
+            let Some(key) = arg.get_env().map(OsStr::to_os_string) else {
+                return arg;
+            };
+            if config_file.environment.contains(&key) {
+                return arg;
+            }
+            let arg = arg.env(None::<&'static str>);
+            match settings.get(key.to_string_lossy().as_ref()) {
+                Some(value) => arg.default_value(value.clone()),
+                None => arg,
+            }
+        });
+        let mut args = Self::from_arg_matches(&command.try_get_matches()?)?;
+        args.config_file = config_file.clone();
+        info!("reloaded the config file");
+        Ok(args)
+    }
+
+    /// Validate the config file keys and set them as the environment variables,
+    /// unless already set – so that the actual environment and flags take precedence.
+    #[instrument]
+    fn load_config_file(path: &Path) -> Result {
+        Self::read_config_file(path)?;
+        dotenvy::from_path(path)?;
+        info!("loaded the config file");
+        Ok(())
+    }
+
+    /// Read the config file settings, validating the keys.
+    fn read_config_file(path: &Path) -> Result<Vec<(String, String)>> {
+        let command = Self::command();
+        let known_keys: HashSet<&OsStr> =
+            command.get_arguments().filter_map(clap::Arg::get_env).collect();
+        let items = dotenvy::from_path_iter(path)
+            .with_context(|| format!("failed to read the config file `{}`", path.display()))?;
+        let mut settings = Vec::new();
+        for item in items {
+            let (key, value) = item
+                .with_context(|| format!("failed to parse the config file `{}`", path.display()))?;
+            ensure!(
+                known_keys.contains(OsStr::new(&key)),
+                "unknown setting `{key}` in `{}`, see `--help` for the environment variable names",
+                path.display(),
+            );
+            settings.push((key, value));
+        }
+        Ok(settings)
+    }
+}
+
+/// Settings file in the `.env` format.
+#[derive(Clone, clap::Args)]
+pub struct ConfigFileArgs {
+    /// File with the `KEY=value` settings, one per line.
+    ///
+    /// The keys are the environment variable names listed in this help.
+    /// The actual environment variables and flags take precedence over the file.
+    /// On SIGHUP, the polling intervals, grid meter, and heartbeat URLs get reloaded from the file.
+    #[clap(long = "config-file", env = "CONFIG_FILE")]
+    pub path: Option<PathBuf>,
+
+    /// Site name to run for, when a single install serves multiple installations.
+    ///
+    /// The site overlay file sits next to the config file: `fennec.env` → `fennec.cabin.env`.
+    /// The learned state is kept in the `sites/<name>` directory, so the sites do not mix up.
+    #[clap(long = "site", env = "SITE", value_parser = parse_site)]
+    pub site: Option<String>,
+
+    /// Environment variable names present before loading the config file.
+    #[clap(skip)]
+    environment: Arc<HashSet<OsString>>,
+}
+
+impl ConfigFileArgs {
+    fn site_path(path: &Path, site: &str) -> PathBuf {
+        let mut site_path = path.with_extension(site);
+        if let Some(extension) = path.extension() {
+            site_path.add_extension(extension);
+        }
+        site_path
+    }
+
+    /// Switch into the site state directory, creating it if needed.
+    #[instrument(skip_all, fields(site = ?self.site))]
+    pub fn enter_site_directory(&self) -> Result {
+        if let Some(site) = &self.site {
+            let directory = Path::new("sites").join(site);
+            std::fs::create_dir_all(&directory)
+                .with_context(|| format!("failed to create `{}`", directory.display()))?;
+            std::env::set_current_dir(&directory)
+                .with_context(|| format!("failed to enter `{}`", directory.display()))?;
+            info!(directory = %directory.display(), "entered the site directory");
+        }
+        Ok(())
+    }
+}
+
+fn parse_site(value: &str) -> Result<String> {
+    ensure!(
+        !value.is_empty()
+            && value
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "-_".contains(character)),
+        "site name must consist of letters, digits, dashes, and underscores",
+    );
+    Ok(value.to_string())
+}
+
 #[derive(clap::Args)]
 pub struct EngineArgs {
     #[clap(flatten)]
@@ -35,12 +270,107 @@ pub struct EngineArgs {
     #[clap(long, env = "INTERVAL", default_value = "5s", value_parser = humantime::parse_duration)]
     pub interval: Duration,
 
+    /// Maximum number of engine iterations allowed to fail in a row before giving up.
+    #[clap(long, env = "MAX_CONSECUTIVE_FAILURES", default_value = "60")]
+    pub max_consecutive_failures: usize,
+
+    /// For how long to keep planning on the last successful battery reading,
+    /// when the battery is unreachable. Zero disables the fallback.
+    #[clap(
+        long = "battery-fallback-max-age",
+        env = "BATTERY_FALLBACK_MAX_AGE",
+        default_value = "15m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub battery_fallback_max_age: Duration,
+
+    /// State-of-charge to subtract from the last successful battery reading on the fallback,
+    /// so that the plan does not count on the energy which may have already been spent.
+    #[clap(
+        long = "battery-fallback-soc-haircut",
+        env = "BATTERY_FALLBACK_SOC_HAIRCUT",
+        default_value = "5"
+    )]
+    pub battery_fallback_soc_haircut: Percentage,
+
+    /// Number of threads to solve the optimization with, defaults to the number of CPUs.
+    #[clap(long, env = "THREADS")]
+    pub threads: Option<NonZeroUsize>,
+
+    /// Energy quantization step of the optimization in watt-hours.
+    ///
+    /// Coarser step solves proportionally faster at the cost of slightly less optimal plans.
+    #[clap(long = "quantum-watt-hours", env = "QUANTUM_WATT_HOURS", default_value = "1")]
+    pub quantum: NonZeroUsize,
+
+    /// Warm-start the optimizer from the previous plan, solving only the energy levels within
+    /// this margin around the previous trajectory, percentage of the battery capacity.
+    ///
+    /// Narrower margin solves faster, but may miss the plans far off the previous one.
+    /// The equally good plans stick to the previous decisions either way.
+    #[clap(long = "warm-start-margin", env = "WARM_START_MARGIN")]
+    pub warm_start_margin: Option<Percentage>,
+
+    /// Merge the plan's working mode switches into the preceding decisions as long as each merge
+    /// costs less than this, in ₥ (thousandths of ¤).
+    ///
+    /// Fewer switches need fewer battery schedule slots, at the cost of a slightly worse plan.
+    #[clap(long = "smoothing-threshold", env = "SMOOTHING_THRESHOLD")]
+    pub smoothing_threshold: Option<Mills>,
+
+    /// Number of the household load scenarios to optimize the expected cost over.
+    ///
+    /// The learned energy balance gets scaled by the normally distributed factors,
+    /// so that the plan accounts for the battery limits on heavier and lighter days.
+    /// Solving takes proportionally longer.
+    #[clap(long = "n-load-scenarios", env = "N_LOAD_SCENARIOS", default_value = "1")]
+    pub n_load_scenarios: NonZeroUsize,
+
+    /// Relative standard deviation of the household load in the scenarios.
+    #[clap(long = "load-deviation", env = "LOAD_DEVIATION", default_value = "20")]
+    pub load_deviation: Percentage,
+
+    /// Household load percentile to plan against, learned per hour for the weekdays and weekends.
+    ///
+    /// The higher percentile makes the plans more cautious about the heavier consumption.
+    /// Until the hour's band has been learned, the plan falls back to the learned mean.
+    #[clap(long = "load-percentile", env = "LOAD_PERCENTILE", default_value = "mean")]
+    pub load_percentile: energy::LoadPercentile,
+
+    /// Check every new plan against the physical invariants and fail the iteration on a violation.
+    #[clap(long, env = "VALIDATE")]
+    pub validate: bool,
+
     #[clap(long, env = "ENERGY_PROVIDER")]
     pub energy_provider: energy::Provider,
 
+    #[clap(flatten)]
+    pub price_sanity: energy::PriceSanity,
+
+    /// Time-dependent grid transport costs on top of the supplier import prices, in ¤/kWh.
+    ///
+    /// Comma-separated daily windows: `07:00-09:00=0.05,17:00-21:00=0.07`.
+    #[clap(long = "transport-costs", env = "TRANSPORT_COSTS", value_delimiter = ',')]
+    pub transport_costs: Vec<energy::TransportCost>,
+
+    #[clap(flatten)]
+    pub billing: energy::Billing,
+
+    #[clap(flatten)]
+    pub currency: Currency,
+
     #[clap(flatten)]
     pub energy_profile: EnergyProfileArgs,
 
+    #[clap(flatten)]
+    pub real_time_price: RealTimePriceArgs,
+
+    #[clap(flatten)]
+    pub ev: ev::Args,
+
+    #[clap(flatten)]
+    pub heat_pump: heat_pump::Args,
+
     /// Minimal state-of-charge required by the end of the price horizon, percentage.
     #[clap(
         long = "min-final-soc",
@@ -49,6 +379,25 @@ pub struct EngineArgs {
     )]
     pub min_final_soc: Percentage,
 
+    /// Number of upcoming 15-minute battery schedule slots to keep in sync with the plan.
+    ///
+    /// Only the changed slots get written to the battery.
+    #[clap(
+        long = "n-schedule-slots",
+        env = "N_SCHEDULE_SLOTS",
+        default_value = "1",
+        value_parser = clap::value_parser!(u8).range(1..=96),
+    )]
+    pub n_schedule_slots: u8,
+
+    /// Narrow the minimum and maximum state-of-charge of each written slot down to the plan,
+    /// instead of writing the same allowed range into every slot.
+    ///
+    /// The battery then stops discharging where the plan expects it to,
+    /// keeping the energy for the later, more expensive hours.
+    #[clap(long = "write-planned-soc", env = "WRITE_PLANNED_SOC")]
+    pub write_planned_soc: bool,
+
     /// Do not push schedule to the device, dry run.
     #[clap(long, alias = "scout", env = "DRY_RUN")]
     pub dry_run: bool,
@@ -86,8 +435,34 @@ pub struct EnergyProfileArgs {
     pub battery_efficiency_half_life_factor: f64,
 }
 
-/// Web UI binding arguments.
+/// Rolling re-optimization against the near-real-time prices, for example, imbalance settlement.
 #[derive(Copy, Clone, clap::Args)]
+pub struct RealTimePriceArgs {
+    /// How often to fetch the real-time price.
+    #[clap(
+        name = "real_time_price_interval",
+        long = "real-time-price-interval",
+        env = "REAL_TIME_PRICE_INTERVAL",
+        default_value = "5m",
+        value_parser = humantime::parse_duration,
+    )]
+    pub interval: Duration,
+
+    /// For how long from now the real-time price overrides the day-ahead prices.
+    ///
+    /// Only this short horizon gets re-optimized on every price update,
+    /// the rest of the solution space stays intact.
+    #[clap(
+        long = "real-time-price-horizon",
+        env = "REAL_TIME_PRICE_HORIZON",
+        default_value = "1h",
+        value_parser = humantime::parse_duration,
+    )]
+    pub horizon: Duration,
+}
+
+/// Web UI binding arguments.
+#[derive(Clone, clap::Args)]
 pub struct BindArgs {
     /// Web UI binding address.
     #[clap(long = "bind-address", env = "BIND_ADDRESS", default_value = "::")]
@@ -96,39 +471,222 @@ pub struct BindArgs {
     /// Web UI binding port.
     #[clap(long = "bind-port", env = "BIND_PORT", default_value = "80")]
     pub port: u16,
+
+    /// Bearer token to require on the JSON API, metrics, and calendar, which are open if not set.
+    ///
+    /// The calendar subscription passes it in the `token` query parameter.
+    #[clap(long = "api-token", env = "API_TOKEN")]
+    pub api_token: Option<String>,
 }
 
 #[derive(clap::Args)]
 pub struct ConnectionArgs {
-    /// P1 meter measurement URL.
+    /// Grid meter kind.
+    #[clap(long, env = "GRID_METER", default_value = "home-wizard")]
+    pub grid_meter: meter::Kind,
+
+    /// HomeWizard P1 meter measurement URL.
     #[clap(long = "grid-measurement-url", env = "GRID_MEASUREMENT_URL")]
-    pub grid_measurement_url: homewizard::Url,
+    pub grid_measurement_url: Option<homewizard::Url>,
+
+    /// DSMR P1 port TCP address, for example, of `ser2net` or a network P1 dongle.
+    #[clap(long = "dsmr-address", env = "DSMR_ADDRESS")]
+    pub dsmr_address: Option<String>,
+
+    #[clap(flatten)]
+    pub shelly: shelly::Args,
+
+    #[clap(flatten)]
+    pub eastron: eastron::Args,
 
-    /// Battery Modbus address. Only Fox ESS MiniQube is supported.
+    #[clap(flatten)]
+    pub topology: energy::TopologyArgs,
+
+    /// Battery inverter kind.
+    #[clap(long, env = "INVERTER", default_value = "mini-qube")]
+    pub inverter: inverter::Kind,
+
+    /// Battery inverter Modbus address, required for all but the HomeWizard batteries.
     #[clap(long = "battery-address", env = "BATTERY_ADDRESS")]
-    pub battery_address: String,
+    pub battery_address: Option<String>,
+
+    /// Battery inverter Modbus transport.
+    #[clap(long = "battery-transport", env = "BATTERY_TRANSPORT", default_value = "tcp")]
+    pub battery_transport: modbus::Transport,
+
+    /// Battery design capacity in watt-hours, for the inverters which do not report it.
+    #[clap(
+        long = "battery-design-capacity-watt-hours",
+        env = "BATTERY_DESIGN_CAPACITY_WATT_HOURS",
+        default_value = "10000"
+    )]
+    pub battery_design_capacity: WattHours<u32>,
+
+    #[clap(flatten)]
+    pub victron: victron::Args,
 
-    /// Heartbeat URL.
+    #[clap(flatten)]
+    pub homewizard_battery: homewizard::battery::Args,
+
+    /// Heartbeat URL, pinged around every engine iteration.
+    ///
+    /// Healthchecks.io-style `/start` and `/fail` are pinged too, and the success carries
+    /// a summary of the current plan.
     #[clap(long = "heartbeat-url", env = "HEARTBEAT_URL")]
     pub heartbeat_url: Option<reqwest::Url>,
 
+    /// Heartbeat URL, pinged only when the battery has been read successfully.
+    #[clap(long = "battery-heartbeat-url", env = "BATTERY_HEARTBEAT_URL")]
+    pub battery_heartbeat_url: Option<reqwest::Url>,
+
+    /// Heartbeat URL, pinged only when the grid meter has been read successfully.
+    #[clap(long = "grid-heartbeat-url", env = "GRID_HEARTBEAT_URL")]
+    pub grid_heartbeat_url: Option<reqwest::Url>,
+
     /// Home Assistant REST API entity state URL.
     ///
     /// The URL must have the fragment set to the bearer token.
     /// Example: `https://homeassistant.local/api/states/sensor.custom_fennec_working_mode#0123...6789`.
     #[clap(long, env = "HOME_ASSISTANT_WORKING_MODE_URL")]
     pub home_assistant_working_mode_url: Option<reqwest::Url>,
+
+    /// Home Assistant REST API battery temperature sensor URL, in degrees Celsius.
+    ///
+    /// The URL must have the fragment set to the bearer token.
+    /// The battery efficiency is then learned per 5°C temperature band.
+    #[clap(long, env = "BATTERY_TEMPERATURE_URL")]
+    pub battery_temperature_url: Option<reqwest::Url>,
+
+    /// Home Assistant REST API entity state URL for the heat pump SG-Ready signal.
+    ///
+    /// The URL must have the fragment set to the bearer token, same as for the working mode.
+    /// The state is one of `block`, `normal`, `recommended`, or `forced`.
+    #[clap(long, env = "HOME_ASSISTANT_HEAT_PUMP_URL")]
+    pub home_assistant_heat_pump_url: Option<reqwest::Url>,
+
+    /// Home Assistant REST API states URL to publish the plan to as the `sensor.fennec_*` entities.
+    ///
+    /// The URL must have the fragment set to the bearer token.
+    /// Example: `https://homeassistant.local/api/states/#0123...6789`.
+    #[clap(long, env = "HOME_ASSISTANT_STATES_URL")]
+    pub home_assistant_states_url: Option<reqwest::Url>,
+
+    /// Near-real-time price feed URL, see [`real_time_price::Client`].
+    #[clap(long, env = "REAL_TIME_PRICE_URL")]
+    pub real_time_price_url: Option<reqwest::Url>,
+
+    /// URL to post the EV charging plan to, whenever it gets re-planned.
+    #[clap(long, env = "EV_WEBHOOK_URL")]
+    pub ev_webhook_url: Option<reqwest::Url>,
+
+    #[clap(flatten)]
+    pub static_tariff: energy::StaticTariff,
+
+    #[clap(flatten)]
+    pub easy_energy: easy_energy::Args,
+
+    #[clap(flatten)]
+    pub zonneplan: zonneplan::Args,
+
+    #[clap(flatten)]
+    pub notify: notify::Args,
+
+    #[clap(flatten)]
+    pub http: http::Args,
 }
 
 impl ConnectionArgs {
     pub fn connect(self) -> Result<Connections> {
+        let design_capacity = Quantity(self.battery_design_capacity.0 / 10);
+        let battery = || {
+            let address =
+                self.battery_address.context("the inverter requires the Modbus address")?;
+            Ok::<_, Error>(modbus::Client::new(address, self.battery_transport))
+        };
         Ok(Connections {
-            grid_measurement: self.grid_measurement_url.client()?,
-            battery: Arc::new(mini_qube::Client::new(self.battery_address)),
-            heartbeat: heartbeat::Client::new(self.heartbeat_url)?,
+            grid_measurement: match self.grid_meter {
+                meter::Kind::HomeWizard => Meter::HomeWizard(
+                    self.grid_measurement_url
+                        .context("HomeWizard requires the grid measurement URL")?
+                        .client(self.http.client_builder()?)?,
+                ),
+                meter::Kind::Dsmr => Meter::Dsmr(dsmr::Client::new(
+                    self.dsmr_address.context("DSMR requires the P1 port address")?,
+                )),
+                meter::Kind::Shelly => Meter::Shelly(shelly::Client::new(
+                    self.shelly.url.context("Shelly requires the base URL")?,
+                    self.shelly.profile,
+                    self.http.client_builder()?,
+                )?),
+                meter::Kind::Eastron => Meter::Eastron(eastron::Client::new(self.eastron)?),
+            },
+            topology: self.topology.connect(&self.http)?,
+            battery: match self.inverter {
+                inverter::Kind::MiniQube => Inverter::MiniQube(mini_qube::Client::new(battery()?)),
+                inverter::Kind::Victron => Inverter::Victron(victron::Client::new(
+                    battery()?,
+                    &self.victron,
+                    design_capacity,
+                )),
+                inverter::Kind::Deye => {
+                    Inverter::Deye(deye::Client::new(battery()?, design_capacity))
+                }
+                inverter::Kind::HomeWizard => {
+                    Inverter::HomeWizard(homewizard::battery::Client::new(
+                        self.homewizard_battery,
+                        design_capacity,
+                        &self.http,
+                    )?)
+                }
+            },
+            heartbeat: heartbeat::Client::new(self.heartbeat_url, self.http.client_builder()?)?,
+            battery_heartbeat: heartbeat::Client::new(
+                self.battery_heartbeat_url,
+                self.http.client_builder()?,
+            )?,
+            grid_heartbeat: heartbeat::Client::new(
+                self.grid_heartbeat_url,
+                self.http.client_builder()?,
+            )?,
             home_assistant_working_mode: home_assistant::StateClient::new(
                 self.home_assistant_working_mode_url,
+                self.http.client_builder()?,
             )?,
+            battery_temperature: home_assistant::SensorClient::new(
+                self.battery_temperature_url,
+                self.http.client_builder()?,
+            )?,
+            home_assistant_heat_pump: home_assistant::StateClient::new(
+                self.home_assistant_heat_pump_url,
+                self.http.client_builder()?,
+            )?,
+            home_assistant_entities: home_assistant::EntitiesClient::new(
+                self.home_assistant_states_url,
+                self.http.client_builder()?,
+            )?,
+            frank_energie: frank_energie::Api::new(self.http.client_builder()?)?,
+            static_tariff: self.static_tariff,
+            easy_energy: easy_energy::Api::new(self.easy_energy, self.http.client_builder()?)?,
+            zonneplan: zonneplan::Api::new(self.zonneplan, self.http.client_builder()?)?,
+            real_time_price: real_time_price::Client::new(
+                self.real_time_price_url,
+                self.http.client_builder()?,
+            )?,
+            ev_webhook: webhook::Client::new(self.ev_webhook_url, self.http.client_builder()?)?,
+            notify: notify::Client::new(self.notify, self.http.client_builder()?)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    /// Clashing argument IDs and groups only panic at runtime otherwise.
+    #[test]
+    fn debug_assert_ok() {
+        Args::command().debug_assert();
+    }
+}
@@ -1,6 +1,31 @@
 mod balance;
+mod billing;
+mod comparison;
+mod digest;
 mod flow;
+mod load_bands;
+mod price_archive;
+mod price_cache;
 mod profile;
 mod provider;
+mod sanity;
+mod static_tariff;
+mod topology;
+mod transport;
+mod valuation;
 
-pub use self::{balance::Balance, flow::Flow, profile::Profile, provider::Provider};
+pub use self::{
+    balance::Balance,
+    billing::Billing,
+    comparison::compare_providers,
+    digest::rates_digest,
+    flow::Flow,
+    load_bands::{LoadBands, LoadPercentile},
+    profile::{Profile, temperature_band_after},
+    provider::Provider,
+    sanity::PriceSanity,
+    static_tariff::StaticTariff,
+    topology::{Flows, Topology, TopologyArgs},
+    transport::{TransportCost, TransportCosts},
+    valuation::residual_energy_value,
+};
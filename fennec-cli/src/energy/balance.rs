@@ -21,6 +21,8 @@ use crate::{
     derive_more::AddAssign,
     derive_more::Sub,
     derive_more::Add,
+    serde::Serialize,
+    serde::Deserialize,
     Encode,
     Decode,
 )]
@@ -84,6 +86,28 @@ impl Balance<Watts> {
     }
 }
 
+impl Balance<Watts> {
+    /// Add the extra household load on top of the balance.
+    ///
+    /// The load consumes the excess power first, and then the battery steps in within its limits.
+    /// The grid covers the rest.
+    pub fn with_extra_load(mut self, load: Watts, battery_limits: Flow<Watts>) -> Self {
+        let from_grid_export = load.min(self.grid.export);
+        let load = load - from_grid_export;
+        let from_battery_import = load.min(self.battery.import);
+        let load = load - from_battery_import;
+        let from_battery_export =
+            load.min((battery_limits.export - self.battery.export).max(Watts::ZERO));
+        let load = load - from_battery_export;
+
+        self.grid.export -= from_grid_export;
+        self.battery.import -= from_battery_import;
+        self.battery.export += from_battery_export;
+        self.grid.import += load;
+        self
+    }
+}
+
 impl<T> Balance<T> {
     /// Change the battery flow and re-balance the resulting grid flow.
     fn with_battery_flow(mut self, battery_flow: Flow<T>) -> Self
@@ -97,8 +121,9 @@ impl<T> Balance<T> {
     }
 
     /// The invariant represents the quantity that stays constant under any re-balancing.
-    #[cfg(test)]
-    fn invariant(self) -> T
+    ///
+    /// That is the net household deficit, which [`Balance::new`] splits into the flows.
+    pub fn invariant(self) -> T
     where
         T: Add<Output = T> + Sub<Output = T>,
     {
@@ -180,6 +205,24 @@ mod tests {
         assert_eq!(initial.with_battery_flow(expected.battery), expected);
     }
 
+    #[test]
+    fn with_extra_load() {
+        let initial = Balance::<Watts> {
+            battery: Flow { import: Quantity(100.0), export: Quantity(200.0) },
+            grid: Flow { import: Quantity(300.0), export: Quantity(50.0) },
+        };
+        let limits = Flow { import: Quantity(1000.0), export: Quantity(800.0) };
+        let expected = Balance::<Watts> {
+            // Excess power is consumed first, then the battery discharges at its limit:
+            battery: Flow { import: Watts::ZERO, export: Quantity(800.0) },
+            // And the grid covers the remaining 250W:
+            grid: Flow { import: Quantity(550.0), export: Watts::ZERO },
+        };
+        let actual = initial.with_extra_load(Quantity(1000.0), limits);
+        assert_eq!(actual, expected);
+        assert_eq!(actual.invariant(), initial.invariant() + Quantity(1000.0));
+    }
+
     #[test]
     fn battery_export_beyond_grid_import() {
         let initial = Balance::<Watts> {
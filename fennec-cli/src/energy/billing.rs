@@ -0,0 +1,86 @@
+//! How the supply contract bills the exported energy, on top of the provider prices.
+
+use crate::{Schedule, energy, quantity::price::KilowattHourPrice};
+
+#[derive(Copy, Clone, clap::Args)]
+pub struct Billing {
+    /// Net metering («salderen»): the exported energy offsets the imported one
+    /// at the full import price, including the taxes.
+    ///
+    /// It is the per-interval approximation of the annual netting,
+    /// which only holds as long as the annual export does not exceed the import.
+    #[clap(long = "net-metering", env = "NET_METERING")]
+    pub net_metering: bool,
+
+    /// Fixed feed-in compensation in ¤/kWh, instead of the provider's dynamic export price.
+    #[clap(
+        long = "feed-in-compensation",
+        env = "FEED_IN_COMPENSATION",
+        conflicts_with = "net_metering"
+    )]
+    pub feed_in_compensation: Option<KilowattHourPrice>,
+
+    /// Feed-in costs charged by the supplier in ¤/kWh, deducted from the export price.
+    #[clap(long = "feed-in-costs", env = "FEED_IN_COSTS", default_value = "0")]
+    pub feed_in_costs: KilowattHourPrice,
+}
+
+impl Billing {
+    /// Apply the contract to the provider prices.
+    pub fn apply(self, price: energy::Flow<KilowattHourPrice>) -> energy::Flow<KilowattHourPrice> {
+        let export = if self.net_metering {
+            price.import
+        } else {
+            self.feed_in_compensation.unwrap_or(price.export)
+        };
+        energy::Flow { import: price.import, export: export - self.feed_in_costs }
+    }
+
+    pub fn apply_to(self, prices: &mut Schedule<energy::Flow<KilowattHourPrice>>) {
+        for index in 0..prices.len() {
+            let price = prices.get_mut(index);
+            *price = self.apply(*price);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::{Quantity, Zero};
+
+    const PRICE: energy::Flow<KilowattHourPrice> =
+        energy::Flow { import: Quantity(0.25), export: Quantity(0.125) };
+
+    #[test]
+    fn dynamic_ok() {
+        let billing = Billing {
+            net_metering: false,
+            feed_in_compensation: None,
+            feed_in_costs: Quantity(0.0625),
+        };
+        let price = billing.apply(PRICE);
+        assert_eq!(price.import, Quantity(0.25));
+        assert_eq!(price.export, Quantity(0.0625));
+    }
+
+    #[test]
+    fn net_metering_ok() {
+        let billing = Billing {
+            net_metering: true,
+            feed_in_compensation: None,
+            feed_in_costs: Quantity::ZERO,
+        };
+        assert_eq!(billing.apply(PRICE).export, Quantity(0.25));
+    }
+
+    #[test]
+    fn fixed_compensation_ok() {
+        let billing = Billing {
+            net_metering: false,
+            feed_in_compensation: Some(Quantity(0.0625)),
+            feed_in_costs: Quantity::ZERO,
+        };
+        assert_eq!(billing.apply(PRICE).export, Quantity(0.0625));
+    }
+}
@@ -0,0 +1,152 @@
+//! Side-by-side comparison of the day prices from all the providers.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Timelike};
+use clap::ValueEnum;
+use itertools::Itertools;
+
+use crate::{
+    Schedule,
+    api,
+    energy::{Flow, Provider},
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+/// Hourly import prices of a single provider.
+struct Column {
+    provider: String,
+    prices: BTreeMap<DateTime<Local>, f64>,
+}
+
+impl Column {
+    /// Average the slots within each hour, so that the hourly and quarterly providers line up.
+    #[expect(clippy::cast_precision_loss)]
+    fn new(provider: String, prices: &Schedule<Flow<KilowattHourPrice>>) -> Self {
+        let mut hours = BTreeMap::<DateTime<Local>, (f64, usize)>::new();
+        for slot in prices.iter() {
+            let start = slot.interval.start();
+            // Subtracting keeps the repeated hour apart when the DST ends:
+            let hour = start - TimeDelta::minutes(i64::from(start.minute()));
+            let (sum, count) = hours.entry(hour).or_default();
+            *sum += slot.value.import.0;
+            *count += 1;
+        }
+        let prices = hours.into_iter().map(|(hour, (sum, count))| (hour, sum / count as f64));
+        Self { provider, prices: prices.collect() }
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    fn mean(&self) -> f64 {
+        self.prices.values().sum::<f64>() / self.prices.len() as f64
+    }
+
+    /// Difference between the most and least expensive hours.
+    fn spread(&self) -> f64 {
+        let (min, max) =
+            self.prices.values().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), price| {
+                (min.min(*price), max.max(*price))
+            });
+        max - min
+    }
+
+    /// Pearson correlation with the other provider over the common hours.
+    ///
+    /// Returns [`None`] if there are too few common hours, or either of the curves is flat.
+    fn correlation_with(&self, other: &Self) -> Option<f64> {
+        let pairs = self
+            .prices
+            .iter()
+            .filter_map(|(hour, price)| Some((*price, *other.prices.get(hour)?)))
+            .collect_vec();
+        correlation(&pairs)
+    }
+}
+
+#[expect(clippy::cast_precision_loss)]
+fn correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let (covariance, variance_x, variance_y) =
+        pairs.iter().fold((0.0, 0.0, 0.0), |(covariance, variance_x, variance_y), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (dx.mul_add(dy, covariance), dx.mul_add(dx, variance_x), dy.mul_add(dy, variance_y))
+        });
+    let denominator = (variance_x * variance_y).sqrt();
+    (denominator > f64::EPSILON).then(|| covariance / denominator)
+}
+
+/// Fetch the day from every provider and print the hourly import prices side by side,
+/// along with the mean, spread, and correlation with the first provider.
+///
+/// The providers which are not configured or fail are skipped with a warning.
+/// Nothing gets cached nor archived, so this is safe to run next to the engine.
+#[instrument(skip_all, fields(on = ?on))]
+pub async fn compare_providers(connections: &api::Connections, on: NaiveDate) -> Result {
+    let mut columns = Vec::new();
+    for provider in Provider::value_variants() {
+        let name = provider.to_possible_value().unwrap().get_name().to_owned();
+        match provider.get_prices(connections, on).await {
+            Ok(prices) if prices.len() != 0 => columns.push(Column::new(name, &prices)),
+            Ok(_) => warn!(provider = name, "no prices for the day"),
+            Err(error) => warn!(provider = name, "skipping: {error:#}"),
+        }
+    }
+    ensure!(!columns.is_empty(), "none of the providers has returned the prices");
+    println!("{}", render(&columns));
+    Ok(())
+}
+
+fn render(columns: &[Column]) -> String {
+    const WIDTH: usize = 24;
+
+    let mut table = String::new();
+    let mut row = |label: &str, cells: &mut dyn Iterator<Item = String>| {
+        write!(table, "{label:<8}").unwrap();
+        for cell in cells {
+            write!(table, "{cell:>WIDTH$}").unwrap();
+        }
+        table.push('\n');
+    };
+    row("", &mut columns.iter().map(|column| column.provider.clone()));
+    let hours = columns.iter().flat_map(|column| column.prices.keys()).sorted().dedup();
+    for hour in hours {
+        row(
+            &hour.format("%H:%M").to_string(),
+            &mut columns.iter().map(|column| {
+                column.prices.get(hour).map_or_else(String::new, |price| format!("{price:.4}"))
+            }),
+        );
+    }
+    row("mean", &mut columns.iter().map(|column| format!("{:.4}", column.mean())));
+    row("spread", &mut columns.iter().map(|column| format!("{:.4}", column.spread())));
+    row(
+        "corr",
+        &mut columns.iter().map(|column| {
+            column
+                .correlation_with(&columns[0])
+                .map_or_else(String::new, |correlation| format!("{correlation:.3}"))
+        }),
+    );
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn correlation_ok() {
+        let pairs = [(1.0, 2.0), (2.0, 4.0), (3.0, 6.0)];
+        assert!((correlation(&pairs).unwrap() - 1.0).abs() < 1e-9);
+        let pairs = [(1.0, 3.0), (2.0, 2.0), (3.0, 1.0)];
+        assert!((correlation(&pairs).unwrap() + 1.0).abs() < 1e-9);
+        assert!(correlation(&[(1.0, 1.0), (2.0, 1.0)]).is_none());
+        assert!(correlation(&[(1.0, 1.0)]).is_none());
+    }
+}
@@ -0,0 +1,161 @@
+//! Digest of the next day prices, so that the household knows when to run the dishwasher.
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta, Timelike};
+use itertools::Itertools;
+
+use crate::{
+    Schedule,
+    api::notify::Notification,
+    energy::Flow,
+    quantity::{Quantity, price::KilowattHourPrice},
+};
+
+/// Mean import price over the consecutive slots.
+#[derive(Copy, Clone)]
+struct Window {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    mean_price: KilowattHourPrice,
+}
+
+/// Summarize the import prices on the day: the cheapest and the most expensive windows
+/// of the specified duration, followed by the hourly price curve.
+///
+/// Returns [`None`] if there are no prices for the day yet.
+pub fn rates_digest(
+    prices: &Schedule<Flow<KilowattHourPrice>>,
+    on: NaiveDate,
+    window: TimeDelta,
+) -> Option<Notification> {
+    let slots = prices
+        .iter()
+        .filter(|slot| slot.interval.start().date_naive() == on)
+        .map(|slot| (slot.interval.start(), slot.interval.end(), slot.value.import))
+        .collect_vec();
+    if slots.is_empty() {
+        return None;
+    }
+
+    let mut lines = Vec::new();
+    let windows = windows_of(&slots, window).collect_vec();
+    let duration = humantime::format_duration(window.to_std().ok()?);
+    let by_price = |lhs: &Window, rhs: &Window| lhs.mean_price.0.total_cmp(&rhs.mean_price.0);
+    if let Some(cheapest) = windows.iter().copied().min_by(by_price) {
+        lines.push(format!("Cheapest {duration}: {}", format_window(cheapest)));
+    }
+    if let Some(priciest) = windows.iter().copied().max_by(by_price) {
+        lines.push(format!("Priciest {duration}: {}", format_window(priciest)));
+    }
+
+    let hours = hourly_means(&slots);
+    let (min, max) = hours.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), hour| {
+        (min.min(hour.1.0), max.max(hour.1.0))
+    });
+    for (start, price) in hours {
+        lines.push(format!("{} {} {price}", start.format("%H:%M"), bar(price.0, min, max)));
+    }
+
+    Some(Notification {
+        title: format!("Prices for {}", on.format("%a %-d %b")),
+        message: lines.join("\n"),
+    })
+}
+
+/// Windows of the consecutive slots covering at least the duration, in the order of their start.
+fn windows_of(
+    slots: &[(DateTime<Local>, DateTime<Local>, KilowattHourPrice)],
+    duration: TimeDelta,
+) -> impl Iterator<Item = Window> {
+    (0..slots.len()).map_while(move |first| {
+        let start = slots[first].0;
+        let mut weighted_sum = 0.0;
+        for (slot_start, slot_end, price) in &slots[first..] {
+            weighted_sum =
+                price.0.mul_add((*slot_end - *slot_start).as_seconds_f64(), weighted_sum);
+            if *slot_end - start >= duration {
+                let mean_price = weighted_sum / (*slot_end - start).as_seconds_f64();
+                return Some(Window { start, end: *slot_end, mean_price: Quantity(mean_price) });
+            }
+        }
+        None
+    })
+}
+
+/// Average the slots within each hour, so that the quarterly prices do not flood the message.
+#[expect(clippy::cast_precision_loss)]
+fn hourly_means(
+    slots: &[(DateTime<Local>, DateTime<Local>, KilowattHourPrice)],
+) -> Vec<(DateTime<Local>, KilowattHourPrice)> {
+    slots
+        .iter()
+        // Subtracting keeps the repeated hour apart when the DST ends:
+        .chunk_by(|(start, _, _)| *start - TimeDelta::minutes(i64::from(start.minute())))
+        .into_iter()
+        .map(|(hour, slots)| {
+            let (sum, count) = slots
+                .fold((0.0, 0_usize), |(sum, count), (_, _, price)| (sum + price.0, count + 1));
+            (hour, Quantity(sum / count as f64))
+        })
+        .collect()
+}
+
+fn format_window(window: Window) -> String {
+    format!(
+        "{}–{}, {} on average",
+        window.start.format("%H:%M"),
+        window.end.format("%H:%M"),
+        window.mean_price,
+    )
+}
+
+/// Sparkline bar of the price relative to the day range.
+#[expect(clippy::cast_possible_truncation)]
+#[expect(clippy::cast_sign_loss)]
+fn bar(price: f64, min: f64, max: f64) -> char {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let ratio = if max - min > f64::EPSILON { (price - min) / (max - min) } else { 0.0 };
+    BARS[((ratio * 7.0).round() as usize).min(7)]
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{ops::interval::Interval, prelude::*};
+
+    #[test]
+    fn rates_digest_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 9, 0, 0, 0).unwrap();
+        let mut prices = Schedule::new();
+        prices.extend_from_iter((0..24).map(|hour| {
+            let start = start + TimeDelta::hours(hour);
+            let import = match hour {
+                13 | 14 => Quantity(0.05),
+                18 | 19 => Quantity(0.40),
+                _ => Quantity(0.20),
+            };
+            (Interval::new(start, start + TimeDelta::hours(1)), Flow { import, export: import })
+        }))?;
+
+        let yesterday = start.date_naive().pred_opt().unwrap();
+        assert!(rates_digest(&prices, yesterday, TimeDelta::hours(2)).is_none());
+
+        let digest = rates_digest(&prices, start.date_naive(), TimeDelta::hours(2)).unwrap();
+        assert_eq!(digest.title, "Prices for Thu 9 Apr");
+        let mut lines = digest.message.lines();
+        assert_eq!(lines.next(), Some("Cheapest 2h: 13:00–15:00, 0.050 ¤/kWh on average"));
+        assert_eq!(lines.next(), Some("Priciest 2h: 18:00–20:00, 0.400 ¤/kWh on average"));
+        assert_eq!(lines.next(), Some("00:00 ▄ 0.200 ¤/kWh"));
+        assert_eq!(lines.count(), 23);
+        Ok(())
+    }
+
+    #[test]
+    fn bar_ok() {
+        assert_eq!(bar(0.1, 0.1, 0.3), '▁');
+        assert_eq!(bar(0.3, 0.1, 0.3), '█');
+        assert_eq!(bar(0.2, 0.2, 0.2), '▁');
+    }
+}
@@ -2,7 +2,13 @@ use std::ops::{Add, Div, Mul, SubAssign};
 
 use musli::{Decode, Encode};
 
-use crate::quantity::{Zero, currency::Mills, energy::WattHours, price::KilowattHourPrice};
+use crate::quantity::{
+    Zero,
+    currency::Mills,
+    energy::WattHours,
+    power::Watts,
+    price::KilowattHourPrice,
+};
 
 /// Generic bidirectional energy flow.
 #[must_use]
@@ -16,6 +22,8 @@ use crate::quantity::{Zero, currency::Mills, energy::WattHours, price::KilowattH
     derive_more::Sub,
     derive_more::Sum,
     derive_more::AddAssign,
+    serde::Serialize,
+    serde::Deserialize,
     Encode,
     Decode,
 )]
@@ -86,6 +94,13 @@ impl<T: Div<Rhs>, Rhs: Copy> Div<Rhs> for Flow<T> {
     }
 }
 
+impl Flow<Watts> {
+    /// Split the net power: positive is import, negative is export.
+    pub fn from_net(net: Watts) -> Self {
+        Self { import: net.max(Watts::ZERO), export: (-net).max(Watts::ZERO) }
+    }
+}
+
 impl Flow<f64> {
     /// Calculate the round-trip efficiency assuming each direction represents efficiency in that direction.
     pub const fn round_trip(self) -> f64 {
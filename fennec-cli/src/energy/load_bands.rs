@@ -0,0 +1,156 @@
+//! Hourly percentile bands of the household net load, learned separately for the weekdays
+//! and weekends.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Local, TimeDelta, Timelike};
+use musli::{Decode, Encode};
+
+use crate::{
+    math::smoothing::{Exponential, HalfLife},
+    quantity::{Quantity, Zero, power::Watts, time::Hours},
+};
+
+/// Household load percentile for the solver to plan against.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum LoadPercentile {
+    /// Learned mean energy balance, not split by the day of week.
+    Mean,
+
+    /// Lighter than usual load, optimistic plans.
+    P25,
+
+    /// Median load.
+    P50,
+
+    /// Heavier than usual load, cautious plans.
+    P75,
+}
+
+#[derive(Clone, Default, Encode, Decode)]
+pub struct LoadBands {
+    /// Weekday bands by the local hour.
+    #[musli(Binary, name = 1)]
+    weekdays: BTreeMap<u32, Band>,
+
+    /// Weekend bands by the local hour.
+    #[musli(Binary, name = 2)]
+    weekends: BTreeMap<u32, Band>,
+}
+
+impl LoadBands {
+    /// Net load at the percentile in the hour of the timestamp, if the band has been seen yet.
+    pub fn at(&self, at: DateTime<Local>, percentile: LoadPercentile) -> Option<Watts> {
+        let band = self.bands_on(at).get(&at.hour())?;
+        match percentile {
+            LoadPercentile::Mean => None,
+            LoadPercentile::P25 => Some(band.p25),
+            LoadPercentile::P50 => Some(band.p50),
+            LoadPercentile::P75 => Some(band.p75),
+        }
+    }
+
+    /// Update the band of the hour with the net load sample.
+    ///
+    /// Each band only sees its own share of the samples, so the elapsed time gets stretched
+    /// accordingly, and the bands adapt with the same half-life as the mean balance.
+    pub fn update(
+        &mut self,
+        net_load: Watts,
+        at: DateTime<Local>,
+        elapsed: TimeDelta,
+        half_life: HalfLife<Hours>,
+    ) {
+        let (bands, n_days_per_week) =
+            if is_weekend(at) { (&mut self.weekends, 2.0) } else { (&mut self.weekdays, 5.0) };
+        let share = n_days_per_week / 7.0 / 24.0;
+        let smoothing_factor = half_life.smoothing_factor(Hours::from(elapsed) / share);
+        bands
+            .entry(at.hour())
+            .or_insert_with(|| Band::new(net_load))
+            .update(net_load, smoothing_factor);
+    }
+
+    fn bands_on(&self, at: DateTime<Local>) -> &BTreeMap<u32, Band> {
+        if is_weekend(at) { &self.weekends } else { &self.weekdays }
+    }
+}
+
+fn is_weekend(at: DateTime<Local>) -> bool {
+    at.weekday().number_from_monday() > 5
+}
+
+/// Online quartile estimates of the net load.
+#[derive(Clone, Encode, Decode)]
+struct Band {
+    #[musli(Binary, name = 1)]
+    p25: Watts,
+
+    #[musli(Binary, name = 2)]
+    p50: Watts,
+
+    #[musli(Binary, name = 3)]
+    p75: Watts,
+
+    /// Mean absolute deviation from the median, which scales the quantile steps.
+    #[musli(Binary, name = 4)]
+    deviation: Exponential<Watts>,
+}
+
+impl Band {
+    /// Minimal step scale, so that the quartiles of a fresh band can diverge.
+    const MIN_DEVIATION: Watts = Quantity(10.0);
+
+    const fn new(net_load: Watts) -> Self {
+        Self { p25: net_load, p50: net_load, p75: net_load, deviation: Exponential(Watts::ZERO) }
+    }
+
+    /// Nudge the quartiles along the [pinball loss][1] gradient.
+    ///
+    /// Each quartile settles where the given fraction of the samples fall below it.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Quantile_regression#Quantiles
+    fn update(&mut self, net_load: Watts, smoothing_factor: f64) {
+        let step = self.deviation.0.max(Self::MIN_DEVIATION) * smoothing_factor;
+        let quartiles = [(&mut self.p25, 0.25), (&mut self.p50, 0.5), (&mut self.p75, 0.75)];
+        for (quantile, level) in quartiles {
+            let gradient = if net_load < *quantile { level - 1.0 } else { level };
+            *quantile += step * gradient;
+        }
+        self.deviation.update((net_load - self.p50).abs(), smoothing_factor);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn band_converges_to_quartiles() {
+        let mut band = Band::new(Quantity(0.0));
+        for _ in 0..200 {
+            // Uniform over 0…1000 W, shuffled:
+            for index in 0..100 {
+                band.update(Quantity(f64::from(index * 37 % 100).mul_add(10.0, 5.0)), 0.01);
+            }
+        }
+        assert!((band.p25.0 - 250.0).abs() < 15.0, "{}", band.p25.0);
+        assert!((band.p50.0 - 500.0).abs() < 15.0, "{}", band.p50.0);
+        assert!((band.p75.0 - 750.0).abs() < 15.0, "{}", band.p75.0);
+    }
+
+    #[test]
+    fn weekends_are_separate() {
+        let monday = Local.with_ymd_and_hms(2026, 4, 6, 18, 30, 0).unwrap();
+        let saturday = Local.with_ymd_and_hms(2026, 4, 11, 18, 30, 0).unwrap();
+        let half_life = HalfLife(Quantity(24.0));
+        let mut bands = LoadBands::default();
+        bands.update(Quantity(500.0), monday, TimeDelta::minutes(1), half_life);
+        let p75 = bands.at(monday, LoadPercentile::P75).unwrap();
+        assert!((p75.0 - 500.0).abs() < 1.0, "{}", p75.0);
+        assert_eq!(bands.at(monday, LoadPercentile::Mean), None);
+        assert_eq!(bands.at(saturday, LoadPercentile::P75), None);
+    }
+}
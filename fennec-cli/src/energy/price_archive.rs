@@ -0,0 +1,52 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate};
+
+use crate::{
+    Schedule,
+    energy,
+    ops::{interval::Interval, jsonl},
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+/// Fetched day-ahead prices, as archived.
+#[must_use]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ArchivedPrices {
+    pub provider: energy::Provider,
+    pub on: NaiveDate,
+    pub fetched_at: DateTime<Local>,
+    pub slots: Vec<ArchivedSlot>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct ArchivedSlot {
+    pub interval: Interval<DateTime<Local>>,
+    pub price: energy::Flow<KilowattHourPrice>,
+}
+
+impl ArchivedPrices {
+    /// Append-only log of every fetched day-ahead price curve, one JSON object per line.
+    ///
+    /// Meant for backtesting and comparing the providers, Fennec itself never reads it.
+    const PATH: &str = "prices.jsonl";
+
+    pub fn new(
+        provider: energy::Provider,
+        on: NaiveDate,
+        fetched_at: DateTime<Local>,
+        prices: &Schedule<energy::Flow<KilowattHourPrice>>,
+    ) -> Self {
+        let slots = prices
+            .iter()
+            .map(|slot| ArchivedSlot { interval: slot.interval, price: *slot.value })
+            .collect();
+        Self { provider, on, fetched_at, slots }
+    }
+
+    #[instrument(skip_all, fields(path = Self::PATH, on = ?self.on))]
+    pub async fn append_to_archive(&self) -> Result {
+        jsonl::append(Path::new(Self::PATH), self).await
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::BTreeMap, path::Path};
+
+use chrono::{DateTime, Datelike, Days, Local, NaiveDate};
+use musli::{Decode, Encode, wire};
+
+use crate::{
+    Schedule,
+    energy,
+    ops::interval::Interval,
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+/// Fetched daily prices, persisted so that a restart does not have to fetch them again.
+///
+/// Day-ahead prices never change once published, so there is nothing to invalidate
+/// except for the past days.
+#[must_use]
+#[derive(Default, Encode, Decode)]
+pub struct PriceCache {
+    /// Daily prices by the number of days from the common era.
+    #[musli(Binary, name = 1)]
+    days: BTreeMap<i32, Vec<CachedSlot>>,
+}
+
+#[derive(Encode, Decode)]
+struct CachedSlot {
+    #[musli(Binary, name = 1)]
+    #[musli(with = crate::ops::musli::chrono)]
+    start: DateTime<Local>,
+
+    #[musli(Binary, name = 2)]
+    #[musli(with = crate::ops::musli::chrono)]
+    end: DateTime<Local>,
+
+    #[musli(Binary, name = 3)]
+    price: energy::Flow<KilowattHourPrice>,
+}
+
+impl PriceCache {
+    /// Read the cache, falling back to an empty one – it is just a cache after all.
+    #[instrument]
+    pub async fn read_from_file(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        let result = async {
+            let bytes = tokio::fs::read(path).await.context("failed to read the file")?;
+            wire::decode(bytes.as_slice()).context("failed to decode the file")
+        };
+        result.await.unwrap_or_else(|error: Error| {
+            warn!("ignoring the price cache: {error:#}");
+            Self::default()
+        })
+    }
+
+    #[instrument(skip_all, fields(path = ?path))]
+    pub async fn write_to_file(&self, path: &Path) -> Result {
+        let temporary_path = path.with_added_extension("temporary");
+        let bytes = wire::to_vec(self).context("failed to encode the price cache")?;
+        tokio::fs::write(&temporary_path, bytes.as_slice())
+            .await
+            .context("failed to write the price cache")?;
+        tokio::fs::rename(&temporary_path, path)
+            .await
+            .context("failed to rename the temporary file")?;
+        Ok(())
+    }
+
+    /// Get the cached prices for the day, if any.
+    pub fn get(&self, on: NaiveDate) -> Result<Option<Schedule<energy::Flow<KilowattHourPrice>>>> {
+        let Some(slots) = self.days.get(&on.num_days_from_ce()) else {
+            return Ok(None);
+        };
+        let mut schedule = Schedule::new();
+        schedule.extend_from_iter(
+            slots.iter().map(|slot| (Interval::new(slot.start, slot.end), slot.price)),
+        )?;
+        Ok(Some(schedule))
+    }
+
+    /// Latest cached prices before the day, moved onto the day with the same local times –
+    /// to fall back to when the provider is down.
+    pub fn get_latest_before(
+        &self,
+        on: NaiveDate,
+    ) -> Result<Option<(NaiveDate, Schedule<energy::Flow<KilowattHourPrice>>)>> {
+        let Some((day, slots)) = self.days.range(..on.num_days_from_ce()).next_back() else {
+            return Ok(None);
+        };
+        let cached_on = NaiveDate::from_num_days_from_ce_opt(*day).context("invalid cached day")?;
+        let shift = Days::new(u64::try_from(on.num_days_from_ce() - day)?);
+        let mut schedule = Schedule::new();
+        schedule.extend_from_iter(slots.iter().filter_map(|slot| {
+            // Skip the slots which do not exist on the day because of the DST switch:
+            let start = slot.start.checked_add_days(shift)?;
+            let end = slot.end.checked_add_days(shift)?;
+            (start < end).then(|| (Interval::new(start, end), slot.price))
+        }))?;
+        Ok(Some((cached_on, schedule)))
+    }
+
+    pub fn insert(&mut self, on: NaiveDate, prices: &Schedule<energy::Flow<KilowattHourPrice>>) {
+        let slots = prices
+            .iter()
+            .map(|slot| CachedSlot {
+                start: slot.interval.start(),
+                end: slot.interval.end(),
+                price: *slot.value,
+            })
+            .collect();
+        self.days.insert(on.num_days_from_ce(), slots);
+    }
+
+    /// Forget the days before the specified one.
+    ///
+    /// Returns [`true`] if any day got forgotten.
+    pub fn retain_since(&mut self, since: NaiveDate) -> bool {
+        let n_days = self.days.len();
+        self.days.retain(|day, _| *day >= since.num_days_from_ce());
+        self.days.len() != n_days
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn roundtrip_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 0, 0, 0).unwrap();
+        let mut prices = Schedule::new();
+        prices.extend_from_iter((0..24).map(|hour| {
+            let start = start + TimeDelta::hours(hour);
+            let price = energy::Flow { import: Quantity(0.25), export: Quantity(0.1) };
+            (Interval::new(start, start + TimeDelta::hours(1)), price)
+        }))?;
+
+        let on = start.date_naive();
+        let mut cache = PriceCache::default();
+        cache.insert(on, &prices);
+        let cache: PriceCache = wire::decode(wire::to_vec(&cache)?.as_slice())?;
+
+        let cached = cache.get(on)?.unwrap();
+        assert_eq!(cached.len(), 24);
+        assert_eq!(cached.start_index(), prices.start_index());
+        assert_eq!(cached.end_index(), prices.end_index());
+        assert!(cache.get(on.succ_opt().unwrap())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn get_latest_before_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 7, 0, 0, 0).unwrap();
+        let mut prices = Schedule::new();
+        prices.extend_from_iter((0..24).map(|hour| {
+            let start = start + TimeDelta::hours(hour);
+            let price = energy::Flow { import: Quantity(0.25), export: Quantity(0.1) };
+            (Interval::new(start, start + TimeDelta::hours(1)), price)
+        }))?;
+        let yesterday = start.date_naive();
+        let mut cache = PriceCache::default();
+        cache.insert(yesterday, &prices);
+
+        let today = yesterday.succ_opt().unwrap();
+        let (cached_on, fallback) = cache.get_latest_before(today)?.unwrap();
+        assert_eq!(cached_on, yesterday);
+        assert_eq!(fallback.len(), 24);
+        assert_eq!(fallback.start_index(), Some(start + TimeDelta::days(1)));
+        assert!(cache.get_latest_before(yesterday)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn retain_since_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 0, 0, 0).unwrap();
+        let on = start.date_naive();
+        let mut cache = PriceCache::default();
+        cache.insert(on, &Schedule::new());
+        assert!(!cache.retain_since(on));
+        assert!(cache.retain_since(on.succ_opt().unwrap()));
+        assert!(cache.get(on)?.is_none());
+        Ok(())
+    }
+}
@@ -1,10 +1,10 @@
-use std::path::Path;
+use std::{collections::BTreeMap, ops::Range, path::Path, range::RangeInclusive};
 
 use chrono::{DateTime, Local, NaiveTime};
 use musli::{Decode, Encode, wire};
 
 use crate::{
-    api::mini_qube,
+    battery,
     energy,
     math::{
         fourier::ExponentialMovingDecomposition,
@@ -22,7 +22,7 @@ use crate::{
 };
 
 #[must_use]
-#[derive(Clone, Encode, Decode)]
+#[derive(Clone, Default, Encode, Decode)]
 pub struct Profile {
     /// Battery profile.
     #[musli(Binary, name = 13)]
@@ -67,31 +67,159 @@ impl Profile {
     }
 }
 
-#[derive(Copy, Clone, Encode, Decode)]
+#[derive(Clone, Encode, Decode)]
 pub struct Battery {
     #[musli(Binary, name = 1)]
     pub efficiency: energy::Flow<f64>,
 
     #[musli(Binary, name = 2)]
     pub tracker: Option<BatteryTracker>,
+
+    /// Mean absolute deviation of the efficiency samples, the estimate uncertainty.
+    #[musli(Binary, name = 3)]
+    #[musli(default = Battery::default_efficiency_deviation)]
+    pub efficiency_deviation: energy::Flow<f64>,
+
+    /// Efficiency estimates per battery temperature band, see [`temperature_band`].
+    #[musli(Binary, name = 4)]
+    #[musli(default)]
+    pub efficiency_by_temperature: BTreeMap<i16, EfficiencyEstimate>,
+
+    /// Long-term state-of-health history for the capacity trend.
+    #[musli(Binary, name = 5)]
+    #[musli(default)]
+    pub health: battery::Health,
+
+    /// Calendar year-to-date throughput for the warranty budget.
+    #[musli(Binary, name = 6)]
+    #[musli(default)]
+    pub throughput: battery::Throughput,
+}
+
+/// Width of the temperature band in degrees Celsius.
+const TEMPERATURE_BAND_WIDTH: f64 = 5.0;
+
+/// Lower bound of the 5°C-wide temperature band.
+#[expect(clippy::cast_possible_truncation)]
+pub fn temperature_band(temperature: f64) -> i16 {
+    ((temperature / TEMPERATURE_BAND_WIDTH).floor() * TEMPERATURE_BAND_WIDTH) as i16
+}
+
+/// Same as [`temperature_band`], but sticks to the previous band until the temperature
+/// leaves it by more than a degree.
+///
+/// The sensor noise around a band boundary would otherwise invalidate the optimizer
+/// on every other reading.
+pub fn temperature_band_after(temperature: f64, previous_band: Option<i16>) -> i16 {
+    const HYSTERESIS: f64 = 1.0;
+
+    match previous_band {
+        Some(previous_band)
+            if (f64::from(previous_band) - HYSTERESIS
+                ..f64::from(previous_band) + TEMPERATURE_BAND_WIDTH + HYSTERESIS)
+                .contains(&temperature) =>
+        {
+            previous_band
+        }
+        _ => temperature_band(temperature),
+    }
+}
+
+/// Efficiency along with its uncertainty.
+#[derive(Copy, Clone, Encode, Decode)]
+pub struct EfficiencyEstimate {
+    #[musli(Binary, name = 1)]
+    pub efficiency: energy::Flow<f64>,
+
+    /// Mean absolute deviation of the samples.
+    #[musli(Binary, name = 2)]
+    pub deviation: energy::Flow<f64>,
+}
+
+impl EfficiencyEstimate {
+    /// Physically plausible one-way efficiency.
+    ///
+    /// The residual energy quantization and BMS recalibrations occasionally produce a streak
+    /// of the bogus samples, which would otherwise drag the estimate out of the sane range.
+    const BOUNDS: RangeInclusive<f64> = RangeInclusive { start: 0.5, last: 1.0 };
+
+    /// Robustly update the estimate in the selected direction, returns the clipped sample.
+    ///
+    /// The estimate is kept within [`Self::BOUNDS`].
+    fn update(
+        &mut self,
+        direction: fn(&mut energy::Flow<f64>) -> &mut f64,
+        sample: f64,
+        smoothing_factor: f64,
+    ) -> f64 {
+        let mut efficiency = Exponential(*direction(&mut self.efficiency));
+        let mut deviation = Exponential(*direction(&mut self.deviation));
+        // The estimate sitting on a bound has been clamped already, no need to repeat the warning:
+        let was_clamped = efficiency.0 == Self::BOUNDS.start || efficiency.0 == Self::BOUNDS.last;
+        let clipped_sample = efficiency.update_robust(&mut deviation, sample, smoothing_factor);
+        if !Self::BOUNDS.contains(&efficiency.0) {
+            if was_clamped {
+                debug!(efficiency = efficiency.0, "the efficiency estimate is still out of bounds");
+            } else {
+                warn!(
+                    efficiency = efficiency.0,
+                    "the efficiency estimate is out of bounds, clamping"
+                );
+            }
+            efficiency.0 = efficiency.0.clamp(Self::BOUNDS.start, Self::BOUNDS.last);
+        }
+        *direction(&mut self.efficiency) = efficiency.0;
+        *direction(&mut self.deviation) = deviation.0;
+        clipped_sample
+    }
 }
 
 impl Default for Battery {
     fn default() -> Self {
-        Self { efficiency: energy::Flow { import: 0.95, export: 0.95 }, tracker: None }
+        Self {
+            efficiency: energy::Flow { import: 0.95, export: 0.95 },
+            tracker: None,
+            efficiency_deviation: Self::default_efficiency_deviation(),
+            efficiency_by_temperature: BTreeMap::new(),
+            health: battery::Health::default(),
+            throughput: battery::Throughput::default(),
+        }
     }
 }
 
 impl Battery {
+    const fn default_efficiency_deviation() -> energy::Flow<f64> {
+        energy::Flow { import: 0.05, export: 0.05 }
+    }
+
+    /// Efficiency in the temperature band, falls back to the overall estimate
+    /// if the temperature is unknown or the band has not been seen yet.
+    pub fn efficiency_at(&self, temperature_band: Option<i16>) -> energy::Flow<f64> {
+        temperature_band
+            .and_then(|band| self.efficiency_by_temperature.get(&band))
+            .map_or(self.efficiency, |estimate| estimate.efficiency)
+    }
+
     /// Track the battery metrics and update the battery efficiency parameters when the residual energy has changed.
     ///
+    /// The samples are noisy because of the residual energy quantization,
+    /// hence the robust update which clips the outliers.
+    ///
+    /// With the battery temperature known, the sample also updates the temperature band's estimate,
+    /// which starts off as a copy of the overall one.
+    ///
     /// # Returns
     ///
     /// - [`true`], if the battery residual energy has changed since the last call;
     /// - [`false`], otherwise.
     #[instrument(skip_all)]
     #[must_use]
-    pub fn track(&mut self, current_metrics: &mini_qube::Metrics, half_life_factor: f64) -> bool {
+    pub fn track(
+        &mut self,
+        current_metrics: &battery::Metrics,
+        temperature: Option<f64>,
+        half_life_factor: f64,
+    ) -> bool {
         let current_tracker = BatteryTracker {
             total_grid_flow: current_metrics.total_grid_flow,
             residual_energy: current_metrics.residual_energy(),
@@ -118,12 +246,17 @@ impl Battery {
                 let smoothing_factor =
                     HalfLife(current_metrics.actual_capacity() * half_life_factor)
                         .smoothing_factor(grid_export);
-                self.efficiency.export =
-                    Exponential(self.efficiency.export).update(efficiency, smoothing_factor).0;
+                let clipped_efficiency = self.update_efficiency(
+                    temperature,
+                    |flow| &mut flow.export,
+                    efficiency,
+                    smoothing_factor,
+                );
                 info!(
                     ?residual_energy_change,
                     ?grid_export,
                     ?efficiency,
+                    ?clipped_efficiency,
                     ?smoothing_factor,
                     "discharging",
                 );
@@ -134,12 +267,17 @@ impl Battery {
                 let smoothing_factor =
                     HalfLife(current_metrics.actual_capacity() * half_life_factor)
                         .smoothing_factor(grid_import);
-                self.efficiency.import =
-                    Exponential(self.efficiency.import).update(efficiency, smoothing_factor).0;
+                let clipped_efficiency = self.update_efficiency(
+                    temperature,
+                    |flow| &mut flow.import,
+                    efficiency,
+                    smoothing_factor,
+                );
                 info!(
                     ?residual_energy_change,
                     ?grid_import,
                     ?efficiency,
+                    ?clipped_efficiency,
                     ?smoothing_factor,
                     "charging",
                 );
@@ -152,6 +290,30 @@ impl Battery {
         self.tracker = Some(current_tracker);
         true
     }
+
+    /// Update the overall estimate, and the temperature band's one if the temperature is known.
+    fn update_efficiency(
+        &mut self,
+        temperature: Option<f64>,
+        direction: fn(&mut energy::Flow<f64>) -> &mut f64,
+        sample: f64,
+        smoothing_factor: f64,
+    ) -> f64 {
+        let mut overall = EfficiencyEstimate {
+            efficiency: self.efficiency,
+            deviation: self.efficiency_deviation,
+        };
+        if let Some(temperature) = temperature {
+            self.efficiency_by_temperature
+                .entry(temperature_band(temperature))
+                .or_insert(overall)
+                .update(direction, sample, smoothing_factor);
+        }
+        let clipped_sample = overall.update(direction, sample, smoothing_factor);
+        self.efficiency = overall.efficiency;
+        self.efficiency_deviation = overall.deviation;
+        clipped_sample
+    }
 }
 
 #[derive(Copy, Clone, Encode, Decode)]
@@ -188,6 +350,11 @@ pub struct Energy {
     #[musli(Binary, name = 5)]
     #[musli(default)]
     pub balance: ExponentialMovingDecomposition<energy::Balance<Watts>>,
+
+    /// Hourly net load percentiles, for the weekdays and weekends separately.
+    #[musli(Binary, name = 6)]
+    #[musli(default)]
+    pub load_bands: energy::LoadBands,
 }
 
 impl Default for Energy {
@@ -211,6 +378,7 @@ impl Energy {
             harmonics: vec![Self::DEFAULT_HARMONIC; n_balance_harmonics],
 
             balance: ExponentialMovingDecomposition::new(n_balance_harmonics),
+            load_bands: energy::LoadBands::default(),
         }
     }
 
@@ -223,16 +391,26 @@ impl Energy {
         &self,
         interval: Interval<DateTime<Local>>,
     ) -> energy::Balance<Watts> {
-        let mean_deviation = {
-            let start_phase = Radians::daily_phase_at(interval.start().time());
-            // FIXME: strictly speaking this is not correct for DST transitions:
-            let end_phase = start_phase + Radians::daily_phase_shift_of(interval.duration());
-            self.balance.mean_deviation_over(start_phase..end_phase)
-        };
+        let mean_deviation = self.balance.mean_deviation_over(daily_phases_over(interval));
         let balance = self.balance.mean() + mean_deviation;
         energy::Balance { grid: balance.grid.normalized(), battery: balance.battery.normalized() }
     }
 
+    /// Balance to plan against over the interval, per the load percentile.
+    ///
+    /// Falls back to the learned mean until the band of the hour has been seen.
+    pub fn balance_over(
+        &self,
+        interval: Interval<DateTime<Local>>,
+        percentile: energy::LoadPercentile,
+        battery_power_limits: battery::PowerLimits,
+    ) -> energy::Balance<Watts> {
+        self.load_bands.at(interval.start(), percentile).map_or_else(
+            || self.normalized_mean_over(interval),
+            |net_load| energy::Balance::new(battery_power_limits, net_load),
+        )
+    }
+
     #[instrument(skip_all)]
     pub fn update(
         &mut self,
@@ -241,13 +419,81 @@ impl Energy {
         at: DateTime<Local>,
         half_life: HalfLife<Hours>,
     ) {
-        let mean_smoothing_factor = {
-            // Smoothing factor based on the configured half-life and elapsed time:
-            let elapsed = at - std::mem::replace(&mut self.updated_at, at);
-            half_life.smoothing_factor(elapsed)
-        };
+        // Smoothing factor based on the configured half-life and elapsed time:
+        let elapsed = at - std::mem::replace(&mut self.updated_at, at);
+        let mean_smoothing_factor = half_life.smoothing_factor(elapsed);
 
         self.eps_active_power.update(eps_active_power, mean_smoothing_factor);
         self.balance.update(balance, Radians::daily_phase_at(at.time()), mean_smoothing_factor);
+        self.load_bands.update(balance.invariant(), at, elapsed, half_life);
+    }
+}
+
+/// Daily cycle phases covered by the interval, following the wall clock.
+///
+/// When the DST starts, the skipped hour is covered as well. When the DST ends,
+/// the repeated hour is covered once again rather than collapsing into nothing.
+fn daily_phases_over(interval: Interval<DateTime<Local>>) -> Range<Radians> {
+    let wall_clock_duration = interval.end().naive_local() - interval.start().naive_local();
+    let start_phase = Radians::daily_phase_at(interval.start().time());
+    let duration = wall_clock_duration.max(interval.duration());
+    start_phase..start_phase + Radians::daily_phase_shift_of(duration)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    use super::*;
+
+    #[test]
+    fn temperature_band_ok() {
+        assert_eq!(temperature_band(21.5), 20);
+        assert_eq!(temperature_band(5.0), 5);
+        assert_eq!(temperature_band(-0.5), -5);
+    }
+
+    #[test]
+    fn temperature_band_after_ok() {
+        assert_eq!(temperature_band_after(20.5, None), 20);
+        assert_eq!(temperature_band_after(19.5, Some(20)), 20);
+        assert_eq!(temperature_band_after(18.5, Some(20)), 15);
+        assert_eq!(temperature_band_after(25.5, Some(20)), 20);
+        assert_eq!(temperature_band_after(26.5, Some(20)), 25);
+    }
+
+    #[test]
+    fn efficiency_estimate_stays_within_bounds() {
+        let mut estimate = EfficiencyEstimate {
+            efficiency: energy::Flow { import: 0.99, export: 0.95 },
+            deviation: energy::Flow { import: 0.05, export: 0.05 },
+        };
+        for _ in 0..10 {
+            estimate.update(|flow| &mut flow.import, 1.2, 0.5);
+        }
+        assert!((estimate.efficiency.import - 1.0).abs() < f64::EPSILON);
+        assert!((estimate.efficiency.export - 0.95).abs() < f64::EPSILON);
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn daily_phases_over_dst_start() {
+        let start = Local.with_ymd_and_hms(2026, 3, 29, 1, 0, 0).unwrap();
+        let end = start + TimeDelta::hours(1);
+        assert_eq!(end.time(), NaiveTime::from_hms_opt(3, 0, 0).unwrap()); // skipped 02:00
+        let phases = daily_phases_over(Interval::new(start, end));
+        assert!((phases.end - Radians::daily_phase_at(end.time())).0.abs() < 1e-9);
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn daily_phases_over_dst_end() {
+        // The first 02:00, in summer time:
+        let start = Utc.with_ymd_and_hms(2026, 10, 25, 0, 0, 0).unwrap().with_timezone(&Local);
+        let end = start + TimeDelta::hours(1);
+        assert_eq!(end.time(), start.time()); // repeated 02:00
+        let phases = daily_phases_over(Interval::new(start, end));
+        let expected_shift = Radians::daily_phase_shift_of(TimeDelta::hours(1));
+        assert!((phases.end - phases.start - expected_shift).0.abs() < 1e-9);
     }
 }
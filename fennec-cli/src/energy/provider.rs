@@ -1,9 +1,20 @@
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 
-use backon::{ConstantBuilder, Retryable};
+use backon::{ExponentialBuilder, Retryable};
 use chrono::{DateTime, Days, Local, NaiveDate};
 
-use crate::{Schedule, api::frank_energie, energy, prelude::*, quantity::price::KilowattHourPrice};
+use crate::{
+    Schedule,
+    api,
+    api::frank_energie::{self, Throttled},
+    energy,
+    energy::{price_archive::ArchivedPrices, price_cache::PriceCache},
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+/// Freshly fetched prices of the day.
+type FetchedDay = (NaiveDate, Schedule<energy::Flow<KilowattHourPrice>>);
 
 #[derive(
     Copy, Clone, Hash, Eq, PartialEq, clap::ValueEnum, serde::Serialize, serde::Deserialize,
@@ -16,44 +27,208 @@ pub enum Provider {
     /// Hourly [Frank Energie](https://www.frankenergie.nl).
     #[serde(rename = "frank_energie_hourly")]
     FrankEnergieHourly,
+
+    /// Hourly [easyEnergy](https://www.easyenergy.com), which ANWB Energie uses as well.
+    #[serde(rename = "easy_energy")]
+    EasyEnergy,
+
+    /// Hourly [Zonneplan](https://www.zonneplan.nl), including their surcharge.
+    #[serde(rename = "zonneplan")]
+    Zonneplan,
+
+    /// Fixed-rate contract, see [`energy::StaticTariff`].
+    #[serde(rename = "static")]
+    Static,
 }
 
 impl Provider {
-    const BACKOFF: ConstantBuilder = ConstantBuilder::new().with_delay(Duration::from_secs(10));
+    const BACKOFF: ExponentialBuilder = ExponentialBuilder::new()
+        .with_min_delay(Duration::from_secs(5))
+        .with_max_delay(Duration::from_mins(1))
+        .with_max_times(4)
+        .with_jitter();
+
+    /// How many past days to keep in the cache, to fall back to when the provider is down.
+    const CACHE_RETENTION: Days = Days::new(7);
 
     /// Fetch energy prices for up to 2 days since the specified timestamp.
     ///
+    /// The fetched days are cached on disk, so that a restart does not have to fetch them again,
+    /// and archived. Should the provider fail to return today's prices, the latest cached day
+    /// gets reused.
+    ///
     /// Errors if no prices are available for today (tomorrow is best-effort).
     #[instrument(skip_all, fields(now = ?now))]
     pub async fn get_future_prices(
         self,
+        connections: &api::Connections,
         now: DateTime<Local>,
     ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        let cache_path = self.cache_path();
+        let (mut cache, is_pruned) = self.read_cache(now.date_naive()).await;
+        let (prices, fetched) = self.fetch_future_prices(connections, &mut cache, now).await?;
+
+        // Locally computed prices are not worth caching nor archiving:
+        if let Some(cache_path) = &cache_path {
+            for (on, day_prices) in &fetched {
+                if let Err(error) = ArchivedPrices::new(self, *on, Local::now(), day_prices)
+                    .append_to_archive()
+                    .await
+                {
+                    warn!("failed to archive the prices: {error:#}");
+                }
+            }
+            // Nothing to write if all the days came from the cache:
+            if (is_pruned || !fetched.is_empty())
+                && let Err(error) = cache.write_to_file(cache_path).await
+            {
+                warn!("failed to cache the prices: {error:#}");
+            }
+        }
+
+        Ok(prices)
+    }
+
+    /// Same as [`Provider::get_future_prices`], but leaves the cache and archive untouched.
+    #[instrument(skip_all, fields(now = ?now))]
+    pub async fn peek_future_prices(
+        self,
+        connections: &api::Connections,
+        now: DateTime<Local>,
+    ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        let (mut cache, _) = self.read_cache(now.date_naive()).await;
+        let (prices, _) = self.fetch_future_prices(connections, &mut cache, now).await?;
+        Ok(prices)
+    }
+
+    /// Read the cache and forget the days beyond the retention.
+    ///
+    /// Returns the cache, and whether any day got forgotten.
+    async fn read_cache(self, today: NaiveDate) -> (PriceCache, bool) {
+        let mut cache = match self.cache_path() {
+            Some(cache_path) => PriceCache::read_from_file(&cache_path).await,
+            None => PriceCache::default(),
+        };
+        let is_pruned = cache.retain_since(today.checked_sub_days(Self::CACHE_RETENTION).unwrap());
+        (cache, is_pruned)
+    }
+
+    /// Get today's and tomorrow's prices through the in-memory cache.
+    ///
+    /// Returns the prices since `now`, and the freshly fetched days.
+    async fn fetch_future_prices(
+        self,
+        connections: &api::Connections,
+        cache: &mut PriceCache,
+        now: DateTime<Local>,
+    ) -> Result<(Schedule<energy::Flow<KilowattHourPrice>>, Vec<FetchedDay>)> {
         const ONE_DAY: Days = Days::new(1);
 
-        // TODO: potentially, check for tomorrow's prices doesn't require fetching today's prices:
+        let mut fetched = Vec::new();
         let today = now.date_naive();
-        let mut prices = self.get_prices(today).await?;
-        ensure!(prices.len() != 0, "received empty price schedule for today");
+        let mut prices = match self
+            .get_cached_prices(connections, cache, &mut fetched, today)
+            .await
+            .and_then(|prices| {
+                ensure!(prices.len() != 0, "received empty price schedule for today");
+                Ok(prices)
+            }) {
+            Ok(prices) => prices,
+            Err(error) => match cache.get_latest_before(today) {
+                Ok(Some((cached_on, prices))) if prices.len() != 0 => {
+                    warn!(?cached_on, "reusing the cached prices: {error:#}");
+                    prices
+                }
+                Ok(_) => return Err(error),
+                Err(cache_error) => {
+                    warn!("failed to reuse the cached prices: {cache_error:#}");
+                    return Err(error);
+                }
+            },
+        };
 
-        prices.extend({
-            let tomorrow = today.checked_add_days(ONE_DAY).unwrap();
-            self.get_prices(tomorrow).await?
-        })?;
+        let tomorrow = today.checked_add_days(ONE_DAY).unwrap();
+        match self.get_cached_prices(connections, cache, &mut fetched, tomorrow).await {
+            Ok(tomorrow_prices) => prices.extend(tomorrow_prices)?,
+            Err(error) => warn!("failed to fetch tomorrow's prices: {error:#}"),
+        }
 
         info!(len = prices.len(), "fetched energy prices");
         prices.advance_to(now);
+        Ok((prices, fetched))
+    }
+
+    /// Cache file path, or [`None`] if the prices are not worth caching.
+    fn cache_path(self) -> Option<PathBuf> {
+        let name = match self {
+            Self::FrankEnergieQuarterly => "frank-energie-quarterly",
+            Self::FrankEnergieHourly => "frank-energie-hourly",
+            Self::EasyEnergy => "easy-energy",
+            Self::Zonneplan => "zonneplan",
+            Self::Static => return None,
+        };
+        Some(PathBuf::from(format!("prices-{name}.musli")))
+    }
+
+    /// Get the day prices from the cache, or fetch them and put into the cache if published.
+    ///
+    /// The fetched days get collected into `fetched`.
+    async fn get_cached_prices(
+        self,
+        connections: &api::Connections,
+        cache: &mut PriceCache,
+        fetched: &mut Vec<FetchedDay>,
+        on: NaiveDate,
+    ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        match cache.get(on) {
+            Ok(Some(prices)) => {
+                debug!(?on, "using the cached prices");
+                return Ok(prices);
+            }
+            Ok(None) => {}
+            Err(error) => warn!(?on, "ignoring the cached prices: {error:#}"),
+        }
+        let prices = self.get_prices(connections, on).await?;
+        if prices.len() != 0 {
+            cache.insert(on, &prices);
+            fetched.push((on, prices.clone()));
+        }
         Ok(prices)
     }
 
     /// Fetch energy prices for a single day.
-    async fn get_prices(self, on: NaiveDate) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+    pub(super) async fn get_prices(
+        self,
+        connections: &api::Connections,
+        on: NaiveDate,
+    ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
         let resolution = match self {
             Self::FrankEnergieQuarterly => frank_energie::Resolution::Quarterly,
             Self::FrankEnergieHourly => frank_energie::Resolution::Hourly,
+            Self::EasyEnergy => {
+                return (|| async { connections.easy_energy.get_prices(on).await })
+                    .retry(Self::BACKOFF)
+                    .notify(log_retried_error)
+                    .await;
+            }
+            Self::Zonneplan => {
+                return (|| async { connections.zonneplan.get_prices(on).await })
+                    .retry(Self::BACKOFF)
+                    .notify(log_retried_error)
+                    .await;
+            }
+            Self::Static => return connections.static_tariff.get_prices(on),
         };
-        (|| async { frank_energie::Api::new(resolution)?.get_prices(on).await })
+        (|| async { connections.frank_energie.get_prices(on, resolution).await })
             .retry(Self::BACKOFF)
+            .when(|error| !matches!(error.downcast_ref(), Some(Throttled::BudgetExhausted { .. })))
+            .adjust(|error, delay| match error.downcast_ref() {
+                // Respect the server's wish, but give up when the attempts are exhausted:
+                Some(Throttled::RateLimited { retry_after: Some(retry_after) }) => {
+                    delay.map(|delay| delay.max(*retry_after))
+                }
+                _ => delay,
+            })
             .notify(log_retried_error)
             .await
     }
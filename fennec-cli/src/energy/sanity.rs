@@ -0,0 +1,184 @@
+//! Sanity limits on the provider prices, so that malformed data does not turn into a nonsense plan.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Local, TimeDelta};
+
+use crate::{Schedule, energy, prelude::*, quantity::price::KilowattHourPrice};
+
+/// What to do with the prices outside the sanity limits.
+#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+pub enum SanityPolicy {
+    /// Clamp the implausible prices into the limits.
+    Clamp,
+
+    /// Drop the implausible price along with all the later ones.
+    Drop,
+
+    /// Fail the iteration.
+    Abort,
+}
+
+#[derive(Copy, Clone, clap::Args)]
+pub struct PriceSanity {
+    /// Lowest plausible import or export price, in ¤/kWh.
+    #[clap(long = "price-min", env = "PRICE_MIN", allow_negative_numbers = true)]
+    pub min: Option<KilowattHourPrice>,
+
+    /// Highest plausible import or export price, in ¤/kWh.
+    #[clap(long = "price-max", env = "PRICE_MAX")]
+    pub max: Option<KilowattHourPrice>,
+
+    /// Largest plausible price change between the consecutive intervals, in ¤/kWh.
+    #[clap(long = "price-max-jump", env = "PRICE_MAX_JUMP")]
+    pub max_jump: Option<KilowattHourPrice>,
+
+    /// Minimal span the prices must cover from now on, for example, `6h`.
+    #[clap(
+        long = "price-min-coverage",
+        env = "PRICE_MIN_COVERAGE",
+        value_parser = humantime::parse_duration,
+    )]
+    pub min_coverage: Option<Duration>,
+
+    /// What to do with the prices outside the limits.
+    ///
+    /// Insufficient coverage always fails the iteration, including after dropping the prices.
+    #[clap(long = "price-sanity-policy", env = "PRICE_SANITY_POLICY", default_value = "abort")]
+    pub policy: SanityPolicy,
+}
+
+impl PriceSanity {
+    /// Check the provider prices against the limits, and handle the violations per the policy.
+    #[instrument(skip_all, fields(policy = ?self.policy))]
+    pub fn apply_to(
+        &self,
+        prices: &mut Schedule<energy::Flow<KilowattHourPrice>>,
+        now: DateTime<Local>,
+    ) -> Result {
+        let mut previous = None;
+        for index in 0..prices.len() {
+            let slot = prices.get(index);
+            let (start, price) = (slot.interval.start(), *slot.value);
+            let sane_price = energy::Flow {
+                import: self.clamp(
+                    price.import,
+                    previous.map(|previous: energy::Flow<KilowattHourPrice>| previous.import),
+                ),
+                export: self.clamp(price.export, previous.map(|previous| previous.export)),
+            };
+            if sane_price != price {
+                match self.policy {
+                    SanityPolicy::Abort => {
+                        bail!("implausible price at {start}: {price:?}");
+                    }
+                    SanityPolicy::Drop => {
+                        warn!(%start, ?price, "dropping the prices since the implausible one");
+                        prices.truncate(index);
+                        break;
+                    }
+                    SanityPolicy::Clamp => {
+                        warn!(%start, ?price, ?sane_price, "clamping the implausible price");
+                        *prices.get_mut(index) = sane_price;
+                    }
+                }
+            }
+            previous = Some(sane_price);
+        }
+        if let Some(min_coverage) = self.min_coverage {
+            let coverage = prices.end_index().map_or(TimeDelta::zero(), |end| end - now);
+            ensure!(
+                coverage >= TimeDelta::from_std(min_coverage)?,
+                "the prices only cover {} minutes ahead, expected at least {}",
+                coverage.num_minutes(),
+                humantime::format_duration(min_coverage),
+            );
+        }
+        Ok(())
+    }
+
+    /// Clamp the price into the bounds, and then within the maximum jump from the previous one.
+    fn clamp(
+        &self,
+        mut price: KilowattHourPrice,
+        previous: Option<KilowattHourPrice>,
+    ) -> KilowattHourPrice {
+        if let Some(min) = self.min {
+            price = price.max(min);
+        }
+        if let Some(max) = self.max {
+            price = price.min(max);
+        }
+        if let Some((max_jump, previous)) = self.max_jump.zip(previous) {
+            price = price.max(previous - max_jump).min(previous + max_jump);
+        }
+        price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{ops::interval::Interval, quantity::Quantity};
+
+    fn schedule_of(
+        start: DateTime<Local>,
+        imports: &[f64],
+    ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        let mut prices = Schedule::new();
+        prices.extend_from_iter(imports.iter().zip(0..).map(|(import, hour)| {
+            let start = start + TimeDelta::hours(hour);
+            let price = energy::Flow { import: Quantity(*import), export: Quantity(0.0) };
+            (Interval::new(start, start + TimeDelta::hours(1)), price)
+        }))?;
+        Ok(prices)
+    }
+
+    const fn sanity(policy: SanityPolicy) -> PriceSanity {
+        PriceSanity {
+            min: Some(Quantity(-0.5)),
+            max: Some(Quantity(1.0)),
+            max_jump: Some(Quantity(0.25)),
+            min_coverage: None,
+            policy,
+        }
+    }
+
+    #[test]
+    fn clamp_ok() -> Result {
+        let now = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let mut prices = schedule_of(now, &[0.25, 0.375, 999.0, 0.25])?;
+        sanity(SanityPolicy::Clamp).apply_to(&mut prices, now)?;
+        let imports = prices.iter().map(|slot| slot.value.import).collect::<Vec<_>>();
+        assert_eq!(imports, [Quantity(0.25), Quantity(0.375), Quantity(0.625), Quantity(0.375)]);
+        Ok(())
+    }
+
+    #[test]
+    fn drop_ok() -> Result {
+        let now = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let mut prices = schedule_of(now, &[0.25, 0.375, 999.0, 0.25])?;
+        sanity(SanityPolicy::Drop).apply_to(&mut prices, now)?;
+        assert_eq!(prices.len(), 2);
+
+        let strict = PriceSanity {
+            min_coverage: Some(Duration::from_hours(3)),
+            ..sanity(SanityPolicy::Drop)
+        };
+        let mut prices = schedule_of(now, &[0.25, 0.375, 999.0, 0.25])?;
+        assert!(strict.apply_to(&mut prices, now).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn abort_ok() -> Result {
+        let now = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let mut prices = schedule_of(now, &[0.25, -2.0])?;
+        assert!(sanity(SanityPolicy::Abort).apply_to(&mut prices, now).is_err());
+        let mut prices = schedule_of(now, &[0.25, 0.125])?;
+        sanity(SanityPolicy::Abort).apply_to(&mut prices, now)?;
+        Ok(())
+    }
+}
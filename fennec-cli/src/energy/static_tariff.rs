@@ -0,0 +1,155 @@
+//! Fixed-rate supply contracts with the day and night tariffs, for those without dynamic pricing.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta, Timelike, Weekday};
+
+use crate::{
+    Schedule,
+    energy,
+    ops::{daily_window::DailyWindow, interval::Interval},
+    prelude::*,
+    quantity::price::KilowattHourPrice,
+};
+
+/// Tariff price within a daily time window, formatted as `HH:MM-HH:MM=price`.
+///
+/// The window may wrap around midnight, for example: `23:00-07:00=0.22`.
+#[derive(Copy, Clone, Debug)]
+pub struct TariffWindow {
+    pub window: DailyWindow,
+    pub price: KilowattHourPrice,
+}
+
+impl FromStr for TariffWindow {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (window, price) = text.split_once('=').context("expected `HH:MM-HH:MM=price`")?;
+        Ok(Self { window: window.parse()?, price: price.trim().parse()? })
+    }
+}
+
+#[derive(Clone, clap::Args)]
+pub struct StaticTariff {
+    /// Static contract import price in ¤/kWh, including the taxes, outside of the tariff windows.
+    #[clap(long = "static-price", env = "STATIC_PRICE")]
+    pub price: Option<KilowattHourPrice>,
+
+    /// Static contract tariff windows overriding the normal price, for example, `23:00-07:00=0.22`
+    /// for the night tariff.
+    #[clap(long = "static-tariff-window", env = "STATIC_TARIFF_WINDOWS", value_delimiter = ',')]
+    pub windows: Vec<TariffWindow>,
+
+    /// Static contract price in ¤/kWh which holds all day on weekends,
+    /// as the night tariff usually does in the Netherlands.
+    #[clap(long = "static-weekend-price", env = "STATIC_WEEKEND_PRICE")]
+    pub weekend_price: Option<KilowattHourPrice>,
+
+    /// Static contract export price in ¤/kWh, defaults to the import price.
+    #[clap(long = "static-export-price", env = "STATIC_EXPORT_PRICE")]
+    pub export_price: Option<KilowattHourPrice>,
+}
+
+impl StaticTariff {
+    /// Contract prices for the day, hourly or quarterly if any window starts within an hour.
+    pub fn get_prices(&self, on: NaiveDate) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        let price = self.price.context("the static provider requires the price")?;
+        let resolution = if self
+            .windows
+            .iter()
+            .all(|window| window.window.start.minute() == 0 && window.window.end.minute() == 0)
+        {
+            TimeDelta::hours(1)
+        } else {
+            TimeDelta::minutes(15)
+        };
+        let day = Interval::local_day(on)?;
+        let mut prices = Schedule::new();
+        prices.extend_from_iter(
+            std::iter::successors(Some(day.start()), |start| Some(*start + resolution))
+                .take_while(|start| *start < day.end())
+                .map(|start| {
+                    let import = self.import_price_at(start, price);
+                    let export = self.export_price.unwrap_or(import);
+                    (Interval::new(start, start + resolution), energy::Flow { import, export })
+                }),
+        )?;
+        Ok(prices)
+    }
+
+    fn import_price_at(
+        &self,
+        timestamp: DateTime<Local>,
+        price: KilowattHourPrice,
+    ) -> KilowattHourPrice {
+        if let Some(weekend_price) = self.weekend_price
+            && matches!(timestamp.weekday(), Weekday::Sat | Weekday::Sun)
+        {
+            return weekend_price;
+        }
+        let time = timestamp.time();
+        self.windows
+            .iter()
+            .find(|window| window.window.contains(time))
+            .map_or(price, |window| window.price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::Quantity;
+
+    fn tariff() -> Result<StaticTariff> {
+        Ok(StaticTariff {
+            price: Some(Quantity(0.25)),
+            windows: vec!["23:00-07:00=0.125".parse()?],
+            weekend_price: Some(Quantity(0.0625)),
+            export_price: None,
+        })
+    }
+
+    #[test]
+    fn weekday_ok() -> Result {
+        let prices = tariff()?.get_prices(NaiveDate::from_ymd_opt(2026, 4, 8).unwrap())?;
+        assert_eq!(prices.len(), 24);
+        assert_eq!(prices.get(6).value.import, Quantity(0.125));
+        assert_eq!(prices.get(7).value.import, Quantity(0.25));
+        assert_eq!(prices.get(7).value.export, Quantity(0.25));
+        assert_eq!(prices.get(23).value.import, Quantity(0.125));
+        Ok(())
+    }
+
+    #[test]
+    fn weekend_ok() -> Result {
+        let prices = tariff()?.get_prices(NaiveDate::from_ymd_opt(2026, 4, 11).unwrap())?;
+        assert!(prices.iter().all(|slot| slot.value.import == Quantity(0.0625)));
+        Ok(())
+    }
+
+    #[test]
+    fn quarterly_ok() -> Result {
+        let tariff = StaticTariff { windows: vec!["21:30-07:00=0.125".parse()?], ..tariff()? };
+        let prices = tariff.get_prices(NaiveDate::from_ymd_opt(2026, 4, 8).unwrap())?;
+        assert_eq!(prices.len(), 96);
+        assert_eq!(prices.get(85).value.import, Quantity(0.25));
+        assert_eq!(prices.get(86).value.import, Quantity(0.125));
+        Ok(())
+    }
+
+    /// Expects the `Europe/Amsterdam` timezone, like in CI.
+    #[test]
+    fn dst_days_ok() -> Result {
+        let tariff = StaticTariff { weekend_price: None, ..tariff()? };
+        let prices = tariff.get_prices(NaiveDate::from_ymd_opt(2026, 3, 29).unwrap())?;
+        assert_eq!(prices.len(), 23);
+        assert_eq!(prices.get(5).value.import, Quantity(0.125)); // 06:00
+        assert_eq!(prices.get(6).value.import, Quantity(0.25)); // 07:00
+        let prices = tariff.get_prices(NaiveDate::from_ymd_opt(2026, 10, 25).unwrap())?;
+        assert_eq!(prices.len(), 25);
+        assert_eq!(prices.get(7).value.import, Quantity(0.125)); // 06:00
+        assert_eq!(prices.get(8).value.import, Quantity(0.25)); // 07:00
+        Ok(())
+    }
+}
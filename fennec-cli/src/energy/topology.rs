@@ -0,0 +1,183 @@
+//! Household metering topology: which meter measures what, and how the overlapping readings
+//! add up to the consistent power flows.
+
+use serde::Serialize;
+use tokio::join;
+
+use crate::{
+    api::{homewizard, http},
+    prelude::*,
+    quantity::power::Watts,
+};
+
+/// Which way the meter is wired.
+#[derive(Copy, Clone, Debug, clap::ValueEnum)]
+pub enum Sign {
+    /// Positive power is consumption through the meter, like the HomeWizard meters report it.
+    Direct,
+
+    /// Positive power is production through the meter.
+    Inverted,
+}
+
+impl Sign {
+    /// Bring the meter power into the consumption-positive convention.
+    pub fn apply(self, power: Watts) -> Watts {
+        match self {
+            Self::Direct => power,
+            Self::Inverted => -power,
+        }
+    }
+
+    /// Bring the meter measurement into the consumption-positive convention,
+    /// swapping the import and export totals of the inverted meters.
+    pub fn apply_to(self, metrics: homewizard::EnergyMetrics) -> homewizard::EnergyMetrics {
+        match self {
+            Self::Direct => metrics,
+            Self::Inverted => homewizard::EnergyMetrics {
+                active_power: -metrics.active_power,
+                import: metrics.export,
+                export: metrics.import,
+            },
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct TopologyArgs {
+    /// Grid meter wiring, positive power must mean the import.
+    #[clap(long = "grid-meter-sign", env = "GRID_METER_SIGN", default_value = "direct")]
+    pub grid_sign: Sign,
+
+    /// HomeWizard kWh meter or energy socket measurement URL, metering the battery on its AC side.
+    ///
+    /// The meter takes precedence over the battery power reported by the inverter,
+    /// which is often measured on the DC side, or not measured at all.
+    #[clap(long = "battery-measurement-url", env = "BATTERY_MEASUREMENT_URL")]
+    pub battery_measurement_url: Option<homewizard::Url>,
+
+    /// Battery meter wiring, directly wired meter sees the charging as consumption.
+    #[clap(long = "battery-meter-sign", env = "BATTERY_METER_SIGN", default_value = "direct")]
+    pub battery_sign: Sign,
+
+    /// HomeWizard kWh meter measurement URL, metering the PV inverter output.
+    ///
+    /// The PV production is already included in the grid power, so it is only needed
+    /// to tell the gross household load.
+    #[clap(long = "pv-measurement-url", env = "PV_MEASUREMENT_URL")]
+    pub pv_measurement_url: Option<homewizard::Url>,
+
+    /// PV meter wiring, directly wired meter sees the production as negative consumption.
+    #[clap(long = "pv-meter-sign", env = "PV_METER_SIGN", default_value = "direct")]
+    pub pv_sign: Sign,
+}
+
+impl TopologyArgs {
+    pub fn connect(self, http: &http::Args) -> Result<Topology> {
+        let connect = |url: Option<homewizard::Url>, sign| {
+            url.map(|url| Ok::<_, Error>((url.client(http.client_builder()?)?, sign))).transpose()
+        };
+        Ok(Topology {
+            grid_sign: self.grid_sign,
+            battery_meter: connect(self.battery_measurement_url, self.battery_sign)?,
+            pv_meter: connect(self.pv_measurement_url, self.pv_sign)?,
+        })
+    }
+}
+
+/// Configured meters on top of the grid meter and the battery inverter.
+pub struct Topology {
+    pub grid_sign: Sign,
+    battery_meter: Option<(homewizard::Client, Sign)>,
+    pv_meter: Option<(homewizard::Client, Sign)>,
+}
+
+impl Topology {
+    /// Read the auxiliary meters, and reconcile them with the grid power and the inverter's own.
+    ///
+    /// A failing auxiliary meter only gets reported, the inverter is then trusted instead.
+    pub async fn reconcile(&self, grid: Watts, inverter: Watts) -> Flows {
+        let (battery_meter, pv_meter) = join!(
+            Self::read(self.battery_meter.as_ref(), "battery"),
+            Self::read(self.pv_meter.as_ref(), "PV"),
+        );
+        Flows::reconcile(grid, inverter, battery_meter, pv_meter)
+    }
+
+    async fn read(meter: Option<&(homewizard::Client, Sign)>, name: &str) -> Option<Watts> {
+        let (client, sign) = meter?;
+        match client.get_measurement().await {
+            Ok(metrics) => Some(sign.apply(metrics.active_power)),
+            Err(error) => {
+                warn!("failed to read the {name} meter: {error:#}");
+                None
+            }
+        }
+    }
+}
+
+/// Consistent power flows of the household, positive ones flow into the household.
+#[must_use]
+#[derive(Copy, Clone, Debug, Serialize)]
+pub struct Flows {
+    /// Positive means import, negative means export.
+    pub grid: Watts,
+
+    /// Positive means discharging, negative means charging.
+    pub battery: Watts,
+
+    /// PV production, if metered.
+    pub pv: Option<Watts>,
+}
+
+impl Flows {
+    /// Reconcile the readings, all of them in the consumption-positive meter convention
+    /// except for the inverter's own battery power, which is positive when discharging.
+    pub fn reconcile(
+        grid: Watts,
+        inverter: Watts,
+        battery_meter: Option<Watts>,
+        pv_meter: Option<Watts>,
+    ) -> Self {
+        // The meters see the charging as consumption, and the PV production as negative one:
+        let battery = battery_meter.map_or(inverter, |power| -power);
+        if battery_meter.is_some() {
+            debug!(?inverter, ?battery, discrepancy = ?(inverter - battery), "battery power");
+        }
+        Self { grid, battery, pv: pv_meter.map(|power| -power) }
+    }
+
+    /// Household consumption not covered by the PV, to be balanced by the grid and battery.
+    pub fn net_deficit(self) -> Watts {
+        self.grid + self.battery
+    }
+
+    /// Gross household consumption, if the PV production is metered.
+    pub fn household_load(self) -> Option<Watts> {
+        self.pv.map(|pv| self.net_deficit() + pv)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn reconcile_ok() {
+        // Importing 500 W and charging the battery at 1500 W, while the PV produces 2500 W:
+        let flows = Flows::reconcile(
+            Quantity(500.0),
+            Quantity(-1400.0),
+            Some(Sign::Inverted.apply(Quantity(-1500.0))),
+            Some(Quantity(-2500.0)),
+        );
+        assert_eq!(flows.battery, Quantity(-1500.0));
+        assert_eq!(flows.net_deficit(), Quantity(-1000.0));
+        assert_eq!(flows.household_load(), Some(Quantity(1500.0)));
+
+        let flows = Flows::reconcile(Quantity(500.0), Quantity(-1400.0), None, None);
+        assert_eq!(flows.battery, Quantity(-1400.0));
+        assert_eq!(flows.household_load(), None);
+    }
+}
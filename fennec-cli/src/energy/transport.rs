@@ -0,0 +1,73 @@
+//! Time-dependent transport costs of the grid operator, on top of the supplier prices.
+
+use std::str::FromStr;
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    Schedule,
+    energy,
+    ops::daily_window::DailyWindow,
+    prelude::*,
+    quantity::{Zero, price::KilowattHourPrice},
+};
+
+/// Transport cost within a daily time window, formatted as `HH:MM-HH:MM=cost`.
+///
+/// The window may wrap around midnight, for example: `22:00-06:00=0.01`.
+#[derive(Copy, Clone, Debug)]
+pub struct TransportCost {
+    pub window: DailyWindow,
+    pub cost: KilowattHourPrice,
+}
+
+impl FromStr for TransportCost {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (window, cost) = text.split_once('=').context("expected `HH:MM-HH:MM=cost`")?;
+        Ok(Self { window: window.parse()?, cost: cost.trim().parse()? })
+    }
+}
+
+/// Configured transport cost windows, the overlapping ones add up.
+#[derive(Clone, Default)]
+pub struct TransportCosts(pub Vec<TransportCost>);
+
+impl TransportCosts {
+    /// Transport cost at the specified timestamp.
+    pub fn at(&self, timestamp: DateTime<Local>) -> KilowattHourPrice {
+        let time = timestamp.time();
+        self.0.iter().filter(|cost| cost.window.contains(time)).map(|cost| cost.cost).sum()
+    }
+
+    /// Add the transport costs to the import prices, taken at the start of each interval.
+    pub fn apply_to(&self, prices: &mut Schedule<energy::Flow<KilowattHourPrice>>) {
+        for index in 0..prices.len() {
+            let cost = self.at(prices.get(index).interval.start());
+            if cost != KilowattHourPrice::ZERO {
+                prices.get_mut(index).import += cost;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn at_ok() -> Result {
+        let costs = TransportCosts(vec!["07:00-09:00=0.25".parse()?, "22:00-08:00=0.125".parse()?]);
+        let at = |hour| costs.at(Local.with_ymd_and_hms(2026, 4, 8, hour, 0, 0).unwrap());
+        assert_eq!(at(6), Quantity(0.125));
+        assert_eq!(at(7), Quantity(0.375));
+        assert_eq!(at(8), Quantity(0.25));
+        assert_eq!(at(9), Quantity(0.0));
+        assert_eq!(at(23), Quantity(0.125));
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    energy::Flow,
+    quantity::{Zero, currency::Mills, energy::WattHours, price::KilowattHourPrice},
+};
+
+/// Estimate the market value of the energy stored in the battery.
+///
+/// Only the energy above the minimal residual energy counts, and it is valued as if discharged
+/// to the grid with the specified efficiency at the average export price.
+///
+/// This accounts for the energy left in the battery by the end of the price horizon,
+/// which otherwise would look like a plain loss.
+pub fn residual_energy_value(
+    rates: impl IntoIterator<Item = Flow<KilowattHourPrice>>,
+    efficiency: Flow<f64>,
+    residual: WattHours,
+    min_residual: WattHours,
+) -> Mills {
+    let (n_rates, total_export_price) = rates
+        .into_iter()
+        .fold((0_u32, KilowattHourPrice::ZERO), |(n, total), rate| (n + 1, total + rate.export));
+    if n_rates == 0 {
+        return Mills::ZERO;
+    }
+    let usable = (residual - min_residual).max(WattHours::ZERO) * efficiency.export;
+    usable * (total_export_price / f64::from(n_rates))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn residual_energy_value_ok() {
+        let rates = [
+            Flow { import: Quantity(0.3), export: Quantity(0.1) },
+            Flow { import: Quantity(0.4), export: Quantity(0.2) },
+        ];
+        let efficiency = Flow { import: 1.0, export: 0.9 };
+        let value = residual_energy_value(
+            rates,
+            efficiency,
+            WattHours::new(3000.0),
+            WattHours::new(1000.0),
+        );
+        // 2 kWh at 0.15 ¤/kWh, minus 10% discharge losses:
+        assert!((value - Mills::new(270.0)).abs() < Mills::new(1e-9));
+    }
+
+    #[test]
+    fn residual_energy_value_below_minimum() {
+        let rates = [Flow { import: Quantity(0.3), export: Quantity(0.1) }];
+        let efficiency = Flow { import: 1.0, export: 1.0 };
+        let value =
+            residual_energy_value(rates, efficiency, WattHours::new(500.0), WattHours::new(1000.0));
+        assert_eq!(value, Mills::ZERO);
+    }
+}
@@ -1,18 +1,60 @@
-use std::{range::RangeInclusive, sync::Arc, time::Duration};
+use std::{num::NonZeroUsize, ops::ControlFlow, range::RangeInclusive, sync::Arc, time::Duration};
 
 use backon::{ConstantBuilder, Retryable};
-use chrono::{DateTime, Local, TimeDelta};
-use tokio::{sync::RwLock, time::MissedTickBehavior, try_join};
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+use fennec_modbus::contrib::deye::N_PROGRAMS;
+use itertools::Itertools;
+use tokio::{
+    join,
+    select,
+    sync::{RwLock, mpsc},
+    time::MissedTickBehavior,
+    try_join,
+};
+use tokio_util::sync::CancellationToken;
 
 use crate::{
     Schedule,
-    api::{Connections, homewizard, mini_qube},
+    api::{
+        Connections,
+        deye,
+        heartbeat,
+        homewizard,
+        inverter::Inverter,
+        mini_qube,
+        notify::Notification,
+    },
+    battery,
+    battery::WorkingMode,
     cli::EngineArgs,
     energy,
+    ev,
+    math::scenarios,
+    ops::{
+        health,
+        staleness::{Alert, Staleness},
+    },
     prelude::*,
-    quantity::{energy::WattHours, power::Watts, price::KilowattHourPrice, ratios::Percentage},
-    series::Slot,
-    solution::{Optimizer, Plan, Step},
+    quantity::{
+        Quantity,
+        Zero,
+        currency::{Currency, Mills},
+        energy::{DecawattHours, KilowattHours, WattHours},
+        power::Watts,
+        price::KilowattHourPrice,
+        ratios::Percentage,
+    },
+    solution::{
+        ExecutionTracker,
+        Flexibility,
+        Forecast,
+        Manifest,
+        Optimizer,
+        Plan,
+        Scenario,
+        Step,
+        WarmStart,
+    },
 };
 
 #[must_use]
@@ -22,6 +64,60 @@ pub struct State {
 
     /// Current solution backtrack.
     pub plan: Option<Plan>,
+
+    /// Residual energy forecast of the current plan.
+    pub forecast: Option<Forecast>,
+
+    /// Flexibility the battery could offer on top of the current plan.
+    pub flexibility: Vec<Flexibility>,
+
+    /// Recent planned steps along with the measured energy flows.
+    pub executions: ExecutionTracker,
+
+    /// Recent battery state-of-charge.
+    pub battery_history: battery::History,
+
+    /// Most recent battery reading.
+    pub battery_reading: Option<battery::Reading>,
+
+    /// Most recent power flows, reconciled from all the configured meters.
+    pub flows: Option<energy::Flows>,
+
+    /// Recent battery temperature in degrees Celsius, if the sensor is configured.
+    pub battery_temperature: Option<f64>,
+
+    /// Battery temperature band to take the efficiency of, see [`energy::temperature_band_after`].
+    pub battery_temperature_band: Option<i16>,
+
+    /// Long-term capacity fade, once there is enough state-of-health history.
+    pub battery_capacity_trend: Option<battery::CapacityTrend>,
+
+    /// Calendar year-to-date battery throughput.
+    pub battery_throughput: Option<battery::YearToDate>,
+
+    /// Current EV charging plan, if enabled.
+    pub ev_plan: Option<ev::Plan>,
+
+    /// Grid operator transport costs, already included in the plan's import prices.
+    pub transport_costs: energy::TransportCosts,
+
+    /// Currency to display the money amounts in.
+    pub currency: Currency,
+
+    /// Inverter stand-by consumption, to display its costs.
+    pub inverter_standby_power: Watts,
+
+    /// Number of engine iterations failed in a row.
+    pub n_consecutive_failures: usize,
+
+    /// Health of the individual measurement sources.
+    pub sources: health::Sources,
+}
+
+/// Freshly parsed settings to pick the reloadable ones from, see [`Engine::reload`].
+pub struct Reload {
+    pub connections: Connections,
+    pub args: EngineArgs,
 }
 
 #[must_use]
@@ -30,20 +126,87 @@ pub struct Engine {
     args: EngineArgs,
     state: Arc<RwLock<State>>,
     optimizer: Option<Optimizer>,
+
+    /// Most recent billed real-time price along with the time it was fetched at, if any.
+    real_time_price: Option<(energy::Flow<KilowattHourPrice>, DateTime<Local>)>,
+
+    /// Last time the real-time price was fetched, successfully or not.
+    real_time_price_checked_at: Option<DateTime<Local>>,
+
+    /// Cancelled on Ctrl-C, stops the engine along with any ongoing solving.
+    shutdown: CancellationToken,
+
+    /// Whether the current plan has failed to get written, so that the next iterations retry it.
+    is_write_pending: bool,
+
+    /// Working mode of the last written plan, so that only the switches get notified about.
+    notified_working_mode: Option<WorkingMode>,
+
+    /// Start of the first plan step, for which the forecast has been journaled last.
+    journaled_forecast_at: Option<DateTime<Local>>,
+
+    /// Last successful battery reading and when it was taken, to fall back on.
+    last_battery_metrics: Option<(DateTime<Local>, battery::Metrics)>,
+
+    /// Grid meter power and totals.
+    grid_staleness: Staleness<(Watts, KilowattHours, KilowattHours)>,
+
+    /// Battery state-of-charge, power, and totals.
+    battery_staleness: Staleness<(Percentage, Watts, energy::Flow<DecawattHours>)>,
 }
 
 impl Engine {
-    const BACKOFF: ConstantBuilder = ConstantBuilder::new().with_delay(Duration::from_secs(1));
+    const BACKOFF: ConstantBuilder =
+        ConstantBuilder::new().with_delay(Duration::from_secs(1)).with_jitter();
 
     #[instrument(skip_all)]
-    pub async fn start(connections: Connections, args: EngineArgs) -> Result<Self> {
+    pub async fn start(
+        connections: Connections,
+        args: EngineArgs,
+        shutdown: CancellationToken,
+    ) -> Result<Self> {
+        if args.energy_provider == energy::Provider::Zonneplan {
+            connections.zonneplan.ensure_logged_in().await?;
+        }
         let energy_profile =
             energy::Profile::read_from_file(args.energy_profile.n_balance_harmonics).await?;
+        let transport_costs = energy::TransportCosts(args.transport_costs.clone());
+        let stale_after = TimeDelta::from_std(connections.notify.stale_after)?;
+        let currency = args.currency.clone();
+        let inverter_standby_power = args.battery.inverter_standby_power;
         let this = Self {
             connections,
             args,
-            state: Arc::new(RwLock::new(State { energy_profile, plan: None })),
+            state: Arc::new(RwLock::new(State {
+                energy_profile,
+                plan: None,
+                forecast: None,
+                flexibility: Vec::new(),
+                executions: ExecutionTracker::default(),
+                battery_history: battery::History::default(),
+                battery_reading: None,
+                flows: None,
+                battery_temperature: None,
+                battery_temperature_band: None,
+                battery_capacity_trend: None,
+                battery_throughput: None,
+                ev_plan: None,
+                transport_costs,
+                currency,
+                inverter_standby_power,
+                n_consecutive_failures: 0,
+                sources: health::Sources::default(),
+            })),
             optimizer: None,
+            real_time_price: None,
+            real_time_price_checked_at: None,
+            shutdown,
+            is_write_pending: false,
+            notified_working_mode: None,
+            journaled_forecast_at: None,
+            last_battery_metrics: None,
+            grid_staleness: Staleness::new(stale_after),
+            battery_staleness: Staleness::new(stale_after),
         };
         Ok(this)
     }
@@ -52,35 +215,157 @@ impl Engine {
         self.state.clone()
     }
 
-    pub async fn run_forever(mut self) -> Result {
+    /// Run the engine iterations until too many of them fail in a row, or until shut down.
+    ///
+    /// A single failed iteration does not stop the engine: the next tick is simply another chance.
+    pub async fn run_forever(mut self, mut reloads: mpsc::Receiver<Reload>) -> Result {
         let mut interval = tokio::time::interval(self.args.interval);
         interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut failing_since: Option<DateTime<Local>> = None;
+        let mut is_failure_notified = false;
         loop {
-            interval.tick().await;
-            self.run_once().await?;
-            self.connections.heartbeat.send().await;
+            select! {
+                _ = interval.tick() => {}
+                Some(reload) = reloads.recv() => {
+                    if self.reload(reload) {
+                        interval = tokio::time::interval(self.args.interval);
+                        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+                    }
+                    continue;
+                }
+                () = self.shutdown.cancelled() => break,
+            }
+            self.connections.heartbeat.signal(heartbeat::Signal::Start, String::new()).await;
+            let result = self.run_once().await;
+            if self.shutdown.is_cancelled() {
+                break;
+            }
+            match result {
+                Ok(()) => {
+                    self.state.write().await.n_consecutive_failures = 0;
+                    let payload = self.heartbeat_payload().await;
+                    self.connections.heartbeat.signal(heartbeat::Signal::Success, payload).await;
+                    failing_since = None;
+                    if is_failure_notified {
+                        is_failure_notified = false;
+                        let notification = Notification {
+                            title: "Fennec has recovered".to_string(),
+                            message: "The engine iterations are succeeding again.".to_string(),
+                        };
+                        self.connections.notify.send(&notification).await;
+                    }
+                }
+                Err(error) => {
+                    let n_consecutive_failures = {
+                        let mut state = self.state.write().await;
+                        state.n_consecutive_failures += 1;
+                        state.n_consecutive_failures
+                    };
+                    let payload = format!("{error:#}");
+                    self.connections.heartbeat.signal(heartbeat::Signal::Failure, payload).await;
+                    if n_consecutive_failures > self.args.max_consecutive_failures {
+                        return Err(error.context("too many consecutive failures"));
+                    }
+                    error!(n_consecutive_failures, "iteration failed: {error:#}");
+                    let now = Local::now();
+                    let failing_since = *failing_since.get_or_insert(now);
+                    if !is_failure_notified
+                        && (now - failing_since).to_std().unwrap_or_default()
+                            >= self.connections.notify.notify_failures_after
+                    {
+                        is_failure_notified = true;
+                        let notification = Notification {
+                            title: "Fennec is failing".to_string(),
+                            message: format!("Failing since {failing_since}: {error:#}"),
+                        };
+                        self.connections.notify.send(&notification).await;
+                    }
+                }
+            }
         }
+        info!("the engine has stopped");
+        Ok(())
+    }
+
+    /// Summary of the current plan to attach to the successful heartbeat.
+    async fn heartbeat_payload(&self) -> String {
+        let state = self.state.read().await;
+        let payload = state.plan.as_ref().map_or_else(
+            || "No plan yet".to_string(),
+            |plan| {
+                let step = plan.schedule.get(0).value.1;
+                format!(
+                    "{} at power level {}, expected loss till {}: {}",
+                    step.working_mode,
+                    step.power_level,
+                    plan.schedule.end_index().unwrap(),
+                    Mills::from(plan.metrics.losses.total()),
+                )
+            },
+        );
+        drop(state);
+        payload
+    }
+
+    /// Swap in the polling intervals, grid meter, and heartbeats from the reloaded settings.
+    ///
+    /// Everything else, including the battery connection and the learned state, stays intact.
+    ///
+    /// # Returns
+    ///
+    /// [`true`], if the engine interval has changed.
+    fn reload(&mut self, reload: Reload) -> bool {
+        let Reload { connections, args } = reload;
+        info!(interval = ?args.interval, "reloading the settings");
+        self.connections.grid_measurement = connections.grid_measurement;
+        self.connections.topology = connections.topology;
+        self.connections.heartbeat = connections.heartbeat;
+        self.connections.battery_heartbeat = connections.battery_heartbeat;
+        self.connections.grid_heartbeat = connections.grid_heartbeat;
+        self.args.real_time_price.interval = args.real_time_price.interval;
+        let has_interval_changed = self.args.interval != args.interval;
+        self.args.interval = args.interval;
+        has_interval_changed
     }
 
     /// Run a single engine iteration.
     ///
-    /// Note that we *only write at most the upcoming battery slot* to the battery. Motivation:
+    /// Note that we *only write a few upcoming battery slots* (only one by default),
+    /// and only those which actually differ from what the battery already has. Motivation:
     ///
     /// - Potential Flash/EEPROM wear on the battery when writing all the changed slots every time.
     /// - Finite horizon problem: e.g. writing tomorrow afternoon barely makes sense
     ///   before the future prices become known.
     /// - Flaky future slots due to small oscillations in the Fourier decomposition.
+    #[expect(clippy::too_many_lines)]
     pub async fn run_once(&mut self) -> Result {
         let now = Local::now();
-        let (battery_metrics, grid_metrics) = (async || self.read_metrics().await)
-            .retry(Self::BACKOFF)
-            .notify(log_retried_error)
-            .await?;
+        let (battery_metrics, grid_metrics) =
+            join!(self.read_battery_metrics_with_retries(), self.read_grid_metrics_with_retries());
+        self.record_source_health(now, &battery_metrics, &grid_metrics).await;
+        let grid_metrics = grid_metrics?;
+        let (mut battery_metrics, is_battery_fallback) = match battery_metrics {
+            Ok(battery_metrics) => {
+                self.last_battery_metrics = Some((now, battery_metrics.clone()));
+                (battery_metrics, false)
+            }
+            Err(error) => (self.fall_back_on_last_battery_metrics(now, error)?, true),
+        };
 
-        let net_deficit = grid_metrics.active_power + battery_metrics.active_power;
+        let flows = self
+            .connections
+            .topology
+            .reconcile(grid_metrics.active_power, battery_metrics.active_power)
+            .await;
+        // The metered battery power is more accurate than what the inverter reports:
+        battery_metrics.active_power = flows.battery;
+        self.state.write().await.flows = Some(flows);
+        let net_deficit = flows.net_deficit();
         let balance = energy::Balance::new(self.args.battery.power_limits, net_deficit);
         debug!(
             ?net_deficit,
+            pv = ?flows.pv,
+            household_load = ?flows.household_load(),
             battery.active_power = ?battery_metrics.active_power,
             battery.eps_active_power = ?battery_metrics.eps_active_power,
             battery.residual_energy = ?battery_metrics.residual_energy(),
@@ -92,34 +377,52 @@ impl Engine {
             ?balance.grid.import,
             "measurements",
         );
+        let has_residual_energy_changed = if is_battery_fallback {
+            // Nothing has been measured, so there is nothing to learn from:
+            false
+        } else {
+            self.track_execution(now, &battery_metrics, &grid_metrics).await?;
+            self.check_staleness(now, &battery_metrics, &grid_metrics).await;
+            self.update_energy_profile(now, balance, &battery_metrics).await?
+        };
 
         let initial_residual_energy: WattHours<usize> =
             (WattHours::from(battery_metrics.residual_energy())).into();
         let battery_capacity = battery_metrics.actual_capacity();
         let allowed_residual_energy = battery_metrics.allowed_residual_energy();
+        let has_real_time_price_changed = self.refresh_real_time_price(now).await;
+        let temperature_band = self.state.read().await.battery_temperature_band;
 
-        let has_residual_energy_changed =
-            self.update_energy_profile(now, balance, &battery_metrics).await?;
-
-        let optimizer = match self.optimizer.take() {
-            Some(mut optimizer) if optimizer.matches(battery_capacity, allowed_residual_energy) => {
+        let mut optimizer = match self.optimizer.take() {
+            Some(mut optimizer)
+                if optimizer.matches(
+                    battery_capacity,
+                    allowed_residual_energy,
+                    temperature_band,
+                ) =>
+            {
                 let has_solution_space_advanced = optimizer.advance_to(now);
-                if !has_solution_space_advanced && !has_residual_energy_changed {
+                if has_real_time_price_changed {
+                    self.apply_real_time_price(&mut optimizer, now).await?;
+                }
+                if !has_solution_space_advanced
+                    && !has_residual_energy_changed
+                    && !has_real_time_price_changed
+                {
                     self.optimizer = Some(optimizer);
-                    return Ok(());
+                    let write_result = if self.is_write_pending {
+                        self.retry_write_plan(&battery_metrics).await
+                    } else {
+                        Ok(())
+                    };
+                    self.steer(balance).await?;
+                    return write_result;
                 }
 
-                let new_prices = if optimizer.solution_space().duration() <= TimeDelta::hours(12) {
-                    // Try to extend the price horizon if it's getting short:
-                    let prices = self.args.energy_provider.get_future_prices(now).await?;
-                    (prices.end_index() != optimizer.solution_space().end_index()).then_some(prices)
-                } else {
-                    None
-                };
-
-                if let Some(prices) = new_prices {
+                if let Some(prices) = self.extend_prices(&optimizer, now).await {
                     info!("optimizer invalidated: new prices arrived");
-                    self.rebuild_optimizer(&prices, battery_capacity, allowed_residual_energy).await
+                    self.rebuild_optimizer(now, prices, battery_capacity, allowed_residual_energy)
+                        .await?
                 } else {
                     info!(?initial_residual_energy, "optimizing current state");
                     optimizer.optimize_state(0, initial_residual_energy);
@@ -129,63 +432,574 @@ impl Engine {
 
             stale_optimizer => {
                 if stale_optimizer.is_some() {
-                    info!("optimizer invalidated: battery parameters changed");
+                    info!(?temperature_band, "optimizer invalidated: battery parameters changed");
                 } else {
                     info!("initializing optimizer: cold start");
                 }
-                let prices = self.args.energy_provider.get_future_prices(now).await?;
-                self.rebuild_optimizer(&prices, battery_capacity, allowed_residual_energy).await
+                let prices =
+                    self.args.energy_provider.get_future_prices(&self.connections, now).await?;
+                self.rebuild_optimizer(now, prices, battery_capacity, allowed_residual_energy)
+                    .await?
             }
         };
 
-        let plan = optimizer
-            .solution_space()
-            .backtrack(initial_residual_energy)
-            .inspect(|plan| plan.trace_summary(battery_metrics.design_capacity))?;
-        // TODO: potential improvement – make the number of written slots configurable:
-        let slot = plan.schedule.get(0);
-        let working_mode = slot.value.1.working_mode;
-        if self.args.dry_run {
-            warn!("not writing the schedule to the battery, just scouting");
-        } else {
-            self.write_schedule_slot(slot, battery_metrics.allowed_soc).await?;
-            self.connections.home_assistant_working_mode.post(&format!("{working_mode:?}")).await;
+        optimizer.fall_back_to_cold_start(initial_residual_energy);
+        let mut plan = optimizer.solution_space().backtrack(initial_residual_energy)?;
+        if let Some(threshold) = self.args.smoothing_threshold {
+            plan = optimizer.smooth(plan, initial_residual_energy, threshold)?;
+        }
+        if matches!(self.connections.battery, Inverter::Deye(_)) {
+            let extra_loss;
+            (plan, extra_loss) = optimizer.compress(
+                plan,
+                initial_residual_energy,
+                TimeDelta::days(1),
+                N_PROGRAMS,
+            )?;
+            if extra_loss > Mills::ZERO {
+                warn!(
+                    extra_loss = %self.args.currency.format(extra_loss),
+                    "compressed the plan to fit the time-of-use table",
+                );
+            }
         }
+        let plan = plan
+            .with_residual_energy_value(
+                optimizer.battery_efficiency(),
+                allowed_residual_energy.start.into(),
+            )
+            .with_n_cycles(battery_metrics.design_capacity, self.args.battery.cycle_depth)
+            .with_manifest(optimizer.manifest());
+        let plan = match &self.state.read().await.plan {
+            Some(previous_plan) => {
+                plan.with_improvement(&optimizer, initial_residual_energy, previous_plan)
+            }
+            None => plan,
+        };
+        plan.trace_summary();
+        if self.args.validate {
+            plan.validate(initial_residual_energy, allowed_residual_energy, self.args.quantum)
+                .context("the plan violates the physical invariants")?;
+        }
+        // Failing to write the plan should not lose it, so it gets committed anyway:
+        let write_result = self.write_plan(&plan, &battery_metrics).await;
+        self.is_write_pending = write_result.is_err();
+        if write_result.is_ok() {
+            self.notify_working_mode(&plan).await;
+        }
+        self.notify_rates(now, &plan).await;
+        self.publish_plan(&plan, &optimizer, initial_residual_energy, &battery_metrics, now).await;
+        let forecast = Forecast::new(&plan, now, battery_metrics.actual_capacity());
+        self.journal_forecast(&plan, &forecast).await;
+        let flexibility = optimizer.flexibility(&plan);
 
         // Commit the new state:
-        self.state.write().await.plan = Some(plan);
+        {
+            let mut state = self.state.write().await;
+            state.plan = Some(plan);
+            state.forecast = Some(forecast);
+            state.flexibility = flexibility;
+        }
         self.optimizer = Some(optimizer);
 
+        self.steer(balance).await?;
+        write_result.context("the plan is kept to retry writing it on the next iterations")
+    }
+
+    /// Simulate the scenario under the current conditions and log its costs next to the optimal plan,
+    /// without touching the battery.
+    #[instrument(skip_all)]
+    pub async fn what_if(&self, scenario: &Scenario) -> Result {
+        let now = Local::now();
+        let (battery_metrics, _) = self.read_metrics().await?;
+        let initial_residual_energy: WattHours<usize> =
+            (WattHours::from(battery_metrics.residual_energy())).into();
+        let prices = self.args.energy_provider.peek_future_prices(&self.connections, now).await?;
+        let prices = self.adjust_prices(now, prices).await?;
+        let previous_ev_plan = self.state.read().await.ev_plan.clone();
+        let ev_plan = self.args.ev.plan(&prices, now, previous_ev_plan.as_ref());
+        let optimizer = self
+            .solve_optimizer(
+                now,
+                &prices,
+                battery_metrics.actual_capacity(),
+                battery_metrics.allowed_residual_energy(),
+                ev_plan,
+            )
+            .await?;
+        let plan = optimizer.solution_space().backtrack(initial_residual_energy)?;
+
+        let currency = &self.args.currency;
+        let decision_at = |time| scenario.decision_at(time);
+        for (interval, step) in optimizer.simulate(initial_residual_energy, decision_at) {
+            info!(
+                start = %interval.start().format("%b %d %H:%M"),
+                working_mode = %step.working_mode,
+                power_level = ?step.power_level,
+                grid.import = ?step.energy_balance.grid.import,
+                grid.export = ?step.energy_balance.grid.export,
+                residual_energy_after = ?step.residual_energy_after,
+                loss = %currency.format(step.metrics.losses.total().into()),
+                "scenario step",
+            );
+        }
+        let metrics = optimizer
+            .evaluate(initial_residual_energy, decision_at)
+            .context("the scenario ends up in a state without a solution")?;
+        let scenario_loss: Mills = metrics.losses.total().into();
+        let optimal_loss: Mills = plan.metrics.losses.total().into();
+        info!(
+            scenario = %currency.format(scenario_loss),
+            optimal = %currency.format(optimal_loss),
+            difference = %currency.format(scenario_loss - optimal_loss),
+            "total loss",
+        );
         Ok(())
     }
 
-    /// Read the MiniQube and HomeWizard P1 metrics simultaneously.
-    async fn read_metrics(&self) -> Result<(mini_qube::Metrics, homewizard::EnergyMetrics)> {
-        try_join!(
+    /// Try to extend the price horizon if it's getting short.
+    ///
+    /// Returns [`None`] if there are no new prices, the current optimizer is still good to go then.
+    async fn extend_prices(
+        &self,
+        optimizer: &Optimizer,
+        now: DateTime<Local>,
+    ) -> Option<Schedule<energy::Flow<KilowattHourPrice>>> {
+        if optimizer.solution_space().duration() > TimeDelta::hours(12) {
+            return None;
+        }
+        match self.args.energy_provider.get_future_prices(&self.connections, now).await {
+            Ok(prices) => {
+                (prices.end_index() != optimizer.solution_space().end_index()).then_some(prices)
+            }
+            Err(error) => {
+                warn!("keeping the current prices: {error:#}");
+                None
+            }
+        }
+    }
+
+    /// Write the plan to the battery, if not dry run.
+    async fn write_plan(&self, plan: &Plan, battery_metrics: &battery::Metrics) -> Result {
+        let has_forced_charging =
+            plan.schedule.iter().any(|slot| slot.value.1.working_mode == WorkingMode::Charge);
+        ensure!(
+            !(self.args.battery.no_grid_charge && has_forced_charging),
+            "refusing to write the forced charging, since the grid charging is forbidden",
+        );
+        if self.args.dry_run {
+            warn!("not writing the schedule to the battery, just scouting");
+            return Ok(());
+        }
+        match &self.connections.battery {
+            Inverter::MiniQube(client) => {
+                self.write_schedule(client, plan, battery_metrics).await?;
+            }
+            Inverter::Deye(client) => {
+                self.write_time_of_use(client, plan, battery_metrics).await?;
+            }
+            Inverter::HomeWizard(client) => {
+                let working_mode = plan.schedule.get(0).value.1.working_mode;
+                (|| async { client.write_working_mode(working_mode).await })
+                    .retry(Self::BACKOFF)
+                    .notify(log_retried_error)
+                    .await?;
+            }
+            Inverter::Victron(_) => {}
+        }
+        let working_mode = plan.schedule.get(0).value.1.working_mode;
+        self.connections.home_assistant_working_mode.post(&format!("{working_mode:?}")).await;
+        let heat_pump_signal = self.args.heat_pump.signal(plan);
+        debug!(%heat_pump_signal, is_on = heat_pump_signal.is_on(), "posting the heat pump signal");
+        self.connections.home_assistant_heat_pump.post(&heat_pump_signal).await;
+        Ok(())
+    }
+
+    /// Retry writing the current plan, which has failed to get written before.
+    ///
+    /// The plan is not stale, since the solution space has not advanced since then:
+    /// otherwise, the iteration would have come up with a new plan, and written that one instead.
+    async fn retry_write_plan(&mut self, battery_metrics: &battery::Metrics) -> Result {
+        let Some(plan) = self.state.read().await.plan.clone() else {
+            return Ok(());
+        };
+        info!("retrying to write the pending plan");
+        self.write_plan(&plan, battery_metrics)
+            .await
+            .context("the plan is kept to retry writing it on the next iterations")?;
+        self.is_write_pending = false;
+        self.notify_working_mode(&plan).await;
+        Ok(())
+    }
+
+    /// Notify about the working mode switch along with the expected loss, if not dry run.
+    async fn notify_working_mode(&mut self, plan: &Plan) {
+        if self.args.dry_run {
+            return;
+        }
+        let step = plan.schedule.get(0).value.1;
+        if self.notified_working_mode == Some(step.working_mode) {
+            return;
+        }
+        self.notified_working_mode = Some(step.working_mode);
+        let total_loss = Mills::from(plan.metrics.losses.total());
+        let notification = Notification {
+            title: format!("Switched to {}", step.working_mode),
+            message: format!(
+                "Power level {}, expected loss till {}: {total_loss}",
+                step.power_level,
+                plan.schedule.end_index().unwrap(),
+            ),
+        };
+        self.connections.notify.send(&notification).await;
+    }
+
+    /// Notify about the tomorrow's prices once they have arrived in the plan, if enabled.
+    ///
+    /// The prices have just arrived, if the previous plan did not have them yet. It is the previous
+    /// plan rather than an in-memory flag, so that a restart does not repeat the notification.
+    /// Without a previous plan, there is no telling, and so no notification either.
+    async fn notify_rates(&self, now: DateTime<Local>, plan: &Plan) {
+        let Some(window) = self.connections.notify.rates_window else {
+            return;
+        };
+        let Some(tomorrow) = now.date_naive().succ_opt() else {
+            return;
+        };
+        let is_notified = self
+            .state
+            .read()
+            .await
+            .plan
+            .as_ref()
+            .is_none_or(|previous_plan| previous_plan.has_prices_on(tomorrow));
+        if is_notified {
+            return;
+        }
+        let Ok(window) = TimeDelta::from_std(window) else {
+            return;
+        };
+        let prices = plan.schedule.map(|(price, _)| *price);
+        if let Some(notification) = energy::rates_digest(&prices, tomorrow, window) {
+            info!(%tomorrow, "notifying about the prices");
+            self.connections.notify.send(&notification).await;
+        }
+    }
+
+    /// Journal the residual energy forecast of the first plan within each step.
+    ///
+    /// Failing to journal is only reported, the forecast is then retried with the next plan.
+    async fn journal_forecast(&mut self, plan: &Plan, forecast: &Forecast) {
+        let step_start = plan.schedule.get(0).interval.start();
+        if self.journaled_forecast_at == Some(step_start) {
+            return;
+        }
+        match forecast.append_to_journal().await {
+            Ok(()) => self.journaled_forecast_at = Some(step_start),
+            Err(error) => warn!("failed to journal the forecast: {error:#}"),
+        }
+    }
+
+    /// Publish the plan summary to Home Assistant, if configured.
+    async fn publish_plan(
+        &self,
+        plan: &Plan,
+        optimizer: &Optimizer,
+        initial_residual_energy: WattHours<usize>,
+        battery_metrics: &battery::Metrics,
+        now: DateTime<Local>,
+    ) {
+        let entities = &self.connections.home_assistant_entities;
+        if !entities.is_configured() {
+            return;
+        }
+
+        let next_hour = now + TimeDelta::hours(1);
+        if let Some(slot) = plan.schedule.iter().find(|slot| slot.interval.end() > next_hour) {
+            let attributes = serde_json::json!({
+                "friendly_name": "Fennec next hour working mode",
+                "power_level": slot.value.1.power_level.0,
+                "icon": "mdi:battery-clock",
+            });
+            let working_mode = slot.value.1.working_mode.to_string();
+            entities.post("sensor.fennec_next_hour_working_mode", working_mode, attributes).await;
+        }
+
+        let actual_capacity = battery_metrics.actual_capacity();
+        let forecast = plan
+            .schedule
+            .iter()
+            .map(|slot| {
+                let residual_energy = WattHours::from(slot.value.1.residual_energy_after);
+                serde_json::json!({
+                    "datetime": slot.interval.end(),
+                    "state_of_charge": (100.0 * residual_energy / actual_capacity).round(),
+                })
+            })
+            .collect_vec();
+        let attributes = serde_json::json!({
+            "friendly_name": "Fennec state-of-charge forecast",
+            "unit_of_measurement": "%",
+            "device_class": "battery",
+            "forecast": forecast,
+        });
+        // By the end of the current interval:
+        let state_of_charge = forecast.first().map(|point| point["state_of_charge"].clone());
+        entities.post("sensor.fennec_state_of_charge_forecast", state_of_charge, attributes).await;
+
+        // Compared to what the same conditions would cost without the battery:
+        let baseline = optimizer
+            .simulate(initial_residual_energy, |_| Some((WorkingMode::Idle, Percentage::FULL)));
+        let tomorrow = now.date_naive().succ_opt().unwrap();
+        let daily_savings = plan.grid_savings_before(&baseline, tomorrow);
+        let attributes = serde_json::json!({
+            "friendly_name": "Fennec expected daily savings",
+            "unit_of_measurement": self.args.currency.symbol,
+            "icon": "mdi:cash",
+            "plan_savings": plan.grid_savings_before(&baseline, NaiveDate::MAX).0 / 1000.0,
+        });
+        entities
+            .post(
+                "sensor.fennec_expected_daily_savings",
+                daily_savings.round_to(self.args.currency.precision).0 / 1000.0,
+                attributes,
+            )
+            .await;
+    }
+
+    /// Steer the inverters which lack a built-in schedule, if not dry run.
+    ///
+    /// Those need their setpoint updated on every tick according to the current balance.
+    async fn steer(&self, balance: energy::Balance<Watts>) -> Result {
+        let Inverter::Victron(client) = &self.connections.battery else {
+            return Ok(());
+        };
+        if self.args.dry_run {
+            return Ok(());
+        }
+        let Some(step) =
+            self.state.read().await.plan.as_ref().map(|plan| plan.schedule.get(0).value.1)
+        else {
+            return Ok(());
+        };
+        let max_flow = self.args.battery.power_limits.max_effective_flow(Watts::ZERO);
+        // The ESS setpoint is on the grid side, so it is the battery flow plus the household deficit:
+        let grid_flow = balance
+            .with_working_mode(step.working_mode, max_flow * step.power_level.to_ratio())
+            .grid;
+        let setpoint = grid_flow.import - grid_flow.export;
+        (|| async { client.write_setpoint(setpoint).await })
+            .retry(Self::BACKOFF)
+            .notify(log_retried_error)
+            .await
+            .context("failed to write the setpoint to the battery")
+    }
+
+    /// Read the battery and grid meter metrics simultaneously.
+    async fn read_metrics(&self) -> Result<(battery::Metrics, homewizard::EnergyMetrics)> {
+        try_join!(self.read_battery_metrics(), self.read_grid_metrics())
+    }
+
+    async fn read_battery_metrics_with_retries(&self) -> Result<battery::Metrics> {
+        (async || self.read_battery_metrics().await)
+            .retry(Self::BACKOFF)
+            .notify(log_retried_error)
+            .await
+    }
+
+    async fn read_grid_metrics_with_retries(&self) -> Result<homewizard::EnergyMetrics> {
+        (async || self.read_grid_metrics().await)
+            .retry(Self::BACKOFF)
+            .notify(log_retried_error)
+            .await
+    }
+
+    async fn read_battery_metrics(&self) -> Result<battery::Metrics> {
+        self.connections.battery.read_metrics().await.context("failed to read the battery metrics")
+    }
+
+    async fn read_grid_metrics(&self) -> Result<homewizard::EnergyMetrics> {
+        let metrics = self
+            .connections
+            .grid_measurement
+            .get_measurement()
+            .await
+            .context("failed to retrieve the grid measurement")?;
+        Ok(self.connections.topology.grid_sign.apply_to(metrics))
+    }
+
+    /// Record the per-source health, and send the heartbeats of the healthy sources.
+    ///
+    /// The sources are retried independently, so that one failing does not mask the other.
+    async fn record_source_health(
+        &self,
+        now: DateTime<Local>,
+        battery_metrics: &Result<battery::Metrics>,
+        grid_metrics: &Result<homewizard::EnergyMetrics>,
+    ) {
+        let sources = {
+            let mut state = self.state.write().await;
+            state.sources.battery.record(now, battery_metrics);
+            state.sources.grid.record(now, grid_metrics);
+            state.sources.clone()
+        };
+        join!(
             async {
-                self.connections
-                    .battery
-                    .read_metrics()
-                    .await
-                    .context("failed to read the battery metrics")
+                if sources.battery.is_healthy() {
+                    self.connections.battery_heartbeat.send().await;
+                }
             },
             async {
-                self.connections
-                    .grid_measurement
-                    .get_measurement()
-                    .await
-                    .context("failed to retrieve the grid measurement")
-            }
-        )
+                if sources.grid.is_healthy() {
+                    self.connections.grid_heartbeat.send().await;
+                }
+            },
+        );
+    }
+
+    /// Make up the battery metrics from the last successful reading,
+    /// so that a short Modbus hiccup does not stop the planning.
+    ///
+    /// The state-of-charge gets a haircut, and the battery is assumed idle.
+    fn fall_back_on_last_battery_metrics(
+        &self,
+        now: DateTime<Local>,
+        error: Error,
+    ) -> Result<battery::Metrics> {
+        let Some((read_at, last_metrics)) = &self.last_battery_metrics else {
+            return Err(error);
+        };
+        let age = (now - *read_at).to_std().unwrap_or_default();
+        if age > self.args.battery_fallback_max_age {
+            return Err(error.context("the last battery reading is too old to fall back on"));
+        }
+        let haircut = self.args.battery_fallback_soc_haircut;
+        let state_of_charge = Quantity(last_metrics.state_of_charge.0.saturating_sub(haircut.0));
+        warn!(
+            ?age,
+            last_state_of_charge = ?last_metrics.state_of_charge,
+            ?state_of_charge,
+            "falling back on the last battery reading: {error:#}",
+        );
+        Ok(battery::Metrics {
+            state_of_charge,
+            active_power: Watts::ZERO,
+            eps_active_power: Watts::ZERO,
+            ..last_metrics.clone()
+        })
+    }
+
+    /// Notify about the measurements which have not changed for too long.
+    async fn check_staleness(
+        &mut self,
+        now: DateTime<Local>,
+        battery_metrics: &battery::Metrics,
+        grid_metrics: &homewizard::EnergyMetrics,
+    ) {
+        let grid_alert = self
+            .grid_staleness
+            .update(now, (grid_metrics.active_power, grid_metrics.import, grid_metrics.export));
+
+        // Idle battery legitimately reports constant values, unlike the power of a working one:
+        let battery_alert = if battery_metrics.active_power == Watts::ZERO {
+            self.battery_staleness.reset()
+        } else {
+            self.battery_staleness.update(
+                now,
+                (
+                    battery_metrics.state_of_charge,
+                    battery_metrics.active_power,
+                    battery_metrics.total_grid_flow,
+                ),
+            )
+        };
+
+        for (source, alert) in [("grid meter", grid_alert), ("battery", battery_alert)] {
+            let notification = match alert {
+                Some(Alert::Stale { since }) => {
+                    warn!(source, %since, "measurements are stale");
+                    Notification {
+                        title: format!("The {source} measurements are stale"),
+                        message: format!("Unchanged since {since}, the {source} may be offline."),
+                    }
+                }
+                Some(Alert::Recovered) => {
+                    info!(source, "measurements are changing again");
+                    Notification {
+                        title: format!("The {source} measurements are back"),
+                        message: format!("The {source} measurements are changing again."),
+                    }
+                }
+                None => continue,
+            };
+            self.connections.notify.send(&notification).await;
+        }
+    }
+
+    /// Track the measured energy flows against the current plan, and journal the completed step.
+    #[expect(clippy::significant_drop_tightening)]
+    async fn track_execution(
+        &self,
+        now: DateTime<Local>,
+        battery_metrics: &battery::Metrics,
+        grid_metrics: &homewizard::EnergyMetrics,
+    ) -> Result {
+        let actual = energy::Balance {
+            grid: energy::Flow::from_net(grid_metrics.active_power),
+            battery: energy::Flow::from_net(-battery_metrics.active_power),
+        };
+        let completed = {
+            let mut state = self.state.write().await;
+            let state = &mut *state;
+            state.executions.track(
+                now,
+                actual,
+                battery_metrics.residual_energy().into(),
+                state.plan.as_ref(),
+            )
+        };
+        if let Some(execution) = completed {
+            info!(
+                interval = ?execution.interval,
+                residual_energy_drift = ?execution.residual_energy_drift(),
+                "completed step",
+            );
+            execution.append_to_journal().await?;
+        }
+        Ok(())
     }
 
-    /// Track the balance and battery metrics and update the persistent energy profile.
+    /// Track the balance and battery metrics, update the persistent energy profile and history.
     async fn update_energy_profile(
         &self,
         now: DateTime<Local>,
         balance: energy::Balance<Watts>,
-        battery_metrics: &mini_qube::Metrics,
+        battery_metrics: &battery::Metrics,
     ) -> Result<bool> {
+        let battery_temperature = self.connections.battery_temperature.get().await;
+        {
+            let mut state = self.state.write().await;
+            state.battery_history.push(now, battery_metrics.state_of_charge);
+            state.battery_reading =
+                Some(battery::Reading::new(now, battery_metrics, self.args.battery.power_limits));
+            state.battery_temperature = battery_temperature;
+            state.battery_temperature_band = battery_temperature.map(|temperature| {
+                energy::temperature_band_after(temperature, state.battery_temperature_band)
+            });
+            let is_health_recorded = state.energy_profile.battery.health.push(
+                now,
+                battery_metrics.state_of_health,
+                battery_metrics.design_capacity,
+            );
+            if is_health_recorded || state.battery_capacity_trend.is_none() {
+                state.battery_capacity_trend =
+                    state.energy_profile.battery.health.trend(self.args.battery.health_threshold);
+            }
+            let total_grid_flow = battery_metrics.total_grid_flow;
+            state.energy_profile.battery.throughput.update(now, total_grid_flow);
+            state.battery_throughput =
+                state.energy_profile.battery.throughput.year_to_date(now, total_grid_flow);
+        }
         let energy_profile = &mut self.state.write().await.energy_profile;
         energy_profile.energy.update(
             balance,
@@ -193,54 +1007,293 @@ impl Engine {
             now,
             self.args.energy_profile.balance_half_life,
         );
-        let is_residual_energy_changed = energy_profile
-            .battery
-            .track(battery_metrics, self.args.energy_profile.battery_efficiency_half_life_factor);
+        let is_residual_energy_changed = energy_profile.battery.track(
+            battery_metrics,
+            battery_temperature,
+            self.args.energy_profile.battery_efficiency_half_life_factor,
+        );
         energy_profile.write_to_file().await.context("failed to write the energy profile")?;
         Ok(is_residual_energy_changed)
     }
 
-    /// Rebuild [`Optimizer`] from scratch.
+    /// Rebuild [`Optimizer`] from scratch, re-planning and announcing the EV charging first.
     async fn rebuild_optimizer(
         &self,
+        now: DateTime<Local>,
+        prices: Schedule<energy::Flow<KilowattHourPrice>>,
+        battery_capacity: WattHours,
+        allowed_residual_energy: RangeInclusive<WattHours<usize>>,
+    ) -> Result<Optimizer> {
+        let prices = self.adjust_prices(now, prices).await?;
+        let ev_plan = self.update_ev_plan(now, &prices).await;
+        self.solve_optimizer(now, &prices, battery_capacity, allowed_residual_energy, ev_plan).await
+    }
+
+    /// Sanity-check the supplier prices, and add the billing and transport costs on top.
+    async fn adjust_prices(
+        &self,
+        now: DateTime<Local>,
+        mut prices: Schedule<energy::Flow<KilowattHourPrice>>,
+    ) -> Result<Schedule<energy::Flow<KilowattHourPrice>>> {
+        self.args
+            .price_sanity
+            .apply_to(&mut prices, now)
+            .context("the provider prices failed the sanity check")?;
+        self.args.billing.apply_to(&mut prices);
+        self.state.read().await.transport_costs.apply_to(&mut prices);
+        Ok(prices)
+    }
+
+    /// Solve a new [`Optimizer`] over the adjusted prices, without any side effects.
+    ///
+    /// The solving is logged every 10% of the intervals, and gets aborted on shutdown.
+    async fn solve_optimizer(
+        &self,
+        now: DateTime<Local>,
         prices: &Schedule<energy::Flow<KilowattHourPrice>>,
         battery_capacity: WattHours,
         allowed_residual_energy: RangeInclusive<WattHours<usize>>,
-    ) -> Optimizer {
+        ev_plan: Option<ev::Plan>,
+    ) -> Result<Optimizer> {
+        let (energy_profile, temperature_band, warm_start, throughput_pace) = {
+            let state = self.state.read().await;
+            let warm_start = self.args.warm_start_margin.zip(state.plan.as_ref()).map(
+                |(margin, previous_plan)| {
+                    WarmStart::new(previous_plan, (battery_capacity * margin).into())
+                },
+            );
+            let throughput_pace = self
+                .args
+                .battery
+                .yearly_throughput
+                .zip(state.battery_throughput)
+                .and_then(|(budget, year_to_date)| year_to_date.pace(budget));
+            (
+                state.energy_profile.clone(),
+                state.battery_temperature_band,
+                warm_start,
+                throughput_pace,
+            )
+        };
+        if let Some(throughput_pace) = throughput_pace
+            && throughput_pace > 1.0
+        {
+            warn!(throughput_pace, "ahead of the yearly throughput budget");
+        }
         let min_final_residual_energy: WattHours<usize> =
             (battery_capacity * self.args.min_final_soc).into();
+        let n_threads = self
+            .args
+            .threads
+            .unwrap_or_else(|| std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN));
         let mut optimizer = Optimizer::new(
-            self.state.read().await.energy_profile.clone(),
+            energy_profile,
             &self.args.battery,
             battery_capacity,
             allowed_residual_energy,
             min_final_residual_energy.min(allowed_residual_energy.last),
-        );
-        optimizer.solve(prices);
-        optimizer
+            ev_plan,
+            Manifest::new(
+                self.args.energy_provider,
+                now,
+                self.args.battery.clone(),
+                self.args.min_final_soc,
+                temperature_band,
+            ),
+        )
+        .with_n_threads(n_threads)
+        .with_quantum(self.args.quantum)
+        .with_warm_start(warm_start)
+        .with_degradation_cost_factor(throughput_pace.unwrap_or(1.0).max(1.0))
+        .with_load_factors(scenarios::normal_factors(
+            self.args.n_load_scenarios,
+            self.args.load_deviation.to_ratio(),
+        ))
+        .with_load_percentile(self.args.load_percentile);
+        optimizer.solve(prices, |n_processed, n_total| {
+            if n_processed.is_multiple_of((n_total / 10).max(1)) {
+                info!(n_processed, n_total, "solving…");
+            }
+            if self.shutdown.is_cancelled() {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })?;
+        self.apply_real_time_price(&mut optimizer, now).await?;
+        Ok(optimizer)
     }
 
-    /// Write the schedule slot to the battery, if not dry run.
-    async fn write_schedule_slot(
+    /// Fetch the real-time price, if it is enabled and it is time to.
+    ///
+    /// The previous price is kept on errors, until it gets older than the horizon.
+    ///
+    /// Returns [`true`] if the price has changed.
+    async fn refresh_real_time_price(&mut self, now: DateTime<Local>) -> bool {
+        if let Some(checked_at) = self.real_time_price_checked_at
+            && (now - checked_at)
+                .to_std()
+                .is_ok_and(|elapsed| elapsed < self.args.real_time_price.interval)
+        {
+            return false;
+        }
+        self.real_time_price_checked_at = Some(now);
+        match self.connections.real_time_price.get_price().await {
+            Ok(Some(price)) => {
+                let price = self.args.billing.apply(price);
+                let has_changed =
+                    self.real_time_price.is_none_or(|(previous, _)| previous != price);
+                self.real_time_price = Some((price, now));
+                has_changed
+            }
+            Ok(None) => false,
+            Err(error) => {
+                let Some((_, fetched_at)) = self.real_time_price else {
+                    warn!("failed to fetch the real-time price: {error:#}");
+                    return false;
+                };
+                if (now - fetched_at)
+                    .to_std()
+                    .is_ok_and(|elapsed| elapsed < self.args.real_time_price.horizon)
+                {
+                    warn!("keeping the previous real-time price: {error:#}");
+                    false
+                } else {
+                    warn!("dropping the expired real-time price: {error:#}");
+                    self.real_time_price = None;
+                    true
+                }
+            }
+        }
+    }
+
+    /// Override the short horizon with the real-time price plus the transport costs,
+    /// restore the day-ahead prices elsewhere, and re-solve the changed intervals.
+    async fn apply_real_time_price(
         &self,
-        slot: Slot<&(energy::Flow<KilowattHourPrice>, Step)>,
-        allowed_soc: RangeInclusive<Percentage>,
+        optimizer: &mut Optimizer,
+        now: DateTime<Local>,
     ) -> Result {
-        let index = mini_qube::schedule::index_of(slot.interval);
-        let slot = mini_qube::schedule::make_slot(
-            index,
-            slot.value.1.working_mode,
-            allowed_soc,
-            self.args.battery.power_limits,
-        );
-        (|| async { self.connections.battery.write_schedule_slot(index, slot).await })
+        let until = now + TimeDelta::from_std(self.args.real_time_price.horizon)?;
+        let price = self.real_time_price.map(|(price, _)| price);
+        let state = self.state.read().await;
+        let n_intervals = optimizer.override_prices(until, |start| {
+            price.map(|price| energy::Flow {
+                import: price.import + state.transport_costs.at(start),
+                export: price.export,
+            })
+        });
+        drop(state);
+        if n_intervals != 0 {
+            info!(?price, n_intervals, "applied the real-time price");
+        }
+        Ok(())
+    }
+
+    /// Re-plan the EV charging against the new prices, and notify the webhook.
+    async fn update_ev_plan(
+        &self,
+        now: DateTime<Local>,
+        prices: &Schedule<energy::Flow<KilowattHourPrice>>,
+    ) -> Option<ev::Plan> {
+        let previous = self.state.read().await.ev_plan.clone();
+        let ev_plan = self.args.ev.plan(prices, now, previous.as_ref())?;
+        info!(deadline = %ev_plan.deadline, n_windows = ev_plan.windows.len(), "planned EV charging");
+        self.connections.ev_webhook.post(&ev_plan).await;
+        self.state.write().await.ev_plan = Some(ev_plan.clone());
+        Some(ev_plan)
+    }
+
+    /// Write the upcoming programs to the Deye time-of-use table.
+    async fn write_time_of_use(
+        &self,
+        client: &deye::Client,
+        plan: &Plan,
+        battery_metrics: &battery::Metrics,
+    ) -> Result {
+        let programs = plan
+            .schedule
+            .iter()
+            .map(|slot| {
+                let step = slot.value.1;
+                let program = deye::schedule::Program::new(
+                    step.working_mode,
+                    step.power_level,
+                    self.allowed_soc_within(&step, battery_metrics),
+                    battery_metrics.state_of_charge,
+                    self.args.battery.power_limits,
+                );
+                (slot.interval.start(), program)
+            })
+            .collect_vec();
+        (|| async {
+            let current = client.read_time_of_use().await?;
+            let time_of_use = deye::schedule::make_time_of_use(&current, programs.iter().copied());
+            client.write_time_of_use(&current, time_of_use).await
+        })
+        .retry(Self::BACKOFF)
+        .notify(log_retried_error)
+        .await
+        .context("failed to write the time-of-use table to the battery")
+    }
+
+    /// Write the upcoming schedule slots to the battery.
+    async fn write_schedule(
+        &self,
+        client: &mini_qube::Client,
+        plan: &Plan,
+        battery_metrics: &battery::Metrics,
+    ) -> Result {
+        let steps = plan.schedule.iter().map(|slot| (slot.interval, slot.value.1));
+        let slots = mini_qube::schedule::indices_over(steps)
+            .map(|(index, step)| {
+                let allowed_soc = self.allowed_soc_within(&step, battery_metrics);
+                // Mirror the backup reserve, so that the battery keeps it even if Fennec stops:
+                let reserve = battery::reserve::reserve_at(
+                    &self.args.battery.reserve_windows,
+                    mini_qube::schedule::slot_start_time(index),
+                );
+                let allowed_soc = RangeInclusive {
+                    start: allowed_soc.start.max(reserve.min(allowed_soc.last)),
+                    last: allowed_soc.last,
+                };
+                let slot = mini_qube::schedule::make_slot(
+                    index,
+                    step.working_mode,
+                    step.power_level,
+                    step.mean_battery_power(),
+                    allowed_soc,
+                    self.args.battery.power_limits,
+                );
+                (index, slot)
+            })
+            .take(self.args.n_schedule_slots.into())
+            .collect_vec();
+        let n_written = (|| async { client.write_schedule(&slots).await })
             .retry(Self::BACKOFF)
             .notify(log_retried_error)
             .await
-            .with_context(|| {
-                format!("failed to write the schedule slot #{index} to the battery")
-            })?;
-
+            .context("failed to write the schedule to the battery")?;
+        debug!(n_written, n_total = slots.len(), "written the schedule");
         Ok(())
     }
+
+    /// State-of-charge range to write for the plan step, narrowed down to the plan if enabled.
+    fn allowed_soc_within(
+        &self,
+        step: &Step,
+        battery_metrics: &battery::Metrics,
+    ) -> RangeInclusive<Percentage> {
+        if self.args.write_planned_soc {
+            let planned_soc = 100.0 * WattHours::from(step.residual_energy_after)
+                / battery_metrics.actual_capacity();
+            step.working_mode.planned_soc_limits(
+                battery_metrics.allowed_soc,
+                planned_soc,
+                battery_metrics.state_of_charge,
+            )
+        } else {
+            battery_metrics.allowed_soc
+        }
+    }
 }
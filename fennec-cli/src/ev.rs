@@ -0,0 +1,194 @@
+//! Electric vehicle charging as a deferrable load.
+//!
+//! The charging windows are the cheapest intervals before the deadline. The optimizer then
+//! accounts for the charging load, so that the battery is not drained into the car
+//! when the grid is cheaper.
+
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Days, Local, NaiveTime};
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    Schedule,
+    energy,
+    ops::interval::Interval,
+    prelude::*,
+    quantity::{Zero, energy::WattHours, power::Watts, price::KilowattHourPrice, time::Hours},
+};
+
+#[derive(Copy, Clone, clap::Args)]
+#[group(id = "ev")]
+pub struct Args {
+    /// Energy to charge into the car before the deadline, in watt-hours. Disables the planning if unset.
+    #[clap(long = "ev-required-energy-watt-hours", env = "EV_REQUIRED_ENERGY_WATT_HOURS")]
+    pub required_energy: Option<WattHours>,
+
+    /// Time of day by which the car must be charged.
+    #[clap(long = "ev-deadline", env = "EV_DEADLINE", default_value = "07:00")]
+    pub deadline: NaiveTime,
+
+    /// Charger power in watts.
+    #[clap(
+        name = "ev_charging_power",
+        long = "ev-charging-power-watts",
+        env = "EV_CHARGING_POWER_WATTS",
+        default_value = "11000"
+    )]
+    pub charging_power: Watts,
+}
+
+/// Single charging window.
+#[derive(Copy, Clone, Serialize)]
+pub struct Window {
+    pub interval: Interval<DateTime<Local>>,
+
+    /// Average charging power within the window.
+    pub power: Watts,
+}
+
+/// Charging plan till the deadline.
+#[must_use]
+#[derive(Clone, Serialize)]
+pub struct Plan {
+    pub deadline: DateTime<Local>,
+
+    /// Charging windows in chronological order.
+    pub windows: Vec<Window>,
+}
+
+impl Args {
+    /// Pick the cheapest intervals to charge the required energy before the next deadline.
+    ///
+    /// The windows of the previous plan for the same deadline that have already started are kept,
+    /// and only the remaining energy gets re-planned: new prices may have arrived in the meantime.
+    ///
+    /// Returns [`None`] if the planning is disabled.
+    pub fn plan(
+        &self,
+        prices: &Schedule<energy::Flow<KilowattHourPrice>>,
+        now: DateTime<Local>,
+        previous: Option<&Plan>,
+    ) -> Option<Plan> {
+        let required_energy = self.required_energy?;
+        let deadline = {
+            let today =
+                now.date_naive().and_time(self.deadline).and_local_timezone(Local).earliest()?;
+            if today > now { today } else { today.checked_add_days(Days::new(1))? }
+        };
+        let mut windows = previous
+            .filter(|previous| previous.deadline == deadline)
+            .map(|previous| previous.windows_until(now))
+            .unwrap_or_default();
+
+        let candidates = prices
+            .iter()
+            .filter(|slot| slot.interval.end() <= deadline)
+            .map(|slot| (slot.interval, slot.value.import))
+            .sorted_by(|(_, lhs), (_, rhs)| lhs.partial_cmp(rhs).unwrap_or(Ordering::Equal));
+
+        let mut remaining_energy = required_energy
+            - windows
+                .iter()
+                .map(|window| window.power * Hours::from(window.interval.duration()))
+                .sum();
+        for (interval, _) in candidates {
+            if remaining_energy <= WattHours::ZERO {
+                break;
+            }
+            let max_energy = self.charging_power * Hours::from(interval.duration());
+            let energy = max_energy.min(remaining_energy);
+            windows.push(Window { interval, power: self.charging_power * (energy / max_energy) });
+            remaining_energy -= energy;
+        }
+        if remaining_energy > WattHours::ZERO {
+            warn!(?remaining_energy, %deadline, "not enough time to charge the car");
+        }
+
+        windows.sort_by_key(|window| window.interval.start());
+        Some(Plan { deadline, windows })
+    }
+}
+
+impl Plan {
+    /// Windows clipped to the specified timestamp.
+    fn windows_until(&self, until: DateTime<Local>) -> Vec<Window> {
+        self.windows
+            .iter()
+            .filter(|window| window.interval.start() < until)
+            .map(|window| Window {
+                interval: Interval::new(window.interval.start(), window.interval.end().min(until)),
+                power: window.power,
+            })
+            .collect()
+    }
+
+    /// Average charging power over the interval.
+    pub fn mean_power_over(&self, interval: Interval<DateTime<Local>>) -> Watts {
+        let duration = Hours::from(interval.duration());
+        if duration.0 <= 0.0 {
+            return Watts::ZERO;
+        }
+        self.windows
+            .iter()
+            .filter_map(|window| {
+                let start = window.interval.start().max(interval.start());
+                let end = window.interval.end().min(interval.end());
+                (start < end).then(|| window.power * (Hours::from(end - start) / duration))
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::quantity::Quantity;
+
+    #[test]
+    fn plan_cheapest_before_deadline() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 0, 0, 0).unwrap();
+        let mut prices = Schedule::new();
+        prices.extend_from_iter([0.30, 0.10, 0.20, 0.05].into_iter().enumerate().map(
+            |(i, price)| {
+                let start = start + TimeDelta::hours(i64::try_from(i).unwrap());
+                let interval = Interval::new(start, start + TimeDelta::hours(1));
+                (interval, energy::Flow { import: Quantity(price), export: Quantity(0.0) })
+            },
+        ))?;
+        let args = Args {
+            required_energy: Some(Quantity(15_000.0)),
+            deadline: NaiveTime::from_hms_opt(3, 0, 0).unwrap(),
+            charging_power: Quantity(10_000.0),
+        };
+
+        let plan = args.plan(&prices, start, None).unwrap();
+
+        // The cheapest interval is past the deadline, so the next two take over:
+        assert_eq!(plan.deadline, start + TimeDelta::hours(3));
+        assert_eq!(plan.windows.len(), 2);
+        assert_eq!(plan.windows[0].interval.start(), start + TimeDelta::hours(1));
+        assert_eq!(plan.windows[0].power, Quantity(10_000.0));
+        assert_eq!(plan.windows[1].interval.start(), start + TimeDelta::hours(2));
+        assert_eq!(plan.windows[1].power, Quantity(5_000.0));
+
+        // Half an hour of full power, and another half an hour of the half power:
+        let interval =
+            Interval::new(start + TimeDelta::minutes(90), start + TimeDelta::minutes(150));
+        assert_eq!(plan.mean_power_over(interval), Quantity(7_500.0));
+
+        // Re-planning in the middle of the first window keeps the charged part:
+        let now = start + TimeDelta::minutes(90);
+        prices.advance_to(now);
+        let plan = args.plan(&prices, now, Some(&plan)).unwrap();
+        assert_eq!(plan.windows.len(), 3);
+        assert_eq!(plan.windows[0].interval, Interval::new(start + TimeDelta::hours(1), now));
+        assert_eq!(plan.windows[1].interval, Interval::new(now, start + TimeDelta::hours(2)));
+        assert_eq!(plan.windows[1].power, Quantity(10_000.0));
+        assert_eq!(plan.windows[2].power, Quantity(5_000.0));
+        Ok(())
+    }
+}
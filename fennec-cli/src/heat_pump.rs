@@ -0,0 +1,116 @@
+//! Smart Grid Ready signal for a heat pump.
+//!
+//! The signal follows the plan: the heat pump is nudged into pre-heating within the same cheap intervals
+//! in which the battery charges, and is held back while the battery is selling into the grid.
+
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::{battery::WorkingMode, quantity::ratios::Percentage, solution::Plan};
+
+#[derive(Copy, Clone, clap::Args)]
+#[group(id = "heat-pump")]
+pub struct Args {
+    /// Share of the cheapest intervals within the price horizon
+    /// in which the heat pump is recommended to turn on, percentage.
+    #[clap(long = "heat-pump-cheap-share", env = "HEAT_PUMP_CHEAP_SHARE", default_value = "25")]
+    pub cheap_share: Percentage,
+}
+
+/// SG-Ready operating state, ordered from the least to the most consumption.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Signal {
+    /// State 1: utility lock, the heat pump should stay off.
+    Block,
+
+    /// State 2: normal operation.
+    Normal,
+
+    /// State 3: switch-on recommendation, the heat pump may raise its setpoints.
+    Recommended,
+
+    /// State 4: definitive switch-on command.
+    Forced,
+}
+
+impl Signal {
+    /// Plain on/off signal for the heat pumps without the SG-Ready input.
+    pub const fn is_on(self) -> bool {
+        matches!(self, Self::Recommended | Self::Forced)
+    }
+}
+
+impl Display for Signal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Self::Block => "Block",
+            Self::Normal => "Normal",
+            Self::Recommended => "Recommended",
+            Self::Forced => "Forced",
+        };
+        text.fmt(f)
+    }
+}
+
+impl Args {
+    /// Derive the signal for the first interval of the plan.
+    pub fn signal(self, plan: &Plan) -> Signal {
+        let Some(current) = plan.schedule.iter().next() else {
+            return Signal::Normal;
+        };
+        let (prices, step) = current.value;
+        match step.working_mode {
+            WorkingMode::Charge => Signal::Forced,
+            WorkingMode::Discharge => Signal::Block,
+            _ => {
+                let n_cheaper =
+                    plan.schedule.iter().filter(|slot| slot.value.0.import < prices.import).count();
+                if n_cheaper * 100 < plan.schedule.len() * usize::from(self.cheap_share.0) {
+                    Signal::Recommended
+                } else {
+                    Signal::Normal
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+    use crate::{energy, prelude::*, quantity::Quantity, solution::Step};
+
+    fn plan(working_mode: WorkingMode, prices: &[f64]) -> Result<Plan> {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 0, 0, 0).unwrap();
+        Plan::hourly(
+            start,
+            prices.iter().map(|price| {
+                let prices = energy::Flow { import: Quantity(*price), export: Quantity(0.0) };
+                (prices, Step::hourly(working_mode, 0))
+            }),
+        )
+    }
+
+    #[test]
+    fn signal_ok() -> Result {
+        let args = Args { cheap_share: Quantity(25) };
+        assert_eq!(
+            args.signal(&plan(WorkingMode::SelfUse, &[0.1, 0.3, 0.2, 0.4])?),
+            Signal::Recommended
+        );
+        assert_eq!(
+            args.signal(&plan(WorkingMode::SelfUse, &[0.2, 0.3, 0.1, 0.4])?),
+            Signal::Normal
+        );
+        assert_eq!(args.signal(&plan(WorkingMode::Charge, &[0.4, 0.3, 0.1, 0.2])?), Signal::Forced);
+        assert_eq!(
+            args.signal(&plan(WorkingMode::Discharge, &[0.1, 0.3, 0.2, 0.4])?),
+            Signal::Block
+        );
+        Ok(())
+    }
+}
@@ -7,6 +7,8 @@ mod battery;
 mod cli;
 mod energy;
 mod engine;
+mod ev;
+mod heat_pump;
 mod math;
 mod ops;
 mod prelude;
@@ -17,23 +19,38 @@ mod web;
 
 use std::borrow::Cow;
 
-use clap::{Parser as _, crate_name, crate_version};
+use chrono::{Local, TimeDelta};
+use clap::{crate_name, crate_version};
 use sentry::{
     SessionMode,
     integrations::{anyhow::capture_anyhow, tracing::EventFilter},
 };
-use tokio::{spawn, try_join};
+use tokio::{
+    signal::{
+        ctrl_c,
+        unix::{SignalKind, signal},
+    },
+    spawn,
+    sync::mpsc,
+    try_join,
+};
+use tokio_util::sync::CancellationToken;
 use tracing::metadata::LevelFilter;
 use tracing_subscriber::{EnvFilter, Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
 pub use self::series::{Schedule, Series};
-use crate::{cli::Args, engine::Engine, prelude::*};
+use crate::{
+    cli::{Args, ConfigFileArgs},
+    engine::{Engine, Reload},
+    prelude::*,
+    solution::{Execution, Scenario},
+};
 
 fn main() -> Result {
     init_tracing()?;
 
     let _ = dotenvy::dotenv();
-    let args = Args::parse();
+    let args = Args::parse_with_config_file()?;
     info!(version = crate_version!(), "starting…");
     let _sentry_guard = init_sentry(args.sentry_dsn.as_deref());
 
@@ -82,11 +99,74 @@ fn init_sentry(dsn: Option<&str>) -> sentry::ClientInitGuard {
     guard
 }
 
-async fn run(args: Args) -> Result {
-    let engine = Engine::start(args.connections.connect()?, args.engine).await?;
+async fn run(mut args: Args) -> Result {
+    args.config_file.enter_site_directory()?;
+    if let Some(path) = &args.export_executions {
+        let since = Local::now() - TimeDelta::from_std(args.export_period)?;
+        return Execution::export_journal(since, path).await;
+    }
+    if args.zonneplan_login {
+        return args.connections.connect()?.zonneplan.log_in().await;
+    }
+    if args.check {
+        return args.connections.connect()?.check(args.engine.energy_provider).await;
+    }
+    if let Some(on) = args.compare_providers {
+        return energy::compare_providers(&args.connections.connect()?, on).await;
+    }
+    args.engine.battery.retain_supported_working_modes(args.connections.inverter)?;
+    if let Some(path) = &args.what_if {
+        let scenario = Scenario::read_from_file(path).await?;
+        let engine =
+            Engine::start(args.connections.connect()?, args.engine, CancellationToken::new())
+                .await?;
+        return engine.what_if(&scenario).await;
+    }
+    let shutdown = CancellationToken::new();
+    spawn(cancel_on_ctrl_c(shutdown.clone()));
+    let (reload_sender, reloads) = mpsc::channel(1);
+    spawn(reload_on_sighup(args.config_file.clone(), reload_sender));
+    let engine = Engine::start(args.connections.connect()?, args.engine, shutdown.clone()).await?;
     let state = engine.state();
-    let engine_future = async { spawn(engine.run_forever()).await? };
-    let web_future = async { spawn(web::serve(args.bind.address, args.bind.port, state)).await? };
+    let engine_future = async { spawn(engine.run_forever(reloads)).await? };
+    let web_future = async { spawn(web::serve(args.bind, state, shutdown)).await? };
     try_join!(engine_future, web_future)?;
     Ok(())
 }
+
+/// Re-read the config file on SIGHUP and pass the reloaded settings on to the engine.
+///
+/// Invalid settings get logged and ignored, so that a typo does not stop the running engine.
+async fn reload_on_sighup(config_file: ConfigFileArgs, reloads: mpsc::Sender<Reload>) {
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(hangups) => hangups,
+        Err(error) => {
+            warn!("failed to listen for SIGHUP: {error:#}");
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        info!("reloading the config file…");
+        let reload = Args::reparse_with_config_file(&config_file).and_then(|args| {
+            Ok(Reload { connections: args.connections.connect()?, args: args.engine })
+        });
+        match reload {
+            Ok(reload) => {
+                if reloads.send(reload).await.is_err() {
+                    break;
+                }
+            }
+            Err(error) => error!("failed to reload the config file: {error:#}"),
+        }
+    }
+}
+
+/// Cancel the token on Ctrl-C, so that the engine and web UI stop gracefully.
+async fn cancel_on_ctrl_c(shutdown: CancellationToken) {
+    if let Err(error) = ctrl_c().await {
+        warn!("failed to listen for Ctrl-C: {error:#}");
+        return;
+    }
+    info!("shutting down…");
+    shutdown.cancel();
+}
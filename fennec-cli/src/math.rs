@@ -1,6 +1,7 @@
 use crate::quantity::angle::Radians;
 
 pub mod fourier;
+pub mod scenarios;
 pub mod smoothing;
 
 /// Non-normalized [sinc](https://en.wikipedia.org/wiki/Sinc_function) function: sin(x)÷x.
@@ -0,0 +1,49 @@
+use std::num::NonZeroUsize;
+
+/// Multiplicative factors around one, distributed normally with the relative deviation.
+///
+/// These are evenly spaced quantiles rather than random samples,
+/// so that the same inputs always produce the same plan.
+/// The probit is approximated with the [logistic function][1], accurate to about 1%.
+///
+/// [1]: https://en.wikipedia.org/wiki/Logit#Comparison_with_probit
+#[must_use]
+pub fn normal_factors(n_scenarios: NonZeroUsize, relative_deviation: f64) -> Vec<f64> {
+    const LOGIT_SCALE: f64 = 1.702;
+
+    #[expect(clippy::cast_precision_loss)]
+    let n_scenarios_f64 = n_scenarios.get() as f64;
+
+    (0..n_scenarios.get())
+        .map(|index| {
+            #[expect(clippy::cast_precision_loss)]
+            let probability = (index as f64 + 0.5) / n_scenarios_f64;
+            let z = (probability / (1.0 - probability)).ln() / LOGIT_SCALE;
+            z.mul_add(relative_deviation, 1.0).max(0.0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_scenario_is_nominal() {
+        assert_eq!(normal_factors(NonZeroUsize::MIN, 0.2), [1.0]);
+    }
+
+    #[test]
+    fn normal_factors_ok() {
+        let factors = normal_factors(NonZeroUsize::new(5).unwrap(), 0.2);
+        assert_eq!(factors.len(), 5);
+        assert!(factors.is_sorted());
+        assert!((factors[2] - 1.0).abs() < 1e-9);
+
+        // Symmetric around one:
+        assert!((factors[0] + factors[4] - 2.0).abs() < 1e-9);
+
+        // 10th percentile of the normal distribution is −1.28σ:
+        assert!((factors[0] - 1.28f64.mul_add(-0.2, 1.0)).abs() < 0.01);
+    }
+}
@@ -30,6 +30,36 @@ impl<V> Exponential<V> {
     }
 }
 
+impl Exponential<f64> {
+    /// Clipping bound in the mean absolute deviations.
+    const N_DEVIATIONS: f64 = 3.0;
+
+    /// Minimal deviation for the clipping, so that the value never freezes.
+    const MIN_DEVIATION: f64 = 0.005;
+
+    /// Update the value robustly, similarly to the [Huber loss][1].
+    ///
+    /// The target gets clipped to a few mean absolute deviations around the current value,
+    /// so that a single outlier cannot drag the value away. The deviation is tracked
+    /// alongside with the same smoothing factor.
+    ///
+    /// Returns the clipped target.
+    ///
+    /// [1]: https://en.wikipedia.org/wiki/Huber_loss
+    pub fn update_robust(
+        &mut self,
+        deviation: &mut Self,
+        target: f64,
+        smoothing_factor: f64,
+    ) -> f64 {
+        let bound = Self::N_DEVIATIONS * deviation.0.max(Self::MIN_DEVIATION);
+        let clipped = target.clamp(self.0 - bound, self.0 + bound);
+        deviation.update((clipped - self.0).abs(), smoothing_factor);
+        self.update(clipped, smoothing_factor);
+        clipped
+    }
+}
+
 /// Half-life of the exponential decay.
 #[must_use]
 #[derive(Copy, Clone)]
@@ -50,3 +80,18 @@ impl<V> HalfLife<V> {
         -(-LN_2 * (delta.into() / self.0)).exp_m1()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_robust_clips_outlier() {
+        let mut value = Exponential(0.95);
+        let mut deviation = Exponential(0.01);
+        let clipped = value.update_robust(&mut deviation, 3.0, 0.5);
+        assert!((clipped - 0.98).abs() < 1e-9);
+        assert!((value.0 - 0.965).abs() < 1e-9);
+        assert!((deviation.0 - 0.02).abs() < 1e-9);
+    }
+}
@@ -1,2 +1,6 @@
+pub mod daily_window;
+pub mod health;
 pub mod interval;
+pub mod jsonl;
 pub mod musli;
+pub mod staleness;
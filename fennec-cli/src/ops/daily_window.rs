@@ -0,0 +1,36 @@
+use std::str::FromStr;
+
+use chrono::NaiveTime;
+
+use crate::prelude::*;
+
+/// Daily time window formatted as `HH:MM-HH:MM`, the end is exclusive.
+///
+/// The window may wrap around midnight, for example: `22:00-06:00`.
+#[derive(Copy, Clone, Debug, serde::Serialize)]
+pub struct DailyWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl FromStr for DailyWindow {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let (start, end) = text.split_once('-').context("expected `HH:MM-HH:MM`")?;
+        Ok(Self {
+            start: NaiveTime::parse_from_str(start.trim(), "%H:%M")?,
+            end: NaiveTime::parse_from_str(end.trim(), "%H:%M")?,
+        })
+    }
+}
+
+impl DailyWindow {
+    pub fn contains(self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            (self.start <= time) && (time < self.end)
+        } else {
+            (self.start <= time) || (time < self.end)
+        }
+    }
+}
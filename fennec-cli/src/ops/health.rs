@@ -0,0 +1,85 @@
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::prelude::*;
+
+/// Health of the individual measurement sources, so that a failing one can be told apart
+/// from the others.
+#[must_use]
+#[derive(Clone, Default, Serialize)]
+pub struct Sources {
+    pub battery: SourceHealth,
+    pub grid: SourceHealth,
+}
+
+#[must_use]
+#[derive(Clone, Default, Serialize)]
+pub struct SourceHealth {
+    /// Last time the source has been read successfully.
+    pub last_success_at: Option<DateTime<Local>>,
+
+    /// Number of reads failed in a row, each after the retries.
+    pub n_consecutive_failures: usize,
+
+    /// Total number of reads since the start, each including its retries.
+    pub n_reads: u64,
+
+    /// Total number of failed reads since the start.
+    pub n_failures: u64,
+
+    /// Error of the latest failed read, cleared on success.
+    pub last_error: Option<String>,
+}
+
+impl SourceHealth {
+    /// Record the read outcome.
+    pub fn record<T>(&mut self, now: DateTime<Local>, result: &Result<T>) {
+        self.n_reads += 1;
+        match result {
+            Ok(_) => {
+                self.last_success_at = Some(now);
+                self.n_consecutive_failures = 0;
+                self.last_error = None;
+            }
+            Err(error) => {
+                self.n_consecutive_failures += 1;
+                self.n_failures += 1;
+                self.last_error = Some(format!("{error:#}"));
+            }
+        }
+    }
+
+    /// Whether the latest read has succeeded.
+    pub const fn is_healthy(&self) -> bool {
+        self.last_success_at.is_some() && self.n_consecutive_failures == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn record_ok() {
+        let now = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let mut health = SourceHealth::default();
+        assert!(!health.is_healthy());
+
+        health.record(now, &Ok(()));
+        assert!(health.is_healthy());
+
+        health.record::<()>(now, &Err(anyhow!("timed out")));
+        health.record::<()>(now, &Err(anyhow!("connection refused")));
+        assert!(!health.is_healthy());
+        assert_eq!(health.n_consecutive_failures, 2);
+        assert_eq!(health.last_error.as_deref(), Some("connection refused"));
+        assert_eq!(health.last_success_at, Some(now));
+        assert_eq!((health.n_reads, health.n_failures), (3, 2));
+
+        health.record(now, &Ok(()));
+        assert!(health.is_healthy());
+        assert!(health.last_error.is_none());
+    }
+}
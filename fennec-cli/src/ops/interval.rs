@@ -1,11 +1,15 @@
 use std::ops::Sub;
 
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+
+use crate::prelude::*;
+
 /// Half-open interval.
 ///
 /// TODO: could become a wrapper around [`std::range::Range`].
 /// TODO: some usages may likely be replaced with [`std::range::Range`] directly.
 #[must_use]
-#[derive(Copy, Clone, PartialEq, Eq, derive_more::Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, derive_more::Debug, serde::Serialize, serde::Deserialize)]
 #[debug("{start:?}..{end:?}")]
 pub struct Interval<Index> {
     start: Index,
@@ -84,6 +88,22 @@ impl<Index> Interval<Index> {
     }
 }
 
+impl Interval<DateTime<Local>> {
+    /// Local calendar day, which may be shorter or longer than 24 hours around the DST switches.
+    pub fn local_day(on: NaiveDate) -> Result<Self> {
+        let midnight = |on: NaiveDate| {
+            on.and_time(NaiveTime::MIN)
+                .and_local_timezone(Local)
+                .earliest()
+                .with_context(|| format!("there is no local midnight on {on}"))
+        };
+        Ok(Self {
+            start: midnight(on)?,
+            end: midnight(on.succ_opt().context("the date is out of range")?)?,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
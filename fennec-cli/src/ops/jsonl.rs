@@ -0,0 +1,48 @@
+//! Append-only [JSON Lines](https://jsonlines.org) journals.
+
+use std::path::Path;
+
+use serde::{Serialize, de::DeserializeOwned};
+use tokio::io::AsyncWriteExt;
+
+use crate::prelude::*;
+
+/// Append the value to the journal as a single line, creating the file if needed.
+pub async fn append(path: &Path, value: &impl Serialize) -> Result {
+    let mut line = serde_json::to_vec(value).context("failed to serialize the entry")?;
+    line.push(b'\n');
+    tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+        .with_context(|| format!("failed to open `{}`", path.display()))?
+        .write_all(&line)
+        .await
+        .with_context(|| format!("failed to append to `{}`", path.display()))
+}
+
+/// Read all the journal entries, or none if the journal does not exist yet.
+///
+/// Malformed lines, for example, a partially written last one, are skipped with a warning.
+pub async fn read<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let journal = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("failed to read `{}`", path.display()))?;
+    let entries = journal
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty())
+        .filter_map(|(index, line)| {
+            serde_json::from_str(line)
+                .inspect_err(|error| {
+                    warn!(line = index + 1, "skipping the malformed entry: {error:#}");
+                })
+                .ok()
+        })
+        .collect();
+    Ok(entries)
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Local, TimeDelta};
+
+/// Detects a measurement which has not advanced for too long, as when a meter goes offline
+/// but its gateway keeps serving the last known values.
+#[must_use]
+pub struct Staleness<T> {
+    /// Stale after this long without a change.
+    after: TimeDelta,
+
+    /// Last value and since when it has been the same.
+    last: Option<(T, DateTime<Local>)>,
+
+    is_alerted: bool,
+}
+
+#[must_use]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Alert {
+    /// The value has not changed since the specified moment.
+    Stale { since: DateTime<Local> },
+
+    /// The value has changed again after the [`Alert::Stale`].
+    Recovered,
+}
+
+impl<T: PartialEq> Staleness<T> {
+    pub const fn new(after: TimeDelta) -> Self {
+        Self { after, last: None, is_alerted: false }
+    }
+
+    /// Record the value and return the alert, if any, once per transition.
+    pub fn update(&mut self, now: DateTime<Local>, value: T) -> Option<Alert> {
+        match &self.last {
+            Some((last, since)) if *last == value => {
+                let since = *since;
+                if !self.is_alerted && now - since >= self.after {
+                    self.is_alerted = true;
+                    return Some(Alert::Stale { since });
+                }
+                None
+            }
+            _ => {
+                self.last = Some((value, now));
+                self.recover()
+            }
+        }
+    }
+
+    /// Forget the value, for example, when it is legitimately not expected to change.
+    pub fn reset(&mut self) -> Option<Alert> {
+        self.last = None;
+        self.recover()
+    }
+
+    fn recover(&mut self) -> Option<Alert> {
+        self.is_alerted.then(|| {
+            self.is_alerted = false;
+            Alert::Recovered
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn update_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let at = |minutes| start + TimeDelta::minutes(minutes);
+        let mut staleness = Staleness::new(TimeDelta::minutes(15));
+        assert_eq!(staleness.update(at(0), 1), None);
+        assert_eq!(staleness.update(at(10), 1), None);
+        assert_eq!(staleness.update(at(15), 1), Some(Alert::Stale { since: at(0) }));
+        assert_eq!(staleness.update(at(20), 1), None);
+        assert_eq!(staleness.update(at(25), 2), Some(Alert::Recovered));
+        assert_eq!(staleness.update(at(30), 3), None);
+    }
+
+    #[test]
+    fn reset_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let mut staleness = Staleness::new(TimeDelta::minutes(15));
+        assert_eq!(staleness.update(start, 1), None);
+        assert_eq!(staleness.reset(), None);
+        assert_eq!(staleness.update(start + TimeDelta::minutes(15), 1), None);
+        assert_eq!(
+            staleness.update(start + TimeDelta::minutes(30), 1),
+            Some(Alert::Stale { since: start + TimeDelta::minutes(15) }),
+        );
+        assert_eq!(staleness.reset(), Some(Alert::Recovered));
+    }
+}
@@ -12,4 +12,98 @@ impl<V> Format for Mills<V> {
 impl Mills<f64> {
     /// One cent.
     pub const TEN: Self = Self(10.0);
+
+    /// Round to the specified number of decimal places of the base unit, half away from zero.
+    pub fn round_to(self, precision: u8) -> Self {
+        let step = 10.0_f64.powi(3 - i32::from(precision));
+        Self((self.0 / step).round() * step)
+    }
+}
+
+/// Currency to display the money amounts in.
+///
+/// The amounts themselves are currency-agnostic: they are in whatever currency the prices are in.
+#[derive(Clone, clap::Args)]
+pub struct Currency {
+    /// Currency symbol to display the money amounts with.
+    #[clap(long = "currency-symbol", env = "CURRENCY_SYMBOL", default_value = "€")]
+    pub symbol: String,
+
+    /// Number of the currency minor unit decimal places to display the money amounts with.
+    #[clap(
+        long = "currency-precision",
+        env = "CURRENCY_PRECISION",
+        default_value = "2",
+        value_parser = clap::value_parser!(u8).range(0..=3),
+    )]
+    pub precision: u8,
+}
+
+impl Currency {
+    /// Format the amount in the base units, for example, `-€1.23`.
+    pub fn format(&self, amount: Mills) -> String {
+        let amount = amount.round_to(self.precision);
+        let sign = if amount.0 < 0.0 { "-" } else { "" };
+        format!("{sign}{}{:.2$}", self.symbol, amount.0.abs() / 1000.0, usize::from(self.precision))
+    }
+}
+
+/// Milli-cent, one-hundred-thousandth of the base unit.
+///
+/// Fixed-point representation for accumulating costs: unlike [`f64`], integer addition
+/// is associative, so the same losses sum up to the same total regardless of the order.
+pub type Millicents<V = i64> = Quantity<V, -5, 0, 0, 1>;
+
+impl<V> Format for Millicents<V> {
+    const SUFFIX: &str = "m¢";
+}
+
+impl Millicents {
+    /// One mill.
+    pub const MILL: Self = Self(100);
+}
+
+impl From<Mills> for Millicents {
+    #[expect(clippy::cast_possible_truncation)]
+    fn from(mills: Mills) -> Self {
+        Self((mills.0 * 100.0).round() as i64)
+    }
+}
+
+impl From<Millicents> for Mills {
+    #[expect(clippy::cast_precision_loss)]
+    fn from(millicents: Millicents) -> Self {
+        Self(millicents.0 as f64 / 100.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(Millicents::from(Mills::new(1.234_56)), Millicents::new(123));
+        assert_eq!(Millicents::from(Mills::new(-1.235_01)), Millicents::new(-124));
+        assert_eq!(Mills::from(Millicents::new(-123)), Mills::new(-1.23));
+    }
+
+    #[test]
+    fn round_to_ok() {
+        assert_eq!(Mills::new(1234.5).round_to(2), Mills::new(1230.0));
+        assert_eq!(Mills::new(-1235.0).round_to(2), Mills::new(-1240.0));
+        assert_eq!(Mills::new(1500.0).round_to(0), Mills::new(2000.0));
+        assert_eq!(Mills::new(1.4).round_to(3), Mills::new(1.0));
+    }
+
+    #[test]
+    fn format_ok() {
+        let currency = Currency { symbol: "€".to_string(), precision: 2 };
+        assert_eq!(currency.format(Mills::new(1234.5)), "€1.23");
+        assert_eq!(currency.format(Mills::new(-1235.0)), "-€1.24");
+        assert_eq!(currency.format(Mills::new(-1.0)), "€0.00");
+
+        let currency = Currency { symbol: "kr ".to_string(), precision: 0 };
+        assert_eq!(currency.format(Mills::new(12_499.0)), "kr 12");
+    }
 }
@@ -8,8 +8,20 @@ impl<V> Format for Watts<V> {
     const SUFFIX: &str = "W";
 }
 
+pub type Kilowatts<V = f64> = Quantity<V, 3, 1, 0, 0>;
+
+impl<V> Format for Kilowatts<V> {
+    const SUFFIX: &str = "kW";
+}
+
 impl From<contrib::types::Watts<i32>> for Watts {
     fn from(watts: contrib::types::Watts<i32>) -> Self {
         Self(f64::from(watts.0))
     }
 }
+
+impl From<contrib::types::Watts<i16>> for Watts {
+    fn from(watts: contrib::types::Watts<i16>) -> Self {
+        Self(f64::from(watts.0))
+    }
+}
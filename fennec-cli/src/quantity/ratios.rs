@@ -37,7 +37,17 @@ impl TryFrom<contrib::types::Percentage<u16>> for Percentage {
     }
 }
 
+impl TryFrom<contrib::types::Permille<u16>> for Percentage {
+    type Error = Error;
+
+    fn try_from(value: contrib::types::Permille<u16>) -> Result<Self> {
+        Ok(Self((value.0 / 10).try_into()?))
+    }
+}
+
 impl Percentage {
+    pub const FULL: Self = Self(100);
+
     /// Convert the percentage into `0.0..=1.0`.
     pub const fn to_ratio(self) -> f64 {
         0.01 * self.0 as f64
@@ -9,7 +9,7 @@ use crate::{ops::interval::Interval, prelude::*, quantity::Zero};
 pub type Schedule<V> = Series<V, DateTime<Local>>;
 
 #[must_use]
-#[derive(IntoIterator)]
+#[derive(Clone, IntoIterator)]
 pub struct Series<V, Index>(VecDeque<Slot<V, Index>>);
 
 impl<V, Index> Series<V, Index> {
@@ -142,6 +142,11 @@ impl<V, Index> Series<V, Index> {
         Ok(())
     }
 
+    /// Keep only the first `len` slots.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
     /// Remove slots that ended at or before the given index
     /// and clamp the first remaining interval's start to that index.
     ///
@@ -169,7 +174,7 @@ impl<V, Index> Series<V, Index> {
 }
 
 #[must_use]
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Slot<V, Index = DateTime<Local>> {
     pub interval: Interval<Index>,
 
@@ -203,4 +208,21 @@ mod tests {
         assert_eq!(schedule.len(), 1);
         assert_eq!(schedule.get(0), Slot { interval: second_interval, value: &2 });
     }
+
+    /// Hourly schedule may get extended with the quarterly one, and vice versa.
+    #[test]
+    fn extend_mixed_resolution() -> Result {
+        let mut schedule = Series::new();
+        schedule.extend_from_iter([(Interval::new(0, 60), 1)])?;
+        schedule.extend_from_iter([(Interval::new(60, 75), 2), (Interval::new(75, 90), 3)])?;
+        assert_eq!(schedule.end_index(), Some(90));
+
+        assert_eq!(schedule.advance_to(30), 0);
+        assert_eq!(schedule.get(0), Slot { interval: Interval::new(30, 60), value: &1 });
+
+        assert_eq!(schedule.advance_to(70), 1);
+        assert_eq!(schedule.get(0), Slot { interval: Interval::new(70, 75), value: &2 });
+        assert_eq!(schedule.len(), 2);
+        Ok(())
+    }
 }
@@ -1,23 +1,44 @@
+mod accuracy;
+mod execution;
+mod flexibility;
+mod forecast;
 mod losses;
+mod manifest;
 mod metrics;
 mod optimizer;
 mod plan;
+mod scenario;
 mod space;
 mod stage;
 mod step;
+mod validate;
+mod warm_start;
 
 use std::cmp::Ordering;
 
 pub use self::{
+    accuracy::HourlyAccuracy,
+    execution::{Attribution, DailyEnergy, Execution, JournalEntry, Tracker as ExecutionTracker},
+    flexibility::Flexibility,
+    forecast::Forecast,
     losses::Losses,
+    manifest::Manifest,
     metrics::Metrics,
     optimizer::Optimizer,
     plan::Plan,
+    scenario::Scenario,
     space::Space,
     stage::Stage,
     step::Step,
+    warm_start::WarmStart,
+};
+use crate::{
+    battery::WorkingMode,
+    quantity::{
+        currency::{Millicents, Mills},
+        ratios::Percentage,
+    },
 };
-use crate::quantity::{Zero, currency::Mills};
 
 /// Solution for a particular energy level at a particular [`Stage`].
 #[must_use]
@@ -32,17 +53,35 @@ pub struct Solution {
 
 impl Solution {
     pub fn total_loss(&self) -> Mills {
-        self.metrics.losses.total()
+        self.metrics.losses.total().into()
     }
 
     /// Compare this solution total loss to the other solution total loss.
-    fn compare_loss_to(&self, other: &Self) -> Ordering {
+    ///
+    /// Missing the backup reserve is worse than any loss, so the reserve shortfall comes first.
+    /// Within the noise floor, the preferred decision wins, if any.
+    fn compare_loss_to(
+        &self,
+        other: &Self,
+        preferred: Option<(WorkingMode, Percentage)>,
+    ) -> Ordering {
+        let shortfall_ordering =
+            self.metrics.reserve_shortfall.0.total_cmp(&other.metrics.reserve_shortfall.0);
+        if shortfall_ordering.is_ne() {
+            return shortfall_ordering;
+        }
         let difference = self.metrics.losses.total() - other.metrics.losses.total();
-        if difference.abs() >= Mills::ONE {
-            difference.partial_cmp(&Mills::ZERO).unwrap_or(Ordering::Equal)
+        if difference.0.abs() >= Millicents::MILL.0 {
+            difference.0.cmp(&0)
         } else {
-            // Within noise floor – compare actions and prefer lower-action mode:
-            self.step.working_mode.cmp(&other.step.working_mode)
+            // Within noise floor – stick to the preferred decision, or prefer lower-action mode:
+            let is_preferred = |solution: &Self| {
+                preferred == Some((solution.step.working_mode, solution.step.power_level))
+            };
+            is_preferred(other)
+                .cmp(&is_preferred(self))
+                .then(self.step.working_mode.cmp(&other.step.working_mode))
+                .then(self.step.power_level.cmp(&other.step.power_level))
         }
     }
 }
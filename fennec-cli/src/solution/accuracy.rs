@@ -0,0 +1,150 @@
+use chrono::Timelike;
+
+use crate::{
+    quantity::{Zero, energy::WattHours},
+    solution::JournalEntry,
+};
+
+/// Plan-versus-actual errors of the journaled executions, which started within the hour of day.
+///
+/// Meant to guide tuning the stand-by power and efficiency: consistent bias at night points
+/// at the stand-by power, and the residual energy bias while (dis)charging – at the efficiency.
+#[must_use]
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct HourlyAccuracy {
+    pub hour: u32,
+    pub n_executions: usize,
+    pub grid_import: Deviation,
+    pub grid_export: Deviation,
+
+    /// Missing if none of the executions has the residual energy measured.
+    pub residual_energy: Option<Deviation>,
+}
+
+/// Actual minus planned energy per execution.
+#[must_use]
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct Deviation {
+    pub mean_absolute_error: WattHours,
+
+    /// Mean error, positive means more than planned.
+    pub bias: WattHours,
+}
+
+#[derive(Copy, Clone)]
+struct Accumulator {
+    n_samples: usize,
+    absolute_sum: WattHours,
+    sum: WattHours,
+}
+
+impl Accumulator {
+    const EMPTY: Self = Self { n_samples: 0, absolute_sum: WattHours::ZERO, sum: WattHours::ZERO };
+
+    fn push(&mut self, error: WattHours) {
+        self.n_samples += 1;
+        self.absolute_sum += error.abs();
+        self.sum += error;
+    }
+
+    #[expect(clippy::cast_precision_loss)]
+    fn deviation(self) -> Option<Deviation> {
+        (self.n_samples != 0).then(|| {
+            let n_samples = self.n_samples as f64;
+            Deviation {
+                mean_absolute_error: self.absolute_sum / n_samples,
+                bias: self.sum / n_samples,
+            }
+        })
+    }
+}
+
+impl HourlyAccuracy {
+    /// Aggregate the journal entries by the local hour of the interval start.
+    ///
+    /// The planned energy gets scaled down to the tracked part of the interval,
+    /// so that the engine downtime does not count as the forecast error.
+    pub fn aggregate(entries: impl IntoIterator<Item = JournalEntry>) -> Vec<Self> {
+        let mut hours = [(Accumulator::EMPTY, Accumulator::EMPTY, Accumulator::EMPTY); 24];
+        for entry in entries {
+            let (grid_import, grid_export, residual_energy) =
+                &mut hours[entry.interval.start().hour() as usize];
+            let expected = entry.expected_balance();
+            grid_import.push(entry.actual.grid.import - expected.grid.import);
+            grid_export.push(entry.actual.grid.export - expected.grid.export);
+            if let Some(actual_residual_energy) = entry.actual_residual_energy_after {
+                residual_energy.push(
+                    actual_residual_energy - WattHours::from(entry.planned.residual_energy_after),
+                );
+            }
+        }
+        (0..24)
+            .zip(hours)
+            .filter_map(|(hour, (grid_import, grid_export, residual_energy))| {
+                Some(Self {
+                    hour,
+                    n_executions: grid_import.n_samples,
+                    grid_import: grid_import.deviation()?,
+                    grid_export: grid_export.deviation()?,
+                    residual_energy: residual_energy.deviation(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Local, TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::{
+        energy,
+        ops::interval::Interval,
+        quantity::Quantity,
+        solution::execution::PlannedEntry,
+    };
+
+    fn entry(start: DateTime<Local>, actual_import: f64, tracked_minutes: i64) -> JournalEntry {
+        let end = start + TimeDelta::hours(1);
+        JournalEntry {
+            interval: Interval::new(start, end),
+            planned: PlannedEntry {
+                duration: Quantity(1.0),
+                energy_balance: energy::Balance {
+                    grid: energy::Flow { import: Quantity(100.0), export: Quantity(0.0) },
+                    battery: energy::Flow::ZERO,
+                },
+                residual_energy_after: Quantity(1000),
+            },
+            tracked_since: end - TimeDelta::minutes(tracked_minutes),
+            actual: energy::Balance {
+                grid: energy::Flow { import: Quantity(actual_import), export: Quantity(0.0) },
+                battery: energy::Flow::ZERO,
+            },
+            actual_residual_energy_after: Some(Quantity(990.0)),
+        }
+    }
+
+    #[test]
+    fn aggregate_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 3, 0, 0).unwrap();
+        let accuracy = HourlyAccuracy::aggregate([
+            entry(start, 120.0, 60),
+            entry(start + TimeDelta::days(1), 90.0, 60),
+            entry(start + TimeDelta::hours(1), 60.0, 30),
+        ]);
+        assert_eq!(accuracy.len(), 2);
+
+        assert_eq!(accuracy[0].hour, 3);
+        assert_eq!(accuracy[0].n_executions, 2);
+        assert_eq!(accuracy[0].grid_import.mean_absolute_error, Quantity(15.0));
+        assert_eq!(accuracy[0].grid_import.bias, Quantity(5.0));
+        assert_eq!(accuracy[0].grid_export.bias, Quantity(0.0));
+        assert_eq!(accuracy[0].residual_energy.unwrap().bias, Quantity(-10.0));
+
+        // Only the tracked half of the interval is expected:
+        assert_eq!(accuracy[1].hour, 4);
+        assert_eq!(accuracy[1].grid_import.bias, Quantity(10.0));
+    }
+}
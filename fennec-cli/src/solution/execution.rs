@@ -0,0 +1,423 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::Path,
+    sync::Arc,
+};
+
+use chrono::{DateTime, Local, NaiveDate, TimeDelta};
+
+use crate::{
+    energy,
+    ops::{interval::Interval, jsonl},
+    prelude::*,
+    quantity::{
+        Zero,
+        currency::Mills,
+        energy::WattHours,
+        power::Watts,
+        price::KilowattHourPrice,
+        time::Hours,
+    },
+    solution::{Manifest, Plan, Step},
+};
+
+/// Planned step along with what has actually happened.
+#[must_use]
+#[derive(Clone, serde::Serialize)]
+pub struct Execution {
+    pub interval: Interval<DateTime<Local>>,
+    pub energy_price: energy::Flow<KilowattHourPrice>,
+    pub planned: Step,
+
+    /// Since when the energy flows are actually measured.
+    ///
+    /// It lags behind the interval start by the engine tick, or more if the engine was down.
+    pub tracked_since: DateTime<Local>,
+
+    /// Measured energy balance within the interval.
+    pub actual: energy::Balance<WattHours>,
+
+    /// Measured residual energy upon the interval completion.
+    pub actual_residual_energy_after: Option<WattHours>,
+
+    /// What produced the plan.
+    pub manifest: Option<Arc<Manifest>>,
+}
+
+/// Journaled [`Execution`], without the prices and manifest.
+#[must_use]
+#[derive(serde::Deserialize)]
+pub struct JournalEntry {
+    pub interval: Interval<DateTime<Local>>,
+    pub planned: PlannedEntry,
+    pub tracked_since: DateTime<Local>,
+    pub actual: energy::Balance<WattHours>,
+    pub actual_residual_energy_after: Option<WattHours>,
+}
+
+/// Planned part of the [`JournalEntry`].
+#[derive(Copy, Clone, serde::Deserialize)]
+pub struct PlannedEntry {
+    pub duration: Hours,
+    pub energy_balance: energy::Balance<WattHours>,
+    pub residual_energy_after: WattHours<usize>,
+}
+
+impl JournalEntry {
+    /// Planned energy balance, scaled down to the tracked part of the interval.
+    pub fn expected_balance(&self) -> energy::Balance<WattHours> {
+        let tracked_fraction =
+            Hours::from(self.interval.end() - self.tracked_since).0 / self.planned.duration.0;
+        self.planned.energy_balance * tracked_fraction.clamp(0.0, 1.0)
+    }
+
+    /// Column names carry the units, so that pandas or DuckDB do not have to guess them.
+    const CSV_HEADER: &str = "start,end,tracked_since,planned_duration_h,\
+        planned_grid_import_wh,planned_grid_export_wh,\
+        planned_battery_import_wh,planned_battery_export_wh,planned_residual_energy_after_wh,\
+        actual_grid_import_wh,actual_grid_export_wh,\
+        actual_battery_import_wh,actual_battery_export_wh,actual_residual_energy_after_wh";
+
+    /// Write the entries as CSV for the offline analysis, the timestamps are in RFC 3339.
+    pub fn write_csv(entries: &[Self], writer: &mut impl std::io::Write) -> Result {
+        writeln!(writer, "{}", Self::CSV_HEADER)?;
+        for entry in entries {
+            let planned = entry.planned.energy_balance;
+            let actual = entry.actual;
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                entry.interval.start().to_rfc3339(),
+                entry.interval.end().to_rfc3339(),
+                entry.tracked_since.to_rfc3339(),
+                entry.planned.duration.0,
+                planned.grid.import.0,
+                planned.grid.export.0,
+                planned.battery.import.0,
+                planned.battery.export.0,
+                entry.planned.residual_energy_after.0,
+                actual.grid.import.0,
+                actual.grid.export.0,
+                actual.battery.import.0,
+                actual.battery.export.0,
+                entry
+                    .actual_residual_energy_after
+                    .map_or_else(String::new, |residual_energy| residual_energy.0.to_string()),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Measured energy flows summed up per day.
+#[must_use]
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct DailyEnergy {
+    pub date: NaiveDate,
+    pub grid: energy::Flow<WattHours>,
+    pub battery: energy::Flow<WattHours>,
+
+    /// Residual energy by the end of the last journaled interval of the day.
+    pub residual_energy_after: Option<WattHours>,
+}
+
+impl DailyEnergy {
+    /// Sum up the journal entries per local date of the interval start.
+    pub fn aggregate(entries: impl IntoIterator<Item = JournalEntry>) -> Vec<Self> {
+        let mut days = BTreeMap::<NaiveDate, Self>::new();
+        for entry in entries {
+            let date = entry.interval.start().date_naive();
+            let day = days.entry(date).or_insert(Self {
+                date,
+                grid: energy::Flow::ZERO,
+                battery: energy::Flow::ZERO,
+                residual_energy_after: None,
+            });
+            day.grid += entry.actual.grid;
+            day.battery += entry.actual.battery;
+            if entry.actual_residual_energy_after.is_some() {
+                day.residual_energy_after = entry.actual_residual_energy_after;
+            }
+        }
+        days.into_values().collect()
+    }
+}
+
+/// Decomposition of the realized minus planned loss.
+///
+/// Prices are day-ahead, so they are exact, and there is no price error to attribute.
+#[must_use]
+#[derive(Copy, Clone, derive_more::Add)]
+pub struct Attribution {
+    /// Household consumption and production forecast error.
+    pub consumption: Mills,
+
+    /// Difference between the planned and the actual battery flow: power limits, efficiency,
+    /// and the battery simply doing its own thing.
+    pub battery: Mills,
+
+    /// Planned flows missed while the interval was not being tracked.
+    pub latency: Mills,
+}
+
+impl Zero for Attribution {
+    const ZERO: Self =
+        Self { consumption: Mills::ZERO, battery: Mills::ZERO, latency: Mills::ZERO };
+}
+
+impl Attribution {
+    pub fn total(self) -> Mills {
+        self.consumption + self.battery + self.latency
+    }
+}
+
+impl Execution {
+    /// Append-only log of the completed executions, one JSON object per line.
+    ///
+    /// Meant for backtesting and post-mortems, Fennec itself only reads it to serve the history API.
+    const JOURNAL_PATH: &str = "executions.jsonl";
+
+    #[instrument(skip_all, fields(path = Self::JOURNAL_PATH))]
+    pub async fn append_to_journal(&self) -> Result {
+        jsonl::append(Path::new(Self::JOURNAL_PATH), self).await
+    }
+
+    /// Read the measured part of the executions which have started since the specified moment.
+    #[instrument(fields(path = Self::JOURNAL_PATH))]
+    pub async fn read_journal(since: DateTime<Local>) -> Result<Vec<JournalEntry>> {
+        let mut entries: Vec<JournalEntry> = jsonl::read(Path::new(Self::JOURNAL_PATH)).await?;
+        entries.retain(|entry| entry.interval.start() >= since);
+        Ok(entries)
+    }
+
+    /// Export the executions which have started since the specified moment into the CSV file.
+    #[instrument(skip_all, fields(since = ?since, path = ?path))]
+    pub async fn export_journal(since: DateTime<Local>, path: &Path) -> Result {
+        let entries = Self::read_journal(since).await?;
+        let mut csv = Vec::new();
+        JournalEntry::write_csv(&entries, &mut csv)?;
+        tokio::fs::write(path, csv).await.context("failed to write the CSV file")?;
+        info!(n_entries = entries.len(), "exported the executions");
+        Ok(())
+    }
+
+    /// Actual minus planned residual energy by the end of the interval.
+    pub fn residual_energy_drift(&self) -> Option<WattHours> {
+        self.actual_residual_energy_after
+            .map(|actual| actual - WattHours::from(self.planned.residual_energy_after))
+    }
+
+    /// Relative deviation of the actual battery throughput from the planned one.
+    ///
+    /// Returns [`None`] if nothing was planned, as any deviation from nothing is infinite.
+    pub fn battery_deviation(&self) -> Option<f64> {
+        let planned = self.planned.energy_balance.battery.total_throughput();
+        let actual = self.actual.battery.total_throughput();
+        (planned >= WattHours::ONE).then(|| (actual - planned) / planned)
+    }
+
+    /// Attribute the realized minus planned grid loss to the different model errors.
+    pub fn attribution(&self) -> Attribution {
+        let tracked_fraction =
+            Hours::from(self.interval.end() - self.tracked_since).0 / self.planned.duration.0;
+        let planned = self.planned.energy_balance;
+        let expected = planned * tracked_fraction.clamp(0.0, 1.0);
+        let latency = self.energy_price.loss(expected.grid) - self.energy_price.loss(planned.grid);
+
+        // Grid is to supply the battery net charge difference:
+        let battery_difference = (self.actual.battery.import - self.actual.battery.export)
+            - (expected.battery.import - expected.battery.export);
+        let battery = if battery_difference >= WattHours::ZERO {
+            battery_difference * self.energy_price.import
+        } else {
+            battery_difference * self.energy_price.export
+        };
+
+        // And whatever remains is on the household:
+        let consumption = self.energy_price.loss(self.actual.grid)
+            - self.energy_price.loss(expected.grid)
+            - battery;
+
+        Attribution { consumption, battery, latency }
+    }
+}
+
+/// Tracks the measured energy flows against the first step of the current plan.
+#[derive(Default)]
+pub struct Tracker {
+    /// Execution in progress and the last measurement timestamp.
+    current: Option<(Execution, DateTime<Local>)>,
+
+    /// Completed executions over the last day, most recent last.
+    history: VecDeque<Execution>,
+}
+
+impl Tracker {
+    const RETENTION: TimeDelta = TimeDelta::days(1);
+
+    /// Accumulate the measured power since the last call and roll over the completed interval.
+    ///
+    /// Returns the just completed execution, if any.
+    pub fn track(
+        &mut self,
+        now: DateTime<Local>,
+        actual: energy::Balance<Watts>,
+        residual_energy: WattHours,
+        plan: Option<&Plan>,
+    ) -> Option<Execution> {
+        let mut completed = None;
+        if let Some((execution, last_timestamp)) = &mut self.current {
+            let until = now.min(execution.interval.end());
+            if until > *last_timestamp {
+                execution.actual += actual * Hours::from(until - *last_timestamp);
+            }
+            *last_timestamp = now;
+            if now >= execution.interval.end() {
+                execution.actual_residual_energy_after = Some(residual_energy);
+                self.history.push_back(execution.clone());
+                completed = Some(execution.clone());
+                self.current = None;
+            }
+        }
+        while self
+            .history
+            .front()
+            .is_some_and(|execution| now - execution.interval.end() > Self::RETENTION)
+        {
+            self.history.pop_front();
+        }
+
+        if self.current.is_none()
+            && let Some(plan) = plan
+            && plan.schedule.len() != 0
+        {
+            let slot = plan.schedule.get(0);
+            if slot.interval.start() <= now && now < slot.interval.end() {
+                let execution = Execution {
+                    interval: slot.interval,
+                    energy_price: slot.value.0,
+                    planned: slot.value.1,
+                    tracked_since: now,
+                    actual: energy::Balance::ZERO,
+                    actual_residual_energy_after: None,
+                    manifest: plan.manifest.clone(),
+                };
+                self.current = Some((execution, now));
+            }
+        }
+        completed
+    }
+
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Execution> {
+        self.history.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{
+        battery::WorkingMode,
+        quantity::{Quantity, ratios::Percentage},
+        solution::Metrics,
+    };
+
+    /// Verify that the attribution adds up to the realized minus planned loss.
+    #[test]
+    fn attribution_adds_up() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let energy_price = energy::Flow { import: Quantity(0.3), export: Quantity(0.1) };
+        let planned = energy::Balance {
+            grid: energy::Flow { import: Quantity(100.0), export: Quantity(0.0) },
+            battery: energy::Flow { import: Quantity(0.0), export: Quantity(200.0) },
+        };
+        let execution = Execution {
+            interval: Interval::new(start, start + TimeDelta::hours(1)),
+            energy_price,
+            planned: Step {
+                duration: Quantity(1.0),
+                energy_balance: planned,
+                working_mode: WorkingMode::SelfUse,
+                power_level: Percentage::FULL,
+                residual_energy_after: Quantity(0),
+                metrics: Metrics::ZERO,
+            },
+            tracked_since: start + TimeDelta::minutes(6),
+            actual: energy::Balance {
+                grid: energy::Flow { import: Quantity(150.0), export: Quantity(10.0) },
+                battery: energy::Flow { import: Quantity(0.0), export: Quantity(120.0) },
+            },
+            actual_residual_energy_after: None,
+            manifest: None,
+        };
+        let attribution = execution.attribution();
+        let difference = energy_price.loss(execution.actual.grid) - energy_price.loss(planned.grid);
+        assert!((attribution.total() - difference).abs() < Mills::ONE / 1000.0);
+        assert!(attribution.latency < Mills::ZERO);
+        assert!(attribution.battery > Mills::ZERO);
+    }
+
+    #[test]
+    fn daily_energy_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 23, 0, 0).unwrap();
+        let entry = |hour: i64, residual_energy: f64| JournalEntry {
+            interval: Interval::new(
+                start + TimeDelta::hours(hour),
+                start + TimeDelta::hours(hour + 1),
+            ),
+            planned: PlannedEntry {
+                duration: Quantity(1.0),
+                energy_balance: energy::Balance::ZERO,
+                residual_energy_after: Quantity(0),
+            },
+            tracked_since: start + TimeDelta::hours(hour),
+            actual: energy::Balance {
+                grid: energy::Flow { import: Quantity(100.0), export: Quantity(10.0) },
+                battery: energy::Flow { import: Quantity(50.0), export: Quantity(0.0) },
+            },
+            actual_residual_energy_after: Some(Quantity(residual_energy)),
+        };
+        let days = DailyEnergy::aggregate([entry(0, 1000.0), entry(1, 1050.0), entry(2, 1100.0)]);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, start.date_naive());
+        assert_eq!(days[0].residual_energy_after, Some(Quantity(1000.0)));
+        assert_eq!(days[1].grid.import, Quantity(200.0));
+        assert_eq!(days[1].battery.import, Quantity(100.0));
+        assert_eq!(days[1].residual_energy_after, Some(Quantity(1100.0)));
+    }
+
+    #[test]
+    fn write_csv_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let entry = JournalEntry {
+            interval: Interval::new(start, start + TimeDelta::hours(1)),
+            planned: PlannedEntry {
+                duration: Quantity(1.0),
+                energy_balance: energy::Balance::ZERO,
+                residual_energy_after: Quantity(1000),
+            },
+            tracked_since: start,
+            actual: energy::Balance {
+                grid: energy::Flow { import: Quantity(150.0), export: Quantity(0.0) },
+                battery: energy::Flow::ZERO,
+            },
+            actual_residual_energy_after: None,
+        };
+        let mut csv = Vec::new();
+        JournalEntry::write_csv(&[entry], &mut csv)?;
+        let csv = String::from_utf8(csv)?;
+        let mut lines = csv.lines();
+        assert_eq!(lines.next().unwrap().split(',').count(), 14);
+        assert_eq!(
+            lines.next(),
+            Some(
+                "2026-04-08T13:00:00+02:00,2026-04-08T14:00:00+02:00,2026-04-08T13:00:00+02:00,\
+                 1,0,0,0,0,1000,150,0,0,0,",
+            ),
+        );
+        assert_eq!(lines.next(), None);
+        Ok(())
+    }
+}
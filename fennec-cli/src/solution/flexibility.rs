@@ -0,0 +1,81 @@
+//! Experimental report of the flexibility the battery could offer on top of the plan,
+//! for the aggregator platforms trading it on the flexibility markets.
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    quantity::{Quantity, energy::WattHours, price::KilowattHourPrice},
+    solution::{Solution, Stage},
+};
+
+#[must_use]
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct Flexibility {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+
+    /// Extra energy the battery could take from the grid within the interval.
+    pub upward: WattHours,
+
+    /// Extra energy the battery could feed into the grid within the interval.
+    pub downward: WattHours,
+
+    /// Marginal value of the stored energy by the end of the interval, per the solution space.
+    ///
+    /// Shifting the energy breaks even at this price: it is the bid to sell the upward flexibility
+    /// above, and the downward one below. Missing at the horizon end and the allowed bounds.
+    pub marginal_value: Option<KilowattHourPrice>,
+}
+
+/// Derivative of the future loss by the residual energy, approximated by the neighbouring levels.
+#[expect(clippy::cast_precision_loss)]
+pub fn marginal_value(
+    next_stage: &Stage,
+    lower: WattHours<usize>,
+    upper: WattHours<usize>,
+) -> Option<KilowattHourPrice> {
+    // Both may round to the same level, which tells nothing about the derivative:
+    if next_stage.level_of(lower) >= next_stage.level_of(upper) {
+        return None;
+    }
+    let loss_at = |residual_energy| next_stage[residual_energy].as_ref().map(Solution::total_loss);
+    let (lower_loss, upper_loss) = (loss_at(lower)?, loss_at(upper)?);
+
+    // The losses are in thousandths of ¤, so per watt-hour they are in ¤/kWh:
+    Some(Quantity((lower_loss.0 - upper_loss.0) / (upper.0 - lower.0) as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroUsize;
+
+    use super::*;
+    use crate::{
+        battery::WorkingMode,
+        energy,
+        quantity::Zero,
+        solution::{Losses, Metrics, Step},
+    };
+
+    #[test]
+    fn marginal_value_ok() {
+        let mut stage =
+            Stage::new(energy::Flow::ZERO, Quantity(1000), NonZeroUsize::new(10).unwrap());
+        for (residual_energy, loss) in [(490, 100.0), (500, 90.0), (510, 80.0)] {
+            let metrics =
+                Metrics { losses: Losses::new(Quantity(loss), Quantity(0.0)), ..Metrics::ZERO };
+            stage[Quantity(residual_energy)] =
+                Some(Solution { metrics, step: Step::hourly(WorkingMode::Idle, residual_energy) });
+        }
+
+        // More stored energy means less future loss, so it is worth buying:
+        let value = marginal_value(&stage, Quantity(490), Quantity(510)).unwrap();
+        assert!((value.0 - 1.0).abs() < 1e-9, "{value:?}");
+
+        // Both round to the same level:
+        assert_eq!(marginal_value(&stage, Quantity(498), Quantity(502)), None);
+
+        // No solution at the lower level:
+        assert_eq!(marginal_value(&stage, Quantity(480), Quantity(500)), None);
+    }
+}
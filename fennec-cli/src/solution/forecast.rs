@@ -0,0 +1,86 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+
+use crate::{ops::jsonl, prelude::*, quantity::energy::WattHours, solution::Plan};
+
+/// Planned residual energy curve, to be compared against the actual one afterwards.
+#[must_use]
+#[derive(Clone, serde::Serialize)]
+pub struct Forecast {
+    pub planned_at: DateTime<Local>,
+
+    /// Battery actual capacity the state-of-charge is relative to.
+    pub actual_capacity: WattHours,
+
+    pub points: Vec<ForecastPoint>,
+}
+
+/// Planned residual energy by the end of the plan step.
+#[derive(Copy, Clone, serde::Serialize)]
+pub struct ForecastPoint {
+    pub timestamp: DateTime<Local>,
+    pub residual_energy: WattHours,
+
+    /// Unrounded percentage, as opposed to what the BMS reports.
+    pub state_of_charge: f64,
+}
+
+impl Forecast {
+    /// Append-only log of the forecasts, one JSON object per line.
+    ///
+    /// Only the first plan of each step gets journaled, which is enough to overlay
+    /// with the actual residual energy, and keeps the file size at bay.
+    const JOURNAL_PATH: &str = "forecasts.jsonl";
+
+    pub fn new(plan: &Plan, planned_at: DateTime<Local>, actual_capacity: WattHours) -> Self {
+        let points = plan
+            .schedule
+            .iter()
+            .map(|slot| {
+                let residual_energy = WattHours::from(slot.value.1.residual_energy_after);
+                ForecastPoint {
+                    timestamp: slot.interval.end(),
+                    residual_energy,
+                    state_of_charge: 100.0 * residual_energy / actual_capacity,
+                }
+            })
+            .collect();
+        Self { planned_at, actual_capacity, points }
+    }
+
+    #[instrument(skip_all, fields(path = Self::JOURNAL_PATH, planned_at = ?self.planned_at))]
+    pub async fn append_to_journal(&self) -> Result {
+        jsonl::append(Path::new(Self::JOURNAL_PATH), self).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::{
+        battery::WorkingMode,
+        energy,
+        quantity::{Quantity, Zero},
+        solution::Step,
+    };
+
+    #[test]
+    fn new_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let plan = Plan::hourly(
+            start,
+            [1000, 2500]
+                .map(|after| (energy::Flow::ZERO, Step::hourly(WorkingMode::Charge, after))),
+        )?;
+
+        let forecast = Forecast::new(&plan, start, Quantity(5000.0));
+        assert_eq!(forecast.points.len(), 2);
+        assert_eq!(forecast.points[0].timestamp, start + TimeDelta::hours(1));
+        assert_eq!(forecast.points[1].residual_energy, Quantity(2500.0));
+        assert!((forecast.points[1].state_of_charge - 50.0).abs() < 1e-9);
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Local};
+use clap::crate_version;
+use serde::Serialize;
+
+use crate::{battery, energy, quantity::ratios::Percentage};
+
+/// Reproducibility manifest: everything that went into the solution space, so that the plans
+/// and executions can later be grouped by configuration.
+#[must_use]
+#[derive(Clone, Serialize)]
+pub struct Manifest {
+    pub fennec_version: &'static str,
+
+    /// Price source.
+    pub energy_provider: energy::Provider,
+
+    /// When the prices were fetched and the solution space was built.
+    pub built_at: DateTime<Local>,
+
+    pub battery: battery::Args,
+    pub min_final_soc: Percentage,
+
+    /// Battery temperature band the efficiency was taken for, if known.
+    pub temperature_band: Option<i16>,
+}
+
+impl Manifest {
+    pub const fn new(
+        energy_provider: energy::Provider,
+        built_at: DateTime<Local>,
+        battery: battery::Args,
+        min_final_soc: Percentage,
+        temperature_band: Option<i16>,
+    ) -> Self {
+        Self {
+            fennec_version: crate_version!(),
+            energy_provider,
+            built_at,
+            battery,
+            min_final_soc,
+            temperature_band,
+        }
+    }
+}
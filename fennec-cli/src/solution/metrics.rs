@@ -7,12 +7,21 @@ use crate::{
 };
 
 #[must_use]
-#[derive(Copy, Clone, Add, AddAssign)]
+#[derive(Copy, Clone, Add, AddAssign, serde::Serialize)]
 pub struct Metrics {
     pub internal_battery_flow: Flow<WattHours>,
     pub losses: Losses,
+
+    /// Cumulative residual energy missing to the backup reserve by the end of the steps.
+    ///
+    /// Whole watt-hours, so that the sums are exact.
+    pub reserve_shortfall: WattHours,
 }
 
 impl Zero for Metrics {
-    const ZERO: Self = Self { internal_battery_flow: Flow::ZERO, losses: Losses::ZERO };
+    const ZERO: Self = Self {
+        internal_battery_flow: Flow::ZERO,
+        losses: Losses::ZERO,
+        reserve_shortfall: WattHours::ZERO,
+    };
 }
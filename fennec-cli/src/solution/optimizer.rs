@@ -1,6 +1,13 @@
-use std::{range::RangeInclusive, time::Instant};
+use std::{
+    num::NonZeroUsize,
+    ops::{ControlFlow, Range},
+    range::RangeInclusive,
+    sync::Arc,
+    thread,
+    time::Instant,
+};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, TimeDelta};
 
 use crate::{
     Schedule,
@@ -8,10 +15,33 @@ use crate::{
     battery,
     battery::WorkingMode,
     energy,
+    ev,
+    ops::interval::Interval,
     prelude::*,
-    quantity::{Quantity, energy::WattHours, power::Watts, price::KilowattHourPrice, time::Hours},
+    quantity::{
+        Quantity,
+        Zero,
+        currency::Mills,
+        energy::WattHours,
+        power::Watts,
+        price::KilowattHourPrice,
+        ratios::Percentage,
+        time::Hours,
+    },
     series::Slot,
-    solution::{Losses, Metrics, Solution, Space, Stage, Step},
+    solution::{
+        Flexibility,
+        Losses,
+        Manifest,
+        Metrics,
+        Plan,
+        Solution,
+        Space,
+        Stage,
+        Step,
+        WarmStart,
+        flexibility,
+    },
 };
 
 #[must_use]
@@ -19,9 +49,21 @@ pub struct Optimizer {
     /// Actual battery capacity.
     battery_capacity: WattHours,
 
+    /// Battery power limits to split the planned-against net load with.
+    battery_power_limits: battery::PowerLimits,
+
     /// Maximum allowed battery flow.
     max_battery_flow: energy::Flow<Watts>,
 
+    /// Minimal operating battery flow.
+    min_battery_flow: energy::Flow<Watts>,
+
+    /// State-of-charge dependent derating points, sorted.
+    battery_derating: energy::Flow<Vec<battery::derating::Point>>,
+
+    /// Backup reserve windows.
+    reserve_windows: Vec<battery::reserve::ReserveWindow>,
+
     /// Allowed residual energy levels per the battery settings.
     allowed_residual_energy: RangeInclusive<WattHours<usize>>,
 
@@ -31,12 +73,58 @@ pub struct Optimizer {
     /// Incurred costs per energy flow to and from the battery.
     battery_degradation_cost: KilowattHourPrice,
 
+    /// Margin to keep away from the allowed residual energy bounds.
+    soc_hysteresis: WattHours,
+
+    /// Hourly penalty for the residual energy within [`Optimizer::soc_hysteresis`].
+    soc_hysteresis_cost: KilowattHourPrice,
+
+    /// Cost of switching the working mode between the adjacent intervals.
+    switching_cost: Mills,
+
     /// Allowed working modes.
     working_modes: Vec<WorkingMode>,
 
+    /// Avoid the working modes which feed into the grid at negative export prices.
+    no_export_when_negative: bool,
+
+    /// Allowed power levels of the forced charging and discharging.
+    power_levels: energy::Flow<Vec<Percentage>>,
+
+    /// What the solution space is built from.
+    manifest: Arc<Manifest>,
+
+    /// Battery efficiency in the temperature band.
+    battery_efficiency: energy::Flow<f64>,
+
+    /// Efficiency of charging from the excess solar power, which depends on the PV coupling.
+    solar_charging_efficiency: f64,
+
+    /// Inverter stand-by consumption on top of the learned household balance.
+    inverter_standby_power: Watts,
+
     /// Learned energy profile to make battery usage prognoses.
     energy_profile: energy::Profile,
 
+    /// Planned EV charging on top of the learned household consumption.
+    ev_plan: Option<ev::Plan>,
+
+    /// Number of threads to solve the energy levels of a single stage with.
+    n_threads: NonZeroUsize,
+
+    /// Energy quantization step in watt-hours.
+    quantum: NonZeroUsize,
+
+    /// Household energy balance scenarios as the factors of the learned mean,
+    /// each step's metrics are the expectation over them.
+    load_factors: Vec<f64>,
+
+    /// Household load percentile to plan against.
+    load_percentile: energy::LoadPercentile,
+
+    /// Previous plan to narrow down the solved energy levels and to stick to.
+    warm_start: Option<WarmStart>,
+
     /// Maintained solution space – this is what we are for.
     solution_space: Space,
 }
@@ -48,33 +136,110 @@ impl Optimizer {
         battery_capacity: WattHours,
         allowed_residual_energy: RangeInclusive<WattHours<usize>>,
         min_final_residual_energy: WattHours<usize>,
+        ev_plan: Option<ev::Plan>,
+        manifest: Manifest,
     ) -> Self {
+        let battery_efficiency = energy_profile.battery.efficiency_at(manifest.temperature_band);
         Self {
+            battery_efficiency,
+            solar_charging_efficiency: battery_args.pv_coupling.solar_charging_efficiency(
+                battery_efficiency.import,
+                battery_args.inverter_efficiency,
+            ),
+            manifest: Arc::new(manifest),
             battery_capacity,
+            battery_power_limits: battery_args.power_limits,
             max_battery_flow: battery_args
                 .power_limits
                 .max_effective_flow(energy_profile.energy.eps_active_power.0),
+            min_battery_flow: battery_args.power_limits.min_flow(),
+            battery_derating: energy::Flow {
+                import: Self::sorted_derating(&battery_args.charging_derating),
+                export: Self::sorted_derating(&battery_args.discharging_derating),
+            },
+            reserve_windows: battery_args.reserve_windows.clone(),
+            inverter_standby_power: battery_args.inverter_standby_power,
             energy_profile,
+            ev_plan,
             allowed_residual_energy,
             min_final_residual_energy,
             battery_degradation_cost: battery_args.degradation_cost,
+            soc_hysteresis: battery_capacity * battery_args.soc_hysteresis,
+            soc_hysteresis_cost: battery_args.soc_hysteresis_cost,
+            switching_cost: battery_args.switching_cost,
             working_modes: battery_args.working_modes.clone(),
+            no_export_when_negative: battery_args.no_export_when_negative,
+            power_levels: battery_args.forced_power_levels(),
+            n_threads: NonZeroUsize::MIN,
+            quantum: NonZeroUsize::MIN,
+            load_factors: vec![1.0],
+            load_percentile: energy::LoadPercentile::Mean,
+            warm_start: None,
             solution_space: Series::new(),
         }
     }
 
+    pub const fn with_n_threads(mut self, n_threads: NonZeroUsize) -> Self {
+        self.n_threads = n_threads;
+        self
+    }
+
+    pub const fn with_quantum(mut self, quantum: NonZeroUsize) -> Self {
+        self.quantum = quantum;
+        self
+    }
+
+    fn sorted_derating(points: &[battery::derating::Point]) -> Vec<battery::derating::Point> {
+        let mut points = points.to_vec();
+        points.sort_by_key(|point| point.state_of_charge);
+        points
+    }
+
+    pub fn with_load_factors(mut self, load_factors: Vec<f64>) -> Self {
+        assert!(!load_factors.is_empty());
+        self.load_factors = load_factors;
+        self
+    }
+
+    pub const fn with_load_percentile(mut self, load_percentile: energy::LoadPercentile) -> Self {
+        self.load_percentile = load_percentile;
+        self
+    }
+
+    /// Scale the battery degradation cost, for example, to stay within the throughput budget.
+    pub fn with_degradation_cost_factor(mut self, factor: f64) -> Self {
+        self.battery_degradation_cost = self.battery_degradation_cost * factor;
+        self
+    }
+
+    pub fn with_warm_start(mut self, warm_start: Option<WarmStart>) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
     pub const fn solution_space(&self) -> &Space {
         &self.solution_space
     }
 
+    pub fn manifest(&self) -> Arc<Manifest> {
+        self.manifest.clone()
+    }
+
+    /// Battery efficiency the solution space is built with.
+    pub const fn battery_efficiency(&self) -> energy::Flow<f64> {
+        self.battery_efficiency
+    }
+
     /// Returns [`true`] if the optimizer's battery parameters still match – no rebuild needed.
     pub fn matches(
         &self,
         battery_capacity: WattHours,
         allowed_residual_energy: RangeInclusive<WattHours<usize>>,
+        temperature_band: Option<i16>,
     ) -> bool {
         (self.battery_capacity == battery_capacity) // FIXME: `f64` exact comparison.
             && (self.allowed_residual_energy == allowed_residual_energy)
+            && (self.manifest.temperature_band == temperature_band)
     }
 
     /// Populate the solution space from scratch.
@@ -89,25 +254,62 @@ impl Optimizer {
     ///
     /// For each state, we pick the battery mode that minimizes total cost including future consequences.
     ///
+    /// The progress callback receives the number of processed and total intervals after each interval,
+    /// and may break out of the solving. A partial backward pass is useless, so the solving fails then.
+    ///
     /// [1]: https://en.wikipedia.org/wiki/Dynamic_programming
     #[instrument(skip_all)]
-    pub fn solve(&mut self, energy_prices: &Schedule<energy::Flow<KilowattHourPrice>>) {
+    pub fn solve(
+        &mut self,
+        energy_prices: &Schedule<energy::Flow<KilowattHourPrice>>,
+        mut on_progress: impl FnMut(usize, usize) -> ControlFlow<()>,
+    ) -> Result {
         let start_instant = Instant::now();
 
         info!(?self.allowed_residual_energy, ?self.min_final_residual_energy, n_intervals = energy_prices.len(), "optimizing…");
 
         let battery_capacity: WattHours<usize> = self.battery_capacity.into();
-        self.solution_space = energy_prices.map(|price| Stage::new(*price, battery_capacity));
+        self.solution_space =
+            energy_prices.map(|price| Stage::new(*price, battery_capacity, self.quantum));
 
         // Going backwards:
-        for interval_index in (0..self.solution_space.len()).rev() {
-            // Calculate partial solutions for the current time interval:
-            for residual_energy in (0..=battery_capacity.0).map(Quantity) {
-                self.optimize_state(interval_index, residual_energy);
+        let n_intervals = self.solution_space.len();
+        for interval_index in (0..n_intervals).rev() {
+            self.optimize_stage(interval_index);
+            if on_progress(n_intervals - interval_index, n_intervals).is_break() {
+                self.solution_space = Series::new();
+                bail!("solving has been cancelled");
             }
         }
 
         info!(elapsed = ?start_instant.elapsed(), "optimized");
+        Ok(())
+    }
+
+    /// Override the prices of the intervals starting before the specified timestamp,
+    /// restore the base prices of the rest, and re-solve the changed intervals.
+    ///
+    /// The later stages do not depend on the earlier ones, so the backward pass
+    /// only needs to cover the intervals up to the last changed one.
+    ///
+    /// Returns the number of the re-solved intervals.
+    pub fn override_prices(
+        &mut self,
+        until: DateTime<Local>,
+        price_at: impl Fn(DateTime<Local>) -> Option<energy::Flow<KilowattHourPrice>>,
+    ) -> usize {
+        let mut n_intervals = 0;
+        for interval_index in 0..self.solution_space.len() {
+            let start = self.solution_space.get(interval_index).interval.start();
+            let price = if start < until { price_at(start) } else { None };
+            if self.solution_space.get_mut(interval_index).override_price(price) {
+                n_intervals = interval_index + 1;
+            }
+        }
+        for interval_index in (0..n_intervals).rev() {
+            self.optimize_stage(interval_index);
+        }
+        n_intervals
     }
 
     /// Advance the optimizer solution space so that it starts at the specified timestamp.
@@ -118,32 +320,110 @@ impl Optimizer {
         self.solution_space.advance_to(timestamp) != 0
     }
 
+    /// Drop the warm start and solve the entire space again,
+    /// if the current state has been left out of the warm-start corridor.
+    pub fn fall_back_to_cold_start(&mut self, initial_residual_energy: WattHours<usize>) {
+        if self.warm_start.is_none()
+            || self.solution_space.len() == 0
+            || self.solution_space.get(0).value[initial_residual_energy].is_some()
+        {
+            return;
+        }
+        warn!(?initial_residual_energy, "no warm-started solution, solving from scratch…");
+        self.warm_start = None;
+        for interval_index in (0..self.solution_space.len()).rev() {
+            self.optimize_stage(interval_index);
+        }
+    }
+
+    /// Calculate partial solutions for all the energy levels of the time interval.
+    ///
+    /// The energy levels only depend on the next stage, so they are solved in parallel.
+    /// When warm-started, only the levels within the corridor get solved, except for the first stage
+    /// which the current state may deviate from the previous plan at.
+    fn optimize_stage(&mut self, interval_index: usize) {
+        let Slot { interval, value: stage } = self.solution_space.get(interval_index);
+        let corridor = self
+            .warm_start
+            .as_ref()
+            .filter(|_| interval_index != 0)
+            .and_then(|warm_start| warm_start.corridor_at(interval.start()));
+        let energy_levels: Vec<_> = stage
+            .energy_levels()
+            .filter(|residual_energy| {
+                corridor.is_none_or(|corridor| corridor.contains(residual_energy))
+            })
+            .collect();
+        let chunk_size = energy_levels.len().div_ceil(self.n_threads.get());
+        let solutions: Vec<Option<Solution>> = if self.n_threads == NonZeroUsize::MIN {
+            energy_levels
+                .iter()
+                .map(|residual_energy| self.solve_state(interval_index, *residual_energy))
+                .collect()
+        } else {
+            let this = &*self;
+            thread::scope(|scope| {
+                // Spawn all the threads before joining any of them:
+                #[expect(clippy::needless_collect)]
+                let handles: Vec<_> = energy_levels
+                    .chunks(chunk_size)
+                    .map(|chunk| {
+                        scope.spawn(move || {
+                            chunk
+                                .iter()
+                                .map(|residual_energy| {
+                                    this.solve_state(interval_index, *residual_energy)
+                                })
+                                .collect::<Vec<_>>()
+                        })
+                    })
+                    .collect();
+                handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+            })
+        };
+        let stage = self.solution_space.get_mut(interval_index);
+        stage.clear();
+        for (residual_energy, solution) in energy_levels.into_iter().zip(solutions) {
+            stage[residual_energy] = solution;
+        }
+    }
+
     /// Optimize the state and assign the solution.
     pub fn optimize_state(
         &mut self,
         interval_index: usize,
         initial_residual_energy: WattHours<usize>,
     ) {
+        self.solution_space.get_mut(interval_index)[initial_residual_energy] =
+            self.solve_state(interval_index, initial_residual_energy);
+    }
+
+    /// Find the best solution for the state.
+    fn solve_state(
+        &self,
+        interval_index: usize,
+        initial_residual_energy: WattHours<usize>,
+    ) -> Option<Solution> {
         let Slot { interval, value: stage } = self.solution_space.get(interval_index);
         let duration = interval.duration().into();
-        let average_balance = self.energy_profile.energy.normalized_mean_over(interval);
-        let battery_simulator = battery::Simulator {
-            residual_energy: initial_residual_energy.into(),
-            capacity: self.battery_capacity,
-            efficiency: self.energy_profile.battery.efficiency,
-        };
-        self.solution_space.get_mut(interval_index)[initial_residual_energy] = self
-            .working_modes
-            .iter()
-            .copied()
-            .filter_map(|working_mode| {
-                let step = self.simulate_step(
+        let average_balance = self.average_balance_over(interval);
+        let battery_simulator = self.battery_simulator(initial_residual_energy);
+        let reserve = self.reserve_after(interval_index);
+        let preferred = self
+            .warm_start
+            .as_ref()
+            .and_then(|warm_start| warm_start.decision_at(interval.start()));
+        self.actions(stage.price())
+            .filter_map(|(working_mode, power_level)| {
+                let mut step = self.simulate_step(
                     battery_simulator,
                     duration,
                     average_balance,
                     stage.price(),
                     working_mode,
+                    power_level,
                 );
+                step.metrics.reserve_shortfall = reserve_shortfall(reserve, &step);
                 if (step.residual_energy_after < initial_residual_energy)
                     && (initial_residual_energy <= self.allowed_residual_energy.start)
                 {
@@ -157,59 +437,722 @@ impl Optimizer {
                     return None;
                 }
 
-                let mut metrics = step.metrics;
                 let next_interval_index = interval_index + 1;
 
-                if next_interval_index < self.solution_space.len() {
+                let metrics = if next_interval_index < self.solution_space.len() {
                     // For non-boundary solutions, accumulate the target optimization metrics:
-                    metrics += self.solution_space.get(next_interval_index).value
+                    let next_solution = self.solution_space.get(next_interval_index).value
                         [step.residual_energy_after]
-                        .as_ref()?
-                        .metrics;
+                        .as_ref()?;
+                    if next_solution.step.working_mode != working_mode {
+                        step.metrics.losses += self.switching_loss();
+                    }
+                    step.metrics + next_solution.metrics
                 } else if step.residual_energy_after < self.min_final_residual_energy {
                     // Enforce the final residual energy:
                     return None;
-                }
+                } else {
+                    step.metrics
+                };
 
                 Some(Solution { metrics, step })
             })
-            .min_by(Solution::compare_loss_to);
+            .min_by(|lhs, rhs| lhs.compare_loss_to(rhs, preferred))
+    }
+
+    /// Evaluate fixed decisions, for example, of the currently active plan,
+    /// under the same conditions as the solution space.
+    ///
+    /// The decisions are followed until the first interval without one,
+    /// and the optimal solution continues from there on – so that the result is comparable
+    /// with the optimal plan over the same horizon.
+    ///
+    /// Returns [`None`] if the decisions end up in a state without a solution.
+    pub fn evaluate(
+        &self,
+        initial_residual_energy: WattHours<usize>,
+        decision_at: impl Fn(DateTime<Local>) -> Option<(WorkingMode, Percentage)>,
+    ) -> Option<Metrics> {
+        let steps = self.simulate(initial_residual_energy, decision_at);
+        let mut metrics = Metrics::ZERO;
+        let mut residual_energy = initial_residual_energy;
+        for (_, step) in &steps {
+            metrics += step.metrics;
+            residual_energy = step.residual_energy_after;
+        }
+        if let Some(slot) = self.solution_space.iter().nth(steps.len()) {
+            metrics += slot.value[residual_energy].as_ref()?.metrics;
+        }
+        Some(metrics)
+    }
+
+    /// Simulate fixed decisions until the first interval without one.
+    pub fn simulate(
+        &self,
+        initial_residual_energy: WattHours<usize>,
+        decision_at: impl Fn(DateTime<Local>) -> Option<(WorkingMode, Percentage)>,
+    ) -> Vec<SimulatedStep> {
+        let mut residual_energy = initial_residual_energy;
+        let mut steps = self
+            .solution_space
+            .iter()
+            .enumerate()
+            .map_while(|(interval_index, slot)| {
+                let (working_mode, power_level) = decision_at(slot.interval.start())?;
+                let mut step = self.simulate_step(
+                    self.battery_simulator(residual_energy),
+                    slot.interval.duration().into(),
+                    self.average_balance_over(slot.interval),
+                    slot.value.price(),
+                    working_mode,
+                    power_level,
+                );
+                step.metrics.reserve_shortfall =
+                    reserve_shortfall(self.reserve_after(interval_index), &step);
+                residual_energy = step.residual_energy_after;
+                Some((slot.interval, step))
+            })
+            .collect::<Vec<_>>();
+
+        // Charge the switches to the step before them, same as the solving does:
+        for index in 1..steps.len() {
+            if steps[index].1.working_mode != steps[index - 1].1.working_mode {
+                steps[index - 1].1.metrics.losses += self.switching_loss();
+            }
+        }
+        steps
+    }
+
+    /// Flexibility the battery could offer on top of the plan steps,
+    /// within the power limits and the allowed residual energy.
+    pub fn flexibility(&self, plan: &Plan) -> Vec<Flexibility> {
+        let quantum = self.quantum.get();
+        let (min_energy, max_energy) =
+            (self.allowed_residual_energy.start, self.allowed_residual_energy.last);
+        plan.schedule
+            .iter()
+            .zip(self.solution_space.iter().skip(1).map(Some).chain([None]))
+            .map(|(slot, next)| {
+                let step = slot.value.1;
+                let residual_energy_after = WattHours::from(step.residual_energy_after);
+                let headroom =
+                    (WattHours::from(max_energy) - residual_energy_after).max(WattHours::ZERO);
+                let stored =
+                    (residual_energy_after - WattHours::from(min_energy)).max(WattHours::ZERO);
+                let lower =
+                    Quantity(step.residual_energy_after.0.saturating_sub(quantum)).max(min_energy);
+                let upper = Quantity(step.residual_energy_after.0 + quantum).min(max_energy);
+                Flexibility {
+                    start: slot.interval.start(),
+                    end: slot.interval.end(),
+                    upward: (self.max_battery_flow.import * step.duration
+                        - step.energy_balance.battery.import)
+                        .max(WattHours::ZERO)
+                        .min(headroom / self.battery_efficiency.import),
+                    downward: (self.max_battery_flow.export * step.duration
+                        - step.energy_balance.battery.export)
+                        .max(WattHours::ZERO)
+                        .min(stored * self.battery_efficiency.export),
+                    marginal_value: next
+                        .and_then(|next| flexibility::marginal_value(next.value, lower, upper)),
+                }
+            })
+            .collect()
+    }
+
+    /// Merge the working mode switches of the plan into the preceding decisions,
+    /// as long as each merge costs less than the threshold.
+    ///
+    /// Every merge makes the plan slightly less optimal, but reduces the number of the schedule
+    /// slots the battery has to be programmed with.
+    pub fn smooth(
+        &self,
+        plan: Plan,
+        initial_residual_energy: WattHours<usize>,
+        threshold: Mills,
+    ) -> Result<Plan> {
+        let mut decisions = decisions_of(&plan);
+        let Some((mut steps, mut metrics)) =
+            self.try_decisions(initial_residual_energy, &decisions)
+        else {
+            return Ok(plan);
+        };
+        let mut n_merged = 0_usize;
+        for index in 1..decisions.len() {
+            if decisions[index].1 == decisions[index - 1].1 {
+                continue;
+            }
+            let mut candidate_decisions = decisions.clone();
+            candidate_decisions[index].1 = decisions[index - 1].1;
+            let Some((candidate_steps, candidate_metrics)) =
+                self.try_decisions(initial_residual_energy, &candidate_decisions)
+            else {
+                continue;
+            };
+            let extra_loss = Mills::from(candidate_metrics.losses.total() - metrics.losses.total());
+            if candidate_metrics.reserve_shortfall <= metrics.reserve_shortfall
+                && extra_loss < threshold
+            {
+                (decisions, steps, metrics) =
+                    (candidate_decisions, candidate_steps, candidate_metrics);
+                n_merged += 1;
+            }
+        }
+        if n_merged == 0 {
+            return Ok(plan);
+        }
+        info!(n_merged, "smoothed the plan");
+        with_steps(plan, steps, metrics)
+    }
+
+    /// Merge the runs of the same decisions, which start within the horizon from the plan start,
+    /// until at most the specified number of them is left.
+    ///
+    /// Each time, the merge with the least extra loss wins. Missing the backup reserve is worse
+    /// than any loss, same as in the solving.
+    ///
+    /// Returns the compressed plan along with the extra loss compared to the original plan.
+    /// The plan may still have too many runs if none of the merges is feasible.
+    pub fn compress(
+        &self,
+        plan: Plan,
+        initial_residual_energy: WattHours<usize>,
+        horizon: TimeDelta,
+        max_n_runs: usize,
+    ) -> Result<(Plan, Mills)> {
+        let mut decisions = decisions_of(&plan);
+        let Some(&(start, _)) = decisions.first() else {
+            return Ok((plan, Mills::ZERO));
+        };
+        let Some((mut steps, original_metrics)) =
+            self.try_decisions(initial_residual_energy, &decisions)
+        else {
+            return Ok((plan, Mills::ZERO));
+        };
+        let mut metrics = original_metrics;
+        let mut n_merged = 0_usize;
+        loop {
+            let runs = runs_of(&decisions);
+            let n_runs = runs.iter().filter(|run| decisions[run.start].0 < start + horizon).count();
+            if n_runs <= max_n_runs {
+                break;
+            }
+            let best = (0..n_runs)
+                .flat_map(|index| [index.checked_sub(1), Some(index + 1)].map(|n| (index, n)))
+                .filter_map(|(index, neighbor)| {
+                    let decision = decisions[runs.get(neighbor?)?.start].1;
+                    let mut candidate_decisions = decisions.clone();
+                    for (_, run_decision) in &mut candidate_decisions[runs[index].clone()] {
+                        *run_decision = decision;
+                    }
+                    let (candidate_steps, candidate_metrics) =
+                        self.try_decisions(initial_residual_energy, &candidate_decisions)?;
+                    Some((candidate_decisions, candidate_steps, candidate_metrics))
+                })
+                .min_by(|(_, _, lhs), (_, _, rhs)| {
+                    lhs.reserve_shortfall
+                        .0
+                        .total_cmp(&rhs.reserve_shortfall.0)
+                        .then(lhs.losses.total().cmp(&rhs.losses.total()))
+                });
+            let Some(best) = best else {
+                warn!(n_runs, max_n_runs, "none of the merges is feasible");
+                break;
+            };
+            (decisions, steps, metrics) = best;
+            n_merged += 1;
+        }
+        if n_merged == 0 {
+            return Ok((plan, Mills::ZERO));
+        }
+        let extra_loss = Mills::from(metrics.losses.total() - original_metrics.losses.total());
+        info!(n_merged, ?extra_loss, "compressed the plan");
+        Ok((with_steps(plan, steps, metrics)?, extra_loss))
+    }
+
+    /// Simulate the decisions, or [`None`] if they do not cover the solution space
+    /// or violate the constraints.
+    fn try_decisions(
+        &self,
+        initial_residual_energy: WattHours<usize>,
+        decisions: &[Decision],
+    ) -> Option<(Vec<SimulatedStep>, Metrics)> {
+        let steps = self.simulate(initial_residual_energy, |timestamp| {
+            let index = decisions.binary_search_by_key(&timestamp, |(start, _)| *start).ok()?;
+            Some(decisions[index].1)
+        });
+        if steps.len() != self.solution_space.len()
+            || !self.is_feasible(initial_residual_energy, &steps)
+        {
+            return None;
+        }
+        let metrics = total_metrics(&steps);
+        Some((steps, metrics))
+    }
+
+    /// Check the simulated steps against the same constraints as the solving does.
+    fn is_feasible(
+        &self,
+        initial_residual_energy: WattHours<usize>,
+        steps: &[SimulatedStep],
+    ) -> bool {
+        let mut residual_energy = initial_residual_energy;
+        for (_, step) in steps {
+            let residual_energy_after = step.residual_energy_after;
+            if (residual_energy_after < residual_energy
+                && residual_energy <= self.allowed_residual_energy.start)
+                || (residual_energy_after > residual_energy
+                    && residual_energy >= self.allowed_residual_energy.last)
+            {
+                return false;
+            }
+            residual_energy = residual_energy_after;
+        }
+        residual_energy >= self.min_final_residual_energy
+    }
+
+    fn switching_loss(&self) -> Losses {
+        Losses::new(Mills::ZERO, self.switching_cost)
+    }
+
+    /// Backup reserve to keep by the end of the interval.
+    ///
+    /// The reserve holds throughout the reserved interval,
+    /// so it applies both at the start and at the end of the interval.
+    fn reserve_after(&self, interval_index: usize) -> WattHours<usize> {
+        if self.reserve_windows.is_empty() {
+            return Quantity(0);
+        }
+        let state_of_charge = (interval_index..=interval_index + 1)
+            .filter(|index| *index < self.solution_space.len())
+            .map(|index| {
+                let start = self.solution_space.get(index).interval.start();
+                battery::reserve::reserve_at(&self.reserve_windows, start.time())
+            })
+            .max()
+            .unwrap_or(Percentage::ZERO);
+        (self.battery_capacity * state_of_charge).into()
+    }
+
+    /// Learned energy balance over the interval at the load percentile,
+    /// including the planned EV charging.
+    fn average_balance_over(&self, interval: Interval<DateTime<Local>>) -> energy::Balance<Watts> {
+        let average_balance = self.energy_profile.energy.balance_over(
+            interval,
+            self.load_percentile,
+            self.battery_power_limits,
+        );
+        self.ev_plan.as_ref().map_or(average_balance, |ev_plan| {
+            average_balance
+                .with_extra_load(ev_plan.mean_power_over(interval), self.max_battery_flow)
+        })
+    }
+
+    fn battery_simulator(&self, residual_energy: WattHours<usize>) -> battery::Simulator<'_> {
+        battery::Simulator {
+            residual_energy: residual_energy.into(),
+            capacity: self.battery_capacity,
+            efficiency: self.battery_efficiency,
+            min_power: self.min_battery_flow,
+            max_power: self.max_battery_flow,
+            derating: energy::Flow {
+                import: battery::derating::Curve(&self.battery_derating.import),
+                export: battery::derating::Curve(&self.battery_derating.export),
+            },
+        }
+    }
+
+    /// Enumerate the allowed combinations of the working modes and power levels.
+    ///
+    /// At negative export prices, the modes feeding into the grid are skipped, if so configured and
+    /// as long as any other mode is allowed.
+    fn actions(
+        &self,
+        energy_price: energy::Flow<KilowattHourPrice>,
+    ) -> impl Iterator<Item = (WorkingMode, Percentage)> {
+        let skip_feeding_in = self.no_export_when_negative
+            && energy_price.export < KilowattHourPrice::ZERO
+            && self.working_modes.iter().any(|working_mode| !working_mode.is_feeding_in());
+        let working_modes = self.working_modes.iter().copied();
+        working_modes
+            .filter(move |working_mode| !(skip_feeding_in && working_mode.is_feeding_in()))
+            .flat_map(|working_mode| {
+                let power_levels: &[Percentage] = match working_mode {
+                    WorkingMode::Charge => &self.power_levels.import,
+                    WorkingMode::Discharge => &self.power_levels.export,
+                    WorkingMode::Idle
+                    | WorkingMode::Harness
+                    | WorkingMode::Compensate
+                    | WorkingMode::SelfUse => &[Percentage::FULL],
+                };
+                power_levels.iter().map(move |power_level| (working_mode, *power_level))
+            })
     }
 
     /// Simulate the battery working in the specified mode given the initial conditions.
+    ///
+    /// The flows and the residual energy are the means over the load scenarios. The losses are linear
+    /// in them, except for the hysteresis penalty which is hence averaged separately.
     fn simulate_step(
         &self,
-        mut battery: battery::Simulator,
+        battery: battery::Simulator<'_>,
         duration: Hours,
         average_balance: energy::Balance<Watts>,
         energy_price: energy::Flow<KilowattHourPrice>,
         working_mode: WorkingMode,
+        power_level: Percentage,
     ) -> Step {
-        // Remember that the average flow represents theoretical possibility,
-        // actual flow depends on the working mode:
-        let balance_request =
-            average_balance.with_working_mode(working_mode, self.max_battery_flow);
-
-        let battery_flows = battery.apply(balance_request.battery, duration);
-        let requested_battery = balance_request.battery * duration;
-        let battery_shortage = requested_battery - battery_flows.external;
-        let grid_flow = balance_request.grid * duration + battery_shortage.reversed();
+        let max_battery_flow = self.max_battery_flow * power_level.to_ratio();
+        let mut grid_flow = energy::Flow::ZERO;
+        let mut battery_flows = battery::Flows::ZERO;
+        let mut residual_energy = WattHours::ZERO;
+        let mut hysteresis_penalty = Mills::ZERO;
+        for load_factor in &self.load_factors {
+            let mut battery = battery;
+
+            // Remember that the average flow represents theoretical possibility,
+            // actual flow depends on the working mode. The stand-by consumption
+            // does not scale with the household load, so it is added afterwards:
+            let scenario_balance = (average_balance * *load_factor)
+                .with_extra_load(self.inverter_standby_power, self.max_battery_flow);
+            let balance_request =
+                scenario_balance.with_working_mode(working_mode, max_battery_flow);
+            battery.efficiency.import = self.charging_efficiency(
+                balance_request.battery.import,
+                scenario_balance.battery.import,
+            );
+
+            let scenario_battery_flows = battery.apply(balance_request.battery, duration);
+            let requested_battery = balance_request.battery * duration;
+            let battery_shortage = requested_battery - scenario_battery_flows.external;
+            grid_flow += balance_request.grid * duration + battery_shortage.reversed();
+            battery_flows += scenario_battery_flows;
+            residual_energy += battery.residual_energy;
+            hysteresis_penalty += self.hysteresis_penalty(battery.residual_energy, duration);
+        }
+
+        #[expect(clippy::cast_precision_loss)]
+        let n_scenarios = self.load_factors.len() as f64;
+        let grid_flow = grid_flow / n_scenarios;
+        let battery_flows = battery_flows / n_scenarios;
+        let residual_energy = residual_energy / n_scenarios;
+
         Step {
             working_mode,
+            power_level,
             duration,
             energy_balance: energy::Balance {
                 grid: grid_flow.normalized(), // Normalize rare tiny negative values.
                 battery: battery_flows.external,
             },
-            residual_energy_after: battery.residual_energy.into(),
+            residual_energy_after: residual_energy.into(),
             metrics: Metrics {
                 internal_battery_flow: battery_flows.internal,
-                losses: Losses {
-                    grid: energy_price.loss(grid_flow),
-                    battery: (battery_flows.internal.import + battery_flows.internal.export)
-                        * self.battery_degradation_cost,
-                },
+                losses: Losses::new(
+                    energy_price.loss(grid_flow),
+                    (battery_flows.internal.import + battery_flows.internal.export)
+                        * self.battery_degradation_cost
+                        + hysteresis_penalty / n_scenarios,
+                ),
+                reserve_shortfall: WattHours::ZERO,
             },
         }
     }
+
+    /// Charging efficiency weighted by how much of the requested charging
+    /// the excess solar power covers, the rest comes from the grid.
+    fn charging_efficiency(&self, requested: Watts, excess_solar: Watts) -> f64 {
+        if requested <= Watts::ZERO {
+            return self.battery_efficiency.import;
+        }
+        let solar_share = excess_solar.min(requested) / requested;
+        (1.0 - solar_share)
+            .mul_add(self.battery_efficiency.import, solar_share * self.solar_charging_efficiency)
+    }
+
+    /// Penalize the residual energy for going into the hysteresis margin, proportionally to the depth.
+    fn hysteresis_penalty(&self, residual_energy: WattHours, duration: Hours) -> Mills {
+        let lower = WattHours::from(self.allowed_residual_energy.start) + self.soc_hysteresis;
+        let upper = WattHours::from(self.allowed_residual_energy.last) - self.soc_hysteresis;
+        let depth = (lower - residual_energy).max(WattHours::ZERO)
+            + (residual_energy - upper).max(WattHours::ZERO);
+        depth * self.soc_hysteresis_cost * duration.0
+    }
+}
+
+/// Interval start along with the working mode and power level.
+type Decision = (DateTime<Local>, (WorkingMode, Percentage));
+
+/// Simulated step along with its interval.
+type SimulatedStep = (Interval<DateTime<Local>>, Step);
+
+fn decisions_of(plan: &Plan) -> Vec<Decision> {
+    plan.schedule
+        .iter()
+        .map(|slot| (slot.interval.start(), (slot.value.1.working_mode, slot.value.1.power_level)))
+        .collect()
+}
+
+/// Index ranges of the consecutive same decisions.
+fn runs_of(decisions: &[Decision]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+    for (index, (_, decision)) in decisions.iter().enumerate() {
+        match runs.last_mut() {
+            Some(run) if decisions[run.start].1 == *decision => run.end = index + 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+    runs
+}
+
+/// Replace the plan steps with the simulated ones, keeping the prices.
+fn with_steps(plan: Plan, steps: Vec<SimulatedStep>, metrics: Metrics) -> Result<Plan> {
+    let mut schedule = Schedule::new();
+    schedule.extend_from_iter(
+        steps
+            .into_iter()
+            .zip(plan.schedule.iter())
+            .map(|((interval, step), slot)| (interval, (slot.value.0, step))),
+    )?;
+    Ok(Plan { metrics, schedule, ..plan })
+}
+
+fn total_metrics(steps: &[SimulatedStep]) -> Metrics {
+    let mut metrics = Metrics::ZERO;
+    for (_, step) in steps {
+        metrics += step.metrics;
+    }
+    metrics
+}
+
+/// Residual energy missing to the reserve by the end of the step.
+fn reserve_shortfall(reserve: WattHours<usize>, step: &Step) -> WattHours {
+    let shortfall: WattHours<usize> =
+        Quantity(reserve.0.saturating_sub(step.residual_energy_after.0));
+    shortfall.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use clap::{Args, Command, FromArgMatches};
+
+    use super::*;
+
+    /// Optimizer of an idle household, solved over the hourly export prices since 17:00,
+    /// with the import price above all of them.
+    ///
+    /// The battery holds 1 kWh, and it is allowed to idle, charge, and discharge.
+    fn solved(
+        extra_args: &[&str],
+        export_prices: &[f64],
+        allowed_residual_energy: RangeInclusive<WattHours<usize>>,
+        min_final_residual_energy: WattHours<usize>,
+    ) -> Result<Optimizer> {
+        let matches = battery::Args::augment_args(Command::new("fennec").no_binary_name(true))
+            .try_get_matches_from(
+                ["--battery-working-modes", "idle,charge,discharge"].iter().chain(extra_args),
+            )?;
+        let battery_args = battery::Args::from_arg_matches(&matches)?;
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 17, 0, 0).unwrap();
+        let mut prices = Schedule::new();
+        prices.extend_from_iter(export_prices.iter().zip(0..).map(|(export, index)| {
+            let start = start + TimeDelta::hours(index);
+            let price = energy::Flow { import: Quantity(0.6), export: Quantity(*export) };
+            (Interval::new(start, start + TimeDelta::hours(1)), price)
+        }))?;
+        let manifest = Manifest::new(
+            energy::Provider::FrankEnergieHourly,
+            start,
+            battery_args.clone(),
+            Percentage::ZERO,
+            None,
+        );
+        let mut optimizer = Optimizer::new(
+            energy::Profile::default(),
+            &battery_args,
+            Quantity(1000.0),
+            allowed_residual_energy,
+            min_final_residual_energy,
+            None,
+            manifest,
+        )
+        .with_quantum(NonZeroUsize::new(10).unwrap());
+        optimizer.solve(&prices, |_, _| ControlFlow::Continue(()))?;
+        Ok(optimizer)
+    }
+
+    #[test]
+    fn reserve_window_honoured() -> Result {
+        let solve = |extra_args: &[&str]| {
+            let optimizer = solved(
+                &[&["--battery-power-levels", "50,100"], extra_args].concat(),
+                &[0.5, 0.1],
+                RangeInclusive::from(Quantity(0)..=Quantity(1000)),
+                Quantity(0),
+            )?;
+            optimizer.solution_space().backtrack(Quantity(1000))
+        };
+
+        // Without the reserve, the battery drains at the peak export price:
+        let plan = solve(&[])?;
+        let step = plan.schedule.get(0).value.1;
+        assert_eq!(
+            (step.working_mode, step.power_level),
+            (WorkingMode::Discharge, Percentage::FULL)
+        );
+        assert!(step.residual_energy_after < Quantity(500), "{:?}", step.residual_energy_after);
+
+        // The reserve holds through 18:00, so the battery only discharges at half the power:
+        let plan = solve(&["--battery-reserve-soc-window", "17:00-19:00=50%"])?;
+        let step = plan.schedule.get(0).value.1;
+        assert_eq!(step.working_mode, WorkingMode::Discharge);
+        assert_eq!(step.power_level, Percentage::new(50));
+        for slot in plan.schedule.iter() {
+            assert!(slot.value.1.residual_energy_after >= Quantity(500));
+        }
+        assert_eq!(plan.metrics.reserve_shortfall, WattHours::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn smooth_ok() -> Result {
+        let optimizer = solved(
+            &[],
+            &[0.1, 0.2],
+            RangeInclusive::from(Quantity(0)..=Quantity(1000)),
+            Quantity(0),
+        )?;
+        let plan = optimizer.solution_space().backtrack(Quantity(1000))?;
+        let working_modes = |plan: &Plan| {
+            plan.schedule.iter().map(|slot| slot.value.1.working_mode).collect::<Vec<_>>()
+        };
+        assert_eq!(working_modes(&plan), [WorkingMode::Idle, WorkingMode::Discharge]);
+
+        // Idling through costs the 800 Wh sold at 0.2 ¤/kWh, minus the saved degradation:
+        let kept = optimizer.smooth(plan, Quantity(1000), Quantity(100.0))?;
+        assert_eq!(working_modes(&kept), [WorkingMode::Idle, WorkingMode::Discharge]);
+        let kept_loss = kept.metrics.losses.total();
+        let merged = optimizer.smooth(kept, Quantity(1000), Quantity(150.0))?;
+        assert_eq!(working_modes(&merged), [WorkingMode::Idle, WorkingMode::Idle]);
+        assert_eq!(merged.schedule.get(1).value.1.residual_energy_after, Quantity(1000));
+        assert!(merged.metrics.losses.total() > kept_loss);
+        Ok(())
+    }
+
+    #[test]
+    fn runs_of_ok() {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 17, 0, 0).unwrap();
+        let idle = (WorkingMode::Idle, Percentage::FULL);
+        let decisions: Vec<Decision> = [idle, idle, (WorkingMode::Charge, Percentage::FULL), idle]
+            .into_iter()
+            .zip(0..)
+            .map(|(decision, index)| (start + TimeDelta::hours(index), decision))
+            .collect();
+        assert_eq!(runs_of(&decisions), [0..2, 2..3, 3..4]);
+        assert!(runs_of(&[]).is_empty());
+    }
+
+    #[test]
+    fn compress_ok() -> Result {
+        let optimizer = solved(
+            &["--discharging-power-watts", "200"],
+            &[0.0, 0.2, 0.02],
+            RangeInclusive::from(Quantity(0)..=Quantity(1000)),
+            Quantity(0),
+        )?;
+        let start = optimizer.solution_space().get(0).interval.start();
+        let plan = Plan::hourly(
+            start,
+            [WorkingMode::Idle, WorkingMode::Discharge, WorkingMode::Idle]
+                .map(|working_mode| (energy::Flow::ZERO, Step::hourly(working_mode, 0))),
+        )?;
+
+        // Discharging later at the low price costs less than discharging earlier for free,
+        // or idling through:
+        let (plan, extra_loss) =
+            optimizer.compress(plan, Quantity(1000), TimeDelta::hours(3), 2)?;
+        let working_modes: Vec<_> =
+            plan.schedule.iter().map(|slot| slot.value.1.working_mode).collect();
+        assert_eq!(
+            working_modes,
+            [WorkingMode::Idle, WorkingMode::Discharge, WorkingMode::Discharge]
+        );
+        assert!(extra_loss > Mills::ZERO, "{extra_loss:?}");
+        assert!(extra_loss < Quantity(10.0), "{extra_loss:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn compress_infeasible() -> Result {
+        let optimizer = solved(
+            &[],
+            &[0.2, 0.1],
+            RangeInclusive::from(Quantity(0)..=Quantity(800)),
+            Quantity(800),
+        )?;
+        let start = optimizer.solution_space().get(0).interval.start();
+        let plan = Plan::hourly(
+            start,
+            [WorkingMode::Discharge, WorkingMode::Charge]
+                .map(|working_mode| (energy::Flow::ZERO, Step::hourly(working_mode, 0))),
+        )?;
+        let decisions = decisions_of(&plan);
+        assert!(optimizer.try_decisions(Quantity(800), &decisions).is_some());
+
+        // Not covering the solution space:
+        assert!(optimizer.try_decisions(Quantity(800), &decisions[..1]).is_none());
+
+        // Charging through goes above the allowed maximum, and discharging through misses
+        // the final residual energy, so none of the merges is feasible:
+        for working_mode in [WorkingMode::Charge, WorkingMode::Discharge] {
+            let merged: Vec<Decision> = decisions
+                .iter()
+                .map(|(start, _)| (*start, (working_mode, Percentage::FULL)))
+                .collect();
+            assert!(optimizer.try_decisions(Quantity(800), &merged).is_none());
+        }
+        let (plan, extra_loss) = optimizer.compress(plan, Quantity(800), TimeDelta::hours(2), 1)?;
+        assert_eq!(runs_of(&decisions_of(&plan)).len(), 2);
+        assert_eq!(extra_loss, Mills::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn flexibility_ok() -> Result {
+        // The hysteresis penalty makes the future loss depend on the residual energy:
+        let optimizer = solved(
+            &["--battery-soc-hysteresis", "20", "--battery-soc-hysteresis-cost", "1"],
+            &[0.0, 0.0],
+            RangeInclusive::from(Quantity(0)..=Quantity(1000)),
+            Quantity(0),
+        )?;
+        let flexibility = |initial_residual_energy| -> Result<Vec<Flexibility>> {
+            let plan = optimizer.solution_space().backtrack(initial_residual_energy)?;
+            for slot in plan.schedule.iter() {
+                assert_eq!(slot.value.1.working_mode, WorkingMode::Idle);
+            }
+            Ok(optimizer.flexibility(&plan))
+        };
+
+        // Nearly empty: the battery can only deliver what is stored, and storing more pays off:
+        let [first, last] = flexibility(Quantity(100))?[..] else {
+            bail!("expected two intervals");
+        };
+        assert!((first.upward.0 - 900.0 / 0.95).abs() < 0.01, "{:?}", first.upward);
+        assert!((first.downward.0 - 95.0).abs() < 0.01, "{:?}", first.downward);
+        let marginal_value = first.marginal_value.unwrap();
+        assert!(marginal_value > KilowattHourPrice::ZERO, "{marginal_value:?}");
+        assert!(last.marginal_value.is_none());
+
+        // Nearly full: the battery can only take the headroom, and storing more costs:
+        let [first, _] = flexibility(Quantity(900))?[..] else {
+            bail!("expected two intervals");
+        };
+        assert!((first.upward.0 - 100.0 / 0.95).abs() < 0.01, "{:?}", first.upward);
+        assert!((first.downward.0 - 800.0).abs() < 0.01, "{:?}", first.downward);
+        let marginal_value = first.marginal_value.unwrap();
+        assert!(marginal_value < KilowattHourPrice::ZERO, "{marginal_value:?}");
+        Ok(())
+    }
 }
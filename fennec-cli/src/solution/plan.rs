@@ -1,33 +1,232 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, NaiveDate};
+
 use crate::{
     Schedule,
+    battery::WorkingMode,
     energy,
+    ops::interval::Interval,
     prelude::*,
-    quantity::{energy::DecawattHours, price::KilowattHourPrice},
-    solution::{Metrics, Step},
+    quantity::{
+        Zero,
+        currency::Mills,
+        energy::{DecawattHours, WattHours},
+        price::KilowattHourPrice,
+        ratios::Percentage,
+    },
+    solution::{Manifest, Metrics, Optimizer, Step},
 };
 
 /// Schedule of working mode decisions along with cumulative metrics.
 #[must_use]
+#[derive(Clone)]
 pub struct Plan {
     /// Cumulative metrics of the entire plan.
     pub metrics: Metrics,
 
+    /// Market value of the energy left in the battery by the end of the plan.
+    pub residual_energy_value: Mills,
+
+    /// Number of the full-equivalent battery cycles over the plan.
+    pub n_cycles: f64,
+
+    /// What produced the plan.
+    pub manifest: Option<Arc<Manifest>>,
+
+    /// Estimated total loss reduction compared to sticking with the previous plan.
+    pub improvement: Option<Mills>,
+
     pub schedule: Schedule<(energy::Flow<KilowattHourPrice>, Step)>,
 }
 
 impl Plan {
-    /// /// Log the plan's headline metrics at `info` level.
-    pub fn trace_summary(&self, battery_design_capacity: DecawattHours) {
-        let n_cycles = self.metrics.internal_battery_flow.total_throughput()
-            / battery_design_capacity.rescale()
-            / 2.0;
+    /// Estimate [`Plan::residual_energy_value`] with [`energy::residual_energy_value`].
+    pub fn with_residual_energy_value(
+        mut self,
+        efficiency: energy::Flow<f64>,
+        min_residual_energy: WattHours,
+    ) -> Self {
+        let residual_energy = self
+            .schedule
+            .iter()
+            .last()
+            .map_or(WattHours::ZERO, |slot| slot.value.1.residual_energy_after.into());
+        self.residual_energy_value = energy::residual_energy_value(
+            self.schedule.iter().map(|slot| slot.value.0),
+            efficiency,
+            residual_energy,
+            min_residual_energy,
+        );
+        self
+    }
+
+    pub fn with_manifest(mut self, manifest: Arc<Manifest>) -> Self {
+        self.manifest = Some(manifest);
+        self
+    }
+
+    /// Estimate [`Plan::improvement`] by evaluating the previous plan under the same conditions.
+    pub fn with_improvement(
+        mut self,
+        optimizer: &Optimizer,
+        initial_residual_energy: WattHours<usize>,
+        previous_plan: &Self,
+    ) -> Self {
+        let previous_metrics = optimizer
+            .evaluate(initial_residual_energy, |timestamp| previous_plan.decision_at(timestamp));
+        let Some(previous_metrics) = previous_metrics else {
+            warn!("the previous plan is no longer feasible");
+            return self;
+        };
+        let previous_loss = Mills::from(previous_metrics.losses.total());
+        let new_loss = Mills::from(self.metrics.losses.total());
+        let improvement = previous_loss - new_loss;
+        info!(?previous_loss, ?new_loss, ?improvement, "compared to the previous plan");
+        self.improvement = Some(improvement);
+        self
+    }
+
+    /// Working mode and power level planned for the specified timestamp.
+    pub fn decision_at(&self, timestamp: DateTime<Local>) -> Option<(WorkingMode, Percentage)> {
+        self.schedule
+            .iter()
+            .find(|slot| slot.interval.start() <= timestamp && timestamp < slot.interval.end())
+            .map(|slot| (slot.value.1.working_mode, slot.value.1.power_level))
+    }
+
+    /// Whether the plan has any steps starting on the day.
+    pub fn has_prices_on(&self, on: NaiveDate) -> bool {
+        self.schedule.iter().any(|slot| slot.interval.start().date_naive() == on)
+    }
+
+    /// Grid bill savings of the steps starting before the day, compared to the baseline steps
+    /// over the same intervals.
+    ///
+    /// Unlike the losses, the savings leave out the battery wear and the solver penalties.
+    pub fn grid_savings_before(
+        &self,
+        baseline: &[(Interval<DateTime<Local>>, Step)],
+        day: NaiveDate,
+    ) -> Mills {
+        let baseline_loss: Mills = baseline
+            .iter()
+            .take_while(|(interval, _)| interval.start().date_naive() < day)
+            .map(|(_, step)| Mills::from(step.metrics.losses.grid))
+            .sum();
+        let planned_loss: Mills = self
+            .schedule
+            .iter()
+            .take_while(|slot| slot.interval.start().date_naive() < day)
+            .map(|slot| Mills::from(slot.value.1.metrics.losses.grid))
+            .sum();
+        baseline_loss - planned_loss
+    }
+
+    /// Count [`Plan::n_cycles`] with the specified cycle depth of the design capacity.
+    pub fn with_n_cycles(
+        mut self,
+        battery_design_capacity: DecawattHours,
+        cycle_depth: Percentage,
+    ) -> Self {
+        let cycle_energy: WattHours = battery_design_capacity.rescale() * cycle_depth;
+        self.n_cycles = self.metrics.internal_battery_flow.total_throughput() / cycle_energy / 2.0;
+        self
+    }
+
+    /// Log the plan's headline metrics at `info` level.
+    pub fn trace_summary(&self) {
         info!(
-            grid_loss = ?self.metrics.losses.grid,
-            battery.loss = ?self.metrics.losses.battery,
+            grid_loss = ?Mills::from(self.metrics.losses.grid),
+            battery.loss = ?Mills::from(self.metrics.losses.battery),
+            residual_energy_value = ?self.residual_energy_value,
             battery.charge = ?self.metrics.internal_battery_flow.import,
             battery.discharge = ?self.metrics.internal_battery_flow.export,
-            n_cycles,
+            n_cycles = self.n_cycles,
+            reserve_shortfall = ?self.metrics.reserve_shortfall,
             "plan summary",
         );
     }
 }
+
+#[cfg(test)]
+impl Plan {
+    /// Plan of the consecutive hourly slots since the start, with zero cumulative metrics.
+    pub fn hourly(
+        start: DateTime<Local>,
+        slots: impl IntoIterator<Item = (energy::Flow<KilowattHourPrice>, Step)>,
+    ) -> Result<Self> {
+        use chrono::TimeDelta;
+
+        let mut schedule = Schedule::new();
+        schedule.extend_from_iter(slots.into_iter().zip(0..).map(|(slot, index)| {
+            let start = start + TimeDelta::hours(index);
+            (Interval::new(start, start + TimeDelta::hours(1)), slot)
+        }))?;
+        Ok(Self {
+            metrics: Metrics::ZERO,
+            residual_energy_value: Mills::ZERO,
+            n_cycles: 0.0,
+            manifest: None,
+            improvement: None,
+            schedule,
+        })
+    }
+}
+
+#[cfg(test)]
+impl Step {
+    /// Hour-long step at the full power level, with zero balance and metrics.
+    pub const fn hourly(working_mode: WorkingMode, residual_energy_after: usize) -> Self {
+        use crate::quantity::Quantity;
+
+        Self {
+            duration: Quantity(1.0),
+            energy_balance: energy::Balance::ZERO,
+            working_mode,
+            power_level: Percentage::FULL,
+            residual_energy_after: Quantity(residual_energy_after),
+            metrics: Metrics::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::{quantity::Quantity, solution::Losses};
+
+    #[test]
+    fn grid_savings_before_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 22, 0, 0).unwrap();
+        let step = |working_mode, grid_loss| {
+            let mut step = Step::hourly(working_mode, 0);
+            step.metrics.losses = Losses::new(Quantity(grid_loss), Quantity(100.0));
+            step
+        };
+        let plan = Plan::hourly(
+            start,
+            [
+                (energy::Flow::ZERO, step(WorkingMode::Charge, 300.0)),
+                (energy::Flow::ZERO, step(WorkingMode::Discharge, -200.0)),
+                (energy::Flow::ZERO, step(WorkingMode::Discharge, -100.0)),
+            ],
+        )?;
+        let baseline = (0..3)
+            .map(|index| {
+                let start = start + TimeDelta::hours(index);
+                let interval = Interval::new(start, start + TimeDelta::hours(1));
+                (interval, step(WorkingMode::Idle, 100.0))
+            })
+            .collect::<Vec<_>>();
+
+        // The battery wear is left out, and only today's steps count:
+        assert_eq!(plan.grid_savings_before(&baseline, start.date_naive()), Quantity(0.0));
+        let tomorrow = start.date_naive().succ_opt().unwrap();
+        assert_eq!(plan.grid_savings_before(&baseline, tomorrow), Quantity(100.0));
+        assert_eq!(plan.grid_savings_before(&baseline, NaiveDate::MAX), Quantity(300.0));
+        Ok(())
+    }
+}
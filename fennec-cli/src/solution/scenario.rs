@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::{battery::WorkingMode, prelude::*, quantity::ratios::Percentage};
+
+/// Manually written working mode schedule, to be evaluated against the optimal plan.
+///
+/// The file is a JSON array of the decisions, each one applies till the next one:
+///
+/// ```json
+/// [
+///     {"since": "2026-04-08T13:00:00+02:00", "working_mode": "charge", "power_level": 50},
+///     {"since": "2026-04-08T18:00:00+02:00", "working_mode": "compensate"}
+/// ]
+/// ```
+#[must_use]
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub struct Scenario(Vec<Decision>);
+
+#[derive(Deserialize)]
+struct Decision {
+    since: DateTime<Local>,
+    working_mode: WorkingMode,
+
+    /// Power level of the forced working modes.
+    #[serde(default = "Decision::default_power_level")]
+    power_level: Percentage,
+}
+
+impl Decision {
+    const fn default_power_level() -> Percentage {
+        Percentage::FULL
+    }
+}
+
+impl Scenario {
+    #[instrument]
+    pub async fn read_from_file(path: &Path) -> Result<Self> {
+        let contents = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("failed to read the scenario `{}`", path.display()))?;
+        Self::from_json(&contents)
+    }
+
+    fn from_json(contents: &str) -> Result<Self> {
+        let this: Self = serde_json::from_str(contents).context("failed to parse the scenario")?;
+        ensure!(!this.0.is_empty(), "the scenario has no decisions");
+        ensure!(
+            this.0.is_sorted_by_key(|decision| decision.since),
+            "the scenario decisions must be in chronological order",
+        );
+        Ok(this)
+    }
+
+    /// Get the decision in effect at the specified time, if the scenario has already started.
+    pub fn decision_at(&self, time: DateTime<Local>) -> Option<(WorkingMode, Percentage)> {
+        let index = self.0.partition_point(|decision| decision.since <= time);
+        let decision = self.0.get(index.checked_sub(1)?)?;
+        Some((decision.working_mode, decision.power_level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn decision_at_ok() -> Result {
+        // language=json
+        let scenario = Scenario::from_json(
+            r#"[
+                {"since": "2026-04-08T13:00:00+02:00", "working_mode": "charge", "power_level": 50},
+                {"since": "2026-04-08T18:00:00+02:00", "working_mode": "compensate"}
+            ]"#,
+        )?;
+        let at =
+            |hour| scenario.decision_at(Local.with_ymd_and_hms(2026, 4, 8, hour, 0, 0).unwrap());
+        assert_eq!(at(12), None);
+        assert_eq!(at(13), Some((WorkingMode::Charge, Percentage::new(50))));
+        assert_eq!(at(17), Some((WorkingMode::Charge, Percentage::new(50))));
+        assert_eq!(at(18), Some((WorkingMode::Compensate, Percentage::FULL)));
+        assert_eq!(at(23), Some((WorkingMode::Compensate, Percentage::FULL)));
+        Ok(())
+    }
+
+    #[test]
+    fn unsorted_err() {
+        // language=json
+        let scenario = r#"[
+            {"since": "2026-04-08T18:00:00+02:00", "working_mode": "compensate"},
+            {"since": "2026-04-08T13:00:00+02:00", "working_mode": "idle"}
+        ]"#;
+        assert!(Scenario::from_json(scenario).is_err());
+    }
+}
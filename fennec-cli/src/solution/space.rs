@@ -3,12 +3,17 @@ use anyhow::Context;
 use crate::{
     Schedule,
     prelude::*,
-    quantity::energy::WattHours,
+    quantity::{Zero, currency::Mills, energy::WattHours},
     solution::{Plan, stage::Stage},
 };
 
 /// [Solution space][1] that associates a [`super::Solution`] with every time interval and energy level.
 ///
+/// The space is a flat table: one pre-allocated [`Stage`] per interval, indexed by the energy level.
+/// Solutions do not point to each other, instead, each step stores the energy level it leads to,
+/// which is the index into the next stage. So, the backtracking is a plain walk over the table,
+/// and the optimization does not allocate per state.
+///
 /// [1]: https://en.wikipedia.org/wiki/Dynamic_programming
 pub type Space = Schedule<Stage>;
 
@@ -30,6 +35,13 @@ impl Space {
             Ok((stage.price(), solution.step))
         })?;
 
-        Ok(Plan { metrics: metrics.context("the solution space is empty")?, schedule })
+        Ok(Plan {
+            metrics: metrics.context("the solution space is empty")?,
+            residual_energy_value: Mills::ZERO,
+            n_cycles: 0.0,
+            manifest: None,
+            improvement: None,
+            schedule,
+        })
     }
 }
@@ -1,8 +1,11 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    num::NonZeroUsize,
+    ops::{Index, IndexMut},
+};
 
 use crate::{
     energy,
-    quantity::{energy::WattHours, price::KilowattHourPrice},
+    quantity::{Quantity, energy::WattHours, price::KilowattHourPrice},
     solution::Solution,
 };
 
@@ -12,32 +15,106 @@ use crate::{
 pub struct Stage {
     price: energy::Flow<KilowattHourPrice>,
 
-    /// Mapping from residual energy to an optional [`Solution`].
+    /// Day-ahead price to fall back to, once the override is gone.
+    base_price: energy::Flow<KilowattHourPrice>,
+
+    /// Energy quantization step.
+    quantum: NonZeroUsize,
+
+    /// Actual battery capacity, the last energy level.
+    battery_capacity: WattHours<usize>,
+
+    /// Mapping from quantized residual energy to an optional [`Solution`].
     solutions: Vec<Option<Solution>>,
 }
 
 impl Index<WattHours<usize>> for Stage {
     type Output = Option<Solution>;
 
-    /// Get a reference to the solution at the specified energy level.
+    /// Get a reference to the solution at the energy level nearest to the residual energy.
     fn index(&self, residual_energy: WattHours<usize>) -> &Self::Output {
-        &self.solutions[residual_energy.0]
+        &self.solutions[self.level_of(residual_energy)]
     }
 }
 
 impl IndexMut<WattHours<usize>> for Stage {
-    /// Get a mutable reference to the solution at the specified energy level.
+    /// Get a mutable reference to the solution at the energy level nearest to the residual energy.
     fn index_mut(&mut self, residual_energy: WattHours<usize>) -> &mut Self::Output {
-        &mut self.solutions[residual_energy.0]
+        let level = self.level_of(residual_energy);
+        &mut self.solutions[level]
     }
 }
 
 impl Stage {
-    pub fn new(price: energy::Flow<KilowattHourPrice>, battery_capacity: WattHours<usize>) -> Self {
-        Self { price, solutions: vec![None; battery_capacity.0 + 1] }
+    pub fn new(
+        price: energy::Flow<KilowattHourPrice>,
+        battery_capacity: WattHours<usize>,
+        quantum: NonZeroUsize,
+    ) -> Self {
+        let mut this =
+            Self { price, base_price: price, quantum, battery_capacity, solutions: Vec::new() };
+        this.solutions = vec![None; this.level_of(battery_capacity) + 1];
+        this
     }
 
     pub const fn price(&self) -> energy::Flow<KilowattHourPrice> {
         self.price
     }
+
+    /// Override the price, or restore the base price if [`None`].
+    ///
+    /// Returns [`true`] if the price has changed.
+    pub fn override_price(&mut self, price: Option<energy::Flow<KilowattHourPrice>>) -> bool {
+        let price = price.unwrap_or(self.base_price);
+        let has_changed = self.price != price;
+        self.price = price;
+        has_changed
+    }
+
+    /// Forget all the partial solutions.
+    pub fn clear(&mut self) {
+        self.solutions.fill(None);
+    }
+
+    /// Residual energy of every quantized energy level.
+    pub fn energy_levels(&self) -> impl Iterator<Item = WattHours<usize>> + use<> {
+        let (quantum, battery_capacity) = (self.quantum.get(), self.battery_capacity);
+        (0..self.solutions.len())
+            .map(move |level| Quantity((level * quantum).min(battery_capacity.0)))
+    }
+
+    /// Index of the energy level nearest to the residual energy.
+    pub const fn level_of(&self, residual_energy: WattHours<usize>) -> usize {
+        let quantum = self.quantum.get();
+        (residual_energy.0 + quantum / 2) / quantum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quantity::Zero;
+
+    #[test]
+    fn energy_levels_ok() {
+        let stage = Stage::new(energy::Flow::ZERO, Quantity(1005), NonZeroUsize::new(10).unwrap());
+        let levels: Vec<_> = stage.energy_levels().collect();
+        assert_eq!(levels.len(), 102);
+        assert_eq!(levels[1], Quantity(10));
+        assert_eq!(levels[101], Quantity(1005));
+        assert!(levels.iter().enumerate().all(|(level, energy)| stage.level_of(*energy) == level));
+        assert_eq!(stage.level_of(Quantity(1004)), 100);
+    }
+
+    #[test]
+    fn override_price_ok() {
+        let base_price = energy::Flow { import: Quantity(0.25), export: Quantity(0.125) };
+        let real_time_price = energy::Flow { import: Quantity(0.5), export: Quantity(0.25) };
+        let mut stage = Stage::new(base_price, Quantity(1000), NonZeroUsize::new(10).unwrap());
+        assert!(stage.override_price(Some(real_time_price)));
+        assert!(!stage.override_price(Some(real_time_price)));
+        assert_eq!(stage.price(), real_time_price);
+        assert!(stage.override_price(None));
+        assert_eq!(stage.price(), base_price);
+    }
 }
@@ -1,13 +1,13 @@
 use crate::{
     battery::WorkingMode,
     energy,
-    quantity::{energy::WattHours, time::Hours},
+    quantity::{Quantity, energy::WattHours, power::Watts, ratios::Percentage, time::Hours},
     solution,
 };
 
 /// Per-interval decision, one step of a [`super::Plan`].
 #[must_use]
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, serde::Serialize)]
 pub struct Step {
     /// Calculated time interval duration.
     ///
@@ -21,9 +21,27 @@ pub struct Step {
     /// Battery working mode taken by the optimizer.
     pub working_mode: WorkingMode,
 
+    /// Power level of the working mode relative to the power limits.
+    ///
+    /// Always 100% for the non-forced working modes.
+    pub power_level: Percentage,
+
     /// Target state at the next stage.
     pub residual_energy_after: WattHours<usize>,
 
     /// Stage cost.
     pub metrics: solution::Metrics,
 }
+
+impl Step {
+    /// Mean external battery power over the step.
+    ///
+    /// It is lower than the working mode power, when the battery is expected to get full
+    /// or empty halfway through the step, and then idle for the rest of it.
+    pub fn mean_battery_power(&self) -> energy::Flow<Watts> {
+        energy::Flow {
+            import: Quantity(self.energy_balance.battery.import.0 / self.duration.0),
+            export: Quantity(self.energy_balance.battery.export.0 / self.duration.0),
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Physical invariants of the produced plans, to catch regressions in the battery model.
+
+use std::{num::NonZeroUsize, range::RangeInclusive};
+
+use crate::{
+    prelude::*,
+    quantity::{Quantity, Zero, energy::WattHours},
+    solution::{Losses, Plan, Step},
+};
+
+impl Plan {
+    /// Check the plan against the physical invariants:
+    ///
+    /// - The intervals follow each other without gaps.
+    /// - The energy flows are non-negative.
+    /// - The residual energy changes exactly by the battery internal flow, up to the truncation
+    ///   and the energy quantum the steps have been solved at.
+    /// - The residual energy never leaves the allowed range further than it started.
+    /// - The step losses add up to the plan losses.
+    pub fn validate(
+        &self,
+        initial_residual_energy: WattHours<usize>,
+        allowed_residual_energy: RangeInclusive<WattHours<usize>>,
+        quantum: NonZeroUsize,
+    ) -> Result {
+        // Residual energy is truncated to the whole watt-hours, and the steps start off the nearest
+        // energy level:
+        let half_quantum: WattHours<usize> = Quantity(quantum.get() / 2);
+        let tolerance = WattHours::from(half_quantum) + Quantity(1.0 + 1e-6);
+
+        let mut residual_energy = initial_residual_energy;
+        let mut previous_end = None;
+        let mut losses = Losses::ZERO;
+
+        for slot in self.schedule.iter() {
+            ensure!(slot.interval.start() < slot.interval.end(), "empty {:?}", slot.interval);
+            if let Some(previous_end) = previous_end {
+                ensure!(slot.interval.start() == previous_end, "gap before {:?}", slot.interval);
+            }
+            previous_end = Some(slot.interval.end());
+
+            let step = &slot.value.1;
+            validate_step(step, residual_energy, allowed_residual_energy, tolerance)
+                .with_context(|| format!("invalid step at {:?}", slot.interval))?;
+            residual_energy = step.residual_energy_after;
+            losses += step.metrics.losses;
+        }
+
+        ensure!(
+            losses.grid == self.metrics.losses.grid
+                && losses.battery == self.metrics.losses.battery,
+            "step losses add up to {:?}, but the plan losses are {:?}",
+            losses.total(),
+            self.metrics.losses.total(),
+        );
+        Ok(())
+    }
+}
+
+fn validate_step(
+    step: &Step,
+    residual_energy: WattHours<usize>,
+    allowed_residual_energy: RangeInclusive<WattHours<usize>>,
+    tolerance: WattHours,
+) -> Result {
+    let balance = step.energy_balance;
+    let internal_flow = step.metrics.internal_battery_flow;
+    for (name, flow) in [
+        ("grid import", balance.grid.import),
+        ("grid export", balance.grid.export),
+        ("battery import", balance.battery.import),
+        ("battery export", balance.battery.export),
+        ("internal battery import", internal_flow.import),
+        ("internal battery export", internal_flow.export),
+    ] {
+        ensure!(flow >= WattHours::ZERO, "negative {name}: {flow:?}");
+    }
+
+    let actual_change =
+        WattHours::from(step.residual_energy_after) - WattHours::from(residual_energy);
+    let expected_change = internal_flow.import - internal_flow.export;
+    ensure!(
+        (actual_change - expected_change).abs() <= tolerance,
+        "residual energy changed by {actual_change:?}, but the net flow is {expected_change:?}",
+    );
+
+    let after = step.residual_energy_after;
+    ensure!(
+        after >= allowed_residual_energy.start.min(residual_energy)
+            && after <= allowed_residual_energy.last.max(residual_energy),
+        "residual energy {after:?} left the allowed range {allowed_residual_energy:?}",
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use super::*;
+    use crate::{battery::WorkingMode, energy, quantity::currency::Mills, solution::Metrics};
+
+    fn step(residual_energy_after: usize, import: f64, loss: f64) -> Step {
+        Step {
+            energy_balance: energy::Balance {
+                grid: energy::Flow { import: Quantity(import), export: Quantity(0.0) },
+                battery: energy::Flow { import: Quantity(import), export: Quantity(0.0) },
+            },
+            metrics: Metrics {
+                internal_battery_flow: energy::Flow {
+                    import: Quantity(import),
+                    export: Quantity(0.0),
+                },
+                losses: Losses::new(Mills::new(loss), Mills::ZERO),
+                reserve_shortfall: WattHours::ZERO,
+            },
+            ..Step::hourly(WorkingMode::Charge, residual_energy_after)
+        }
+    }
+
+    fn plan(steps: [Step; 2]) -> Result<Plan> {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let plan = Plan::hourly(start, steps.map(|step| (energy::Flow::ZERO, step)))?;
+        Ok(Plan {
+            metrics: Metrics {
+                internal_battery_flow: energy::Flow::ZERO,
+                losses: Losses::new(Mills::new(3.0), Mills::ZERO),
+                reserve_shortfall: WattHours::ZERO,
+            },
+            ..plan
+        })
+    }
+
+    fn validate(plan: &Plan, max_residual_energy: usize) -> Result {
+        let allowed_residual_energy = Quantity(100)..=Quantity(max_residual_energy);
+        plan.validate(Quantity(500), allowed_residual_energy.into(), NonZeroUsize::MIN)
+    }
+
+    #[test]
+    fn validate_ok() -> Result {
+        let plan = plan([step(600, 100.4, 1.0), step(800, 200.0, 2.0)])?;
+        validate(&plan, 900)
+    }
+
+    #[test]
+    fn validate_energy_not_conserved() -> Result {
+        let plan = plan([step(600, 100.0, 1.0), step(850, 200.0, 2.0)])?;
+        assert!(validate(&plan, 900).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_out_of_range() -> Result {
+        let plan = plan([step(600, 100.0, 1.0), step(800, 200.0, 2.0)])?;
+        assert!(validate(&plan, 700).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_losses_do_not_add_up() -> Result {
+        let plan = plan([step(600, 100.0, 1.0), step(800, 200.0, 1.0)])?;
+        assert!(validate(&plan, 900).is_err());
+        Ok(())
+    }
+}
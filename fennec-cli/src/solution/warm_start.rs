@@ -0,0 +1,93 @@
+use std::range::RangeInclusive;
+
+use chrono::{DateTime, Local};
+
+use crate::{
+    battery::WorkingMode,
+    quantity::{Quantity, energy::WattHours, ratios::Percentage},
+    solution::Plan,
+};
+
+/// Previous plan to warm-start the optimizer with.
+///
+/// The optimizer only solves the energy levels within the margin around the previous trajectory,
+/// and prefers the previous decisions over the equally good ones – so that successive runs
+/// solve faster and do not flip the schedule back and forth.
+#[must_use]
+pub struct WarmStart {
+    steps: Vec<PreviousStep>,
+    margin: WattHours<usize>,
+}
+
+struct PreviousStep {
+    start: DateTime<Local>,
+
+    /// Planned residual energy by the start of the interval, unknown for the first one.
+    residual_energy_before: Option<WattHours<usize>>,
+
+    decision: (WorkingMode, Percentage),
+}
+
+impl WarmStart {
+    pub fn new(previous_plan: &Plan, margin: WattHours<usize>) -> Self {
+        let mut residual_energy = None;
+        let steps = previous_plan
+            .schedule
+            .iter()
+            .map(|slot| {
+                let step = &slot.value.1;
+                PreviousStep {
+                    start: slot.interval.start(),
+                    residual_energy_before: residual_energy.replace(step.residual_energy_after),
+                    decision: (step.working_mode, step.power_level),
+                }
+            })
+            .collect();
+        Self { steps, margin }
+    }
+
+    /// Previous decision for the interval starting at the timestamp.
+    pub fn decision_at(&self, start: DateTime<Local>) -> Option<(WorkingMode, Percentage)> {
+        self.find(start).map(|step| step.decision)
+    }
+
+    /// Residual energy range to solve the interval starting at the timestamp for,
+    /// or [`None`] if the previous plan does not constrain it.
+    pub fn corridor_at(&self, start: DateTime<Local>) -> Option<RangeInclusive<WattHours<usize>>> {
+        let residual_energy = self.find(start)?.residual_energy_before?;
+        Some(RangeInclusive {
+            start: Quantity(residual_energy.0.saturating_sub(self.margin.0)),
+            last: Quantity(residual_energy.0 + self.margin.0),
+        })
+    }
+
+    fn find(&self, start: DateTime<Local>) -> Option<&PreviousStep> {
+        self.steps.iter().find(|step| step.start == start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeDelta, TimeZone};
+
+    use super::*;
+    use crate::{energy, prelude::*, quantity::Zero, solution::Step};
+
+    #[test]
+    fn corridor_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let plan = Plan::hourly(
+            start,
+            [300, 700].map(|after| (energy::Flow::ZERO, Step::hourly(WorkingMode::Charge, after))),
+        )?;
+
+        let warm_start = WarmStart::new(&plan, Quantity(500));
+        assert!(warm_start.corridor_at(start).is_none());
+        let corridor = warm_start.corridor_at(start + TimeDelta::hours(1)).unwrap();
+        assert_eq!(corridor.start, Quantity(0));
+        assert_eq!(corridor.last, Quantity(800));
+        assert!(warm_start.corridor_at(start + TimeDelta::hours(2)).is_none());
+        assert_eq!(warm_start.decision_at(start), Some((WorkingMode::Charge, Percentage::FULL)));
+        Ok(())
+    }
+}
@@ -3,20 +3,51 @@ mod partials;
 mod plotters;
 mod working_mode;
 
-use std::{net::IpAddr, sync::Arc};
+use std::sync::Arc;
 
-use axum::{Router, routing::get};
+use axum::{Router, middleware, routing::get};
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
 
-use crate::{engine::State, prelude::*};
+use crate::{cli::BindArgs, engine::State, prelude::*};
 
-pub async fn serve(address: IpAddr, port: u16, state: Arc<RwLock<State>>) -> Result {
-    info!(%address, port, "serving web UI…");
+pub async fn serve(
+    args: BindArgs,
+    state: Arc<RwLock<State>>,
+    shutdown: CancellationToken,
+) -> Result {
+    info!(address = %args.address, port = args.port, "serving web UI…");
+    let mut api = Router::new()
+        .route("/api/plan", get(handlers::api::get_plan))
+        .route("/api/forecast", get(handlers::api::get_forecast))
+        .route("/api/flexibility", get(handlers::api::get_flexibility))
+        .route("/api/battery-state", get(handlers::api::get_battery_state))
+        .route("/api/battery-history", get(handlers::api::get_battery_history))
+        .route("/api/battery-efficiency", get(handlers::api::get_battery_efficiency))
+        .route("/api/battery-health", get(handlers::api::get_battery_health))
+        .route("/api/battery-throughput", get(handlers::api::get_battery_throughput))
+        .route("/api/sources", get(handlers::api::get_sources))
+        .route("/api/daily-energy", get(handlers::api::get_daily_energy))
+        .route("/api/residual-energy", get(handlers::api::get_residual_energy))
+        .route("/api/accuracy", get(handlers::api::get_accuracy))
+        .route("/metrics", get(handlers::metrics::get))
+        .route(handlers::calendar::PATH, get(handlers::calendar::get));
+    if let Some(api_token) = args.api_token {
+        api = api.route_layer(middleware::from_fn_with_state(
+            Arc::<str>::from(api_token),
+            handlers::api::authorize,
+        ));
+    }
     let app = Router::new()
         .route("/", get(handlers::index::get))
         .route(handlers::energy_profile::PATH, get(handlers::energy_profile::get))
+        .merge(api)
         .route("/readiness", get(handlers::readiness::get))
+        .route("/health", get(handlers::health::get))
         .with_state(state);
-    let listener = tokio::net::TcpListener::bind((address, port)).await?;
-    axum::serve(listener, app).await.context("the web application has failed")
+    let listener = tokio::net::TcpListener::bind((args.address, args.port)).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown.cancelled_owned())
+        .await
+        .context("the web application has failed")
 }
@@ -1,3 +1,7 @@
+pub mod api;
+pub mod calendar;
 pub mod energy_profile;
+pub mod health;
 pub mod index;
+pub mod metrics;
 pub mod readiness;
@@ -0,0 +1,351 @@
+//! JSON API for those who prefer their own dashboards.
+
+use std::sync::Arc;
+
+use axum::{
+    Json,
+    extract::{Query, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Local, TimeDelta};
+use http::{StatusCode, header};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::{
+    battery::{CapacityTrend, Reading, WorkingMode, YearToDate},
+    energy,
+    engine,
+    ops::health::Sources,
+    prelude::*,
+    quantity::{currency::Mills, energy::WattHours, price::KilowattHourPrice, ratios::Percentage},
+    solution::{
+        DailyEnergy,
+        Execution,
+        Flexibility,
+        Forecast,
+        HourlyAccuracy,
+        JournalEntry,
+        Manifest,
+    },
+};
+
+#[derive(Serialize)]
+pub struct Plan {
+    /// Estimated losses till the end of the forecast period, negative losses are profits.
+    losses: Losses,
+
+    /// Market value of the energy left in the battery by the end of the plan.
+    residual_energy_value: Mills,
+
+    /// Full-equivalent battery cycles over the plan, as configured to match the BMS.
+    n_cycles: f64,
+
+    /// Estimated total loss reduction compared to sticking with the previous plan.
+    improvement: Option<Mills>,
+
+    manifest: Option<Arc<Manifest>>,
+
+    steps: Vec<Step>,
+}
+
+#[derive(Serialize)]
+struct Losses {
+    grid: Mills,
+    battery: Mills,
+    total: Mills,
+}
+
+#[derive(Serialize)]
+struct Step {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    energy_price: energy::Flow<KilowattHourPrice>,
+
+    /// Grid operator transport cost, included in the import price.
+    transport_cost: KilowattHourPrice,
+
+    working_mode: WorkingMode,
+    power_level: Percentage,
+    energy_balance: energy::Balance<WattHours>,
+    residual_energy_after: WattHours<usize>,
+    losses: Losses,
+}
+
+impl From<crate::solution::Losses> for Losses {
+    fn from(losses: crate::solution::Losses) -> Self {
+        Self {
+            grid: losses.grid.into(),
+            battery: losses.battery.into(),
+            total: losses.total().into(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct BatteryEfficiency {
+    efficiency: energy::Flow<f64>,
+
+    /// Mean absolute deviation of the samples.
+    deviation: energy::Flow<f64>,
+
+    by_temperature: Vec<TemperatureBandEfficiency>,
+}
+
+#[derive(Serialize)]
+struct TemperatureBandEfficiency {
+    /// Lower bound of the temperature band in degrees Celsius.
+    min_temperature: i16,
+
+    efficiency: energy::Flow<f64>,
+    deviation: energy::Flow<f64>,
+}
+
+#[derive(Serialize)]
+pub struct ResidualEnergyRecord {
+    timestamp: DateTime<Local>,
+    residual_energy: WattHours,
+}
+
+#[derive(Deserialize)]
+pub struct HistoryQuery {
+    /// Number of the recent days to look back.
+    #[serde(default = "HistoryQuery::default_days")]
+    days: u16,
+}
+
+impl HistoryQuery {
+    const fn default_days() -> u16 {
+        7
+    }
+
+    fn since(&self) -> DateTime<Local> {
+        Local::now() - TimeDelta::days(self.days.into())
+    }
+}
+
+#[derive(Serialize)]
+pub struct BatteryRecord {
+    timestamp: DateTime<Local>,
+    state_of_charge: Percentage,
+}
+
+#[derive(Deserialize)]
+struct TokenQuery {
+    token: Option<String>,
+}
+
+/// Require the bearer token on the API routes.
+///
+/// The token is also accepted in the `token` query parameter, since the calendar apps
+/// cannot send the headers.
+pub async fn authorize(
+    State(api_token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let bearer_token = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(ToOwned::to_owned);
+    let token = bearer_token.or_else(|| {
+        Query::<TokenQuery>::try_from_uri(request.uri()).ok().and_then(|query| query.0.token)
+    });
+    if token.is_some_and(|token| is_token_valid(&token, &api_token)) {
+        Ok(next.run(request).await)
+    } else {
+        warn!("unauthorized");
+        Err(StatusCode::UNAUTHORIZED)
+    }
+}
+
+/// Compare the tokens in constant time, so that the response time does not leak
+/// how much of the token matches.
+fn is_token_valid(token: &str, api_token: &str) -> bool {
+    token.len() == api_token.len()
+        && token
+            .bytes()
+            .zip(api_token.bytes())
+            .fold(0, |difference, (lhs, rhs)| difference | (lhs ^ rhs))
+            == 0
+}
+
+/// Current plan, or `null` if the engine has not come up with one yet.
+#[instrument(skip_all)]
+pub async fn get_plan(State(state): State<Arc<RwLock<engine::State>>>) -> Json<Option<Plan>> {
+    debug!("access");
+    let state = state.read().await;
+    let plan = state.plan.as_ref().map(|plan| Plan {
+        losses: plan.metrics.losses.into(),
+        residual_energy_value: plan.residual_energy_value,
+        n_cycles: plan.n_cycles,
+        improvement: plan.improvement,
+        manifest: plan.manifest.clone(),
+        steps: plan
+            .schedule
+            .iter()
+            .map(|slot| {
+                let (energy_price, step) = slot.value;
+                Step {
+                    start: slot.interval.start(),
+                    end: slot.interval.end(),
+                    energy_price: *energy_price,
+                    transport_cost: state.transport_costs.at(slot.interval.start()),
+                    working_mode: step.working_mode,
+                    power_level: step.power_level,
+                    energy_balance: step.energy_balance,
+                    residual_energy_after: step.residual_energy_after,
+                    losses: step.metrics.losses.into(),
+                }
+            })
+            .collect(),
+    });
+    drop(state);
+    Json(plan)
+}
+
+/// Planned residual energy curve of the current plan, or `null` if there is no plan yet.
+///
+/// Overlay it with the residual energy history to see how well the plans hold up.
+#[instrument(skip_all)]
+pub async fn get_forecast(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Option<Forecast>> {
+    debug!("access");
+    Json(state.read().await.forecast.clone())
+}
+
+/// Most recent battery reading, or `null` if the engine has not read the battery yet.
+///
+/// This never touches the inverter, so it is safe to poll by any number of consumers.
+#[instrument(skip_all)]
+pub async fn get_battery_state(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Option<Reading>> {
+    debug!("access");
+    Json(state.read().await.battery_reading)
+}
+
+/// Battery state-of-charge over the last day.
+#[instrument(skip_all)]
+pub async fn get_battery_history(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Vec<BatteryRecord>> {
+    debug!("access");
+    let records = state
+        .read()
+        .await
+        .battery_history
+        .iter()
+        .map(|(timestamp, state_of_charge)| BatteryRecord {
+            timestamp: *timestamp,
+            state_of_charge: *state_of_charge,
+        })
+        .collect();
+    Json(records)
+}
+
+/// Long-term capacity fade, or `null` if there is not enough state-of-health history yet.
+#[instrument(skip_all)]
+pub async fn get_battery_health(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Option<CapacityTrend>> {
+    debug!("access");
+    Json(state.read().await.battery_capacity_trend)
+}
+
+/// Calendar year-to-date battery throughput, or `null` if the engine has not read the battery yet.
+#[instrument(skip_all)]
+pub async fn get_battery_throughput(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Option<YearToDate>> {
+    debug!("access");
+    Json(state.read().await.battery_throughput)
+}
+
+/// Experimental: flexibility the battery could offer on top of the current plan, per interval.
+#[instrument(skip_all)]
+pub async fn get_flexibility(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<Vec<Flexibility>> {
+    debug!("access");
+    Json(state.read().await.flexibility.clone())
+}
+
+/// Health of the individual measurement sources.
+#[instrument(skip_all)]
+pub async fn get_sources(State(state): State<Arc<RwLock<engine::State>>>) -> Json<Sources> {
+    debug!("access");
+    Json(state.read().await.sources.clone())
+}
+
+/// Learned battery efficiency, overall and per temperature band.
+#[instrument(skip_all)]
+pub async fn get_battery_efficiency(
+    State(state): State<Arc<RwLock<engine::State>>>,
+) -> Json<BatteryEfficiency> {
+    debug!("access");
+    let battery = &state.read().await.energy_profile.battery;
+    Json(BatteryEfficiency {
+        efficiency: battery.efficiency,
+        deviation: battery.efficiency_deviation,
+        by_temperature: battery
+            .efficiency_by_temperature
+            .iter()
+            .map(|(min_temperature, estimate)| TemperatureBandEfficiency {
+                min_temperature: *min_temperature,
+                efficiency: estimate.efficiency,
+                deviation: estimate.deviation,
+            })
+            .collect(),
+    })
+}
+
+/// Measured energy flows per day, read from the execution journal.
+#[instrument(skip_all, fields(days = query.days))]
+pub async fn get_daily_energy(
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<DailyEnergy>>, StatusCode> {
+    debug!("access");
+    let entries = read_journal(&query).await?;
+    Ok(Json(DailyEnergy::aggregate(entries)))
+}
+
+/// Measured residual energy by the end of each journaled interval.
+#[instrument(skip_all, fields(days = query.days))]
+pub async fn get_residual_energy(
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<ResidualEnergyRecord>>, StatusCode> {
+    debug!("access");
+    let records = read_journal(&query)
+        .await?
+        .into_iter()
+        .filter_map(|entry| {
+            Some(ResidualEnergyRecord {
+                timestamp: entry.interval.end(),
+                residual_energy: entry.actual_residual_energy_after?,
+            })
+        })
+        .collect();
+    Ok(Json(records))
+}
+
+/// Plan-versus-actual errors per local hour of day, read from the execution journal.
+#[instrument(skip_all, fields(days = query.days))]
+pub async fn get_accuracy(
+    Query(query): Query<HistoryQuery>,
+) -> Result<Json<Vec<HourlyAccuracy>>, StatusCode> {
+    debug!("access");
+    let entries = read_journal(&query).await?;
+    Ok(Json(HourlyAccuracy::aggregate(entries)))
+}
+
+async fn read_journal(query: &HistoryQuery) -> Result<Vec<JournalEntry>, StatusCode> {
+    Execution::read_journal(query.since()).await.map_err(|error| {
+        error!("failed to read the journal: {error:#}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
@@ -0,0 +1,122 @@
+//! [iCalendar][1] feed of the planned schedule, to subscribe to from a phone calendar.
+//!
+//! [1]: https://datatracker.ietf.org/doc/html/rfc5545
+
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse};
+use chrono::{DateTime, TimeZone, Utc};
+use http::header;
+use itertools::Itertools;
+use tokio::sync::RwLock;
+
+use crate::{
+    battery::WorkingMode,
+    engine,
+    prelude::*,
+    quantity::currency::{Currency, Mills},
+    solution::Plan,
+};
+
+pub const PATH: &str = "/calendar.ics";
+
+#[instrument(skip_all)]
+pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> impl IntoResponse {
+    debug!("access");
+    let state = state.read().await;
+    let calendar = render(state.plan.as_ref(), &state.currency, Utc::now());
+    drop(state);
+    ([(header::CONTENT_TYPE, "text/calendar; charset=utf-8")], calendar)
+}
+
+/// Render the plan as a calendar with one event per run of the same working mode.
+///
+/// The idle runs are left out, as they would only clutter the calendar.
+fn render(plan: Option<&Plan>, currency: &Currency, now: DateTime<Utc>) -> String {
+    let mut calendar = String::new();
+    let mut line = |line: &str| {
+        calendar.push_str(line);
+        calendar.push_str("\r\n");
+    };
+    line("BEGIN:VCALENDAR");
+    line("VERSION:2.0");
+    line("PRODID:-//Fennec//Plan//EN");
+    line("X-WR-CALNAME:Fennec");
+    let runs = plan.map(|plan| {
+        plan.schedule.iter().chunk_by(|slot| (slot.value.1.working_mode, slot.value.1.power_level))
+    });
+    for ((working_mode, power_level), mut slots) in runs.iter().flatten() {
+        if working_mode == WorkingMode::Idle {
+            continue;
+        }
+        let first_slot = slots.next().unwrap();
+        let (end, loss) = slots.fold(
+            (first_slot.interval.end(), Mills::from(first_slot.value.1.metrics.losses.total())),
+            |(_, loss), slot| {
+                (slot.interval.end(), loss + Mills::from(slot.value.1.metrics.losses.total()))
+            },
+        );
+        let start = first_slot.interval.start();
+        let summary = if working_mode.is_forced() {
+            format!("{working_mode} at {power_level}")
+        } else {
+            working_mode.to_string()
+        };
+        line("BEGIN:VEVENT");
+        line(&format!("UID:{}@fennec", start.timestamp()));
+        line(&format!("DTSTAMP:{}", format_timestamp(&now)));
+        line(&format!("DTSTART:{}", format_timestamp(&start)));
+        line(&format!("DTEND:{}", format_timestamp(&end)));
+        line(&format!("SUMMARY:🔋 {summary}"));
+        line(&format!("DESCRIPTION:Expected loss: {}", currency.format(loss)));
+        line("TRANSP:TRANSPARENT");
+        line("END:VEVENT");
+    }
+    line("END:VCALENDAR");
+    calendar
+}
+
+fn format_timestamp<Tz: TimeZone>(timestamp: &DateTime<Tz>) -> String {
+    timestamp.with_timezone(&Utc).format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeDelta};
+
+    use super::*;
+    use crate::{
+        energy,
+        quantity::{Quantity, Zero},
+        solution::{Losses, Metrics, Step},
+    };
+
+    #[test]
+    fn render_ok() -> Result {
+        let start = Local.with_ymd_and_hms(2026, 4, 8, 13, 0, 0).unwrap();
+        let working_modes = [WorkingMode::Charge, WorkingMode::Charge, WorkingMode::Idle];
+        let plan = Plan::hourly(
+            start,
+            working_modes.map(|working_mode| {
+                let step = Step {
+                    metrics: Metrics {
+                        losses: Losses::new(Quantity(50.0), Quantity(10.0)),
+                        ..Metrics::ZERO
+                    },
+                    ..Step::hourly(working_mode, 0)
+                };
+                (energy::Flow::ZERO, step)
+            }),
+        )?;
+        let currency = Currency { symbol: "€".to_owned(), precision: 2 };
+
+        let calendar = render(Some(&plan), &currency, start.with_timezone(&Utc));
+        assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 1);
+        let end = start + TimeDelta::hours(2);
+        assert!(calendar.contains(&format!("DTSTART:{}\r\n", format_timestamp(&start))));
+        assert!(calendar.contains(&format!("DTEND:{}\r\n", format_timestamp(&end))));
+        assert!(calendar.contains("SUMMARY:🔋 Charge at 100 %\r\n"));
+        assert!(calendar.contains("DESCRIPTION:Expected loss: €0.12\r\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,20 @@
+use std::sync::Arc;
+
+use axum::{extract::State, response::IntoResponse};
+use http::{StatusCode, header};
+use tokio::sync::RwLock;
+
+use crate::{engine, prelude::*};
+
+/// Report whether the latest engine iteration has succeeded.
+#[instrument(skip_all)]
+pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> impl IntoResponse {
+    let n_consecutive_failures = state.read().await.n_consecutive_failures;
+    debug!(n_consecutive_failures, "check");
+    let status_code = if n_consecutive_failures == 0 {
+        StatusCode::NO_CONTENT
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status_code, [(header::CACHE_CONTROL, "no-cache, no-store, must-revalidate")])
+}
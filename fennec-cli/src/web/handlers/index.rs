@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
 use axum::extract::State;
+use chrono::TimeDelta;
 use maud::{Markup, PreEscaped, html};
 use tokio::sync::RwLock;
 
@@ -8,7 +9,15 @@ use crate::{
     battery::WorkingMode,
     engine,
     prelude::*,
-    quantity::{currency::Mills, energy::WattHours},
+    quantity::{
+        Zero,
+        currency::{Currency, Mills},
+        energy::WattHours,
+        power::Watts,
+        price::KilowattHourPrice,
+        ratios::Percentage,
+    },
+    solution::{Attribution, Plan},
     web::{partials, working_mode::WorkingModeColor},
 };
 
@@ -37,7 +46,35 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                                     }
                                 }
                                 span.tag {
-                                    (plan.metrics.losses.total())
+                                    (state.currency.format(plan.metrics.losses.total().into()))
+                                }
+                            }
+                        }
+                        @if state.inverter_standby_power > Watts::ZERO {
+                            div.control {
+                                div.tags.has-addons {
+                                    span.tag.is-info {
+                                        span.icon-text {
+                                            span.icon { i.fas.fa-plug {} }
+                                            span { "Stand-by" }
+                                        }
+                                    }
+                                    span.tag title="Inverter stand-by consumption over the next 24 hours at the import prices" {
+                                        (state.currency.format(standby_daily_cost(plan, state.inverter_standby_power)))
+                                    }
+                                }
+                            }
+                        }
+                        div.control {
+                            div.tags.has-addons {
+                                span.tag.is-info {
+                                    span.icon-text {
+                                        span.icon { i.fas.fa-battery-half {} }
+                                        span { "Stored" }
+                                    }
+                                }
+                                span.tag title="Market value of the energy left in the battery by the end of the plan" {
+                                    (state.currency.format(plan.residual_energy_value))
                                 }
                             }
                         }
@@ -61,6 +98,12 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                                         span { (plan.metrics.internal_battery_flow.export) }
                                     }
                                 }
+                                span.tag title="Full-equivalent battery cycles" {
+                                    span.icon-text {
+                                        span.icon { i.fas.fa-rotate {} }
+                                        span { (format!("{:.2}", plan.n_cycles)) }
+                                    }
+                                }
                             }
                         }
                     }
@@ -77,6 +120,47 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                             }
                         }
                     }
+                    @if let Some(battery_temperature) = state.battery_temperature {
+                        div.control {
+                            div.tags.has-addons {
+                                span.tag.is-info {
+                                    span.icon-text {
+                                        span.icon { i.fas.fa-temperature-half {} }
+                                        span { "Temperature" }
+                                    }
+                                }
+                                span.tag { (format!("{battery_temperature:.1}°C")) }
+                            }
+                        }
+                    }
+                    @if let Some(trend) = state.battery_capacity_trend {
+                        div.control {
+                            div.tags.has-addons {
+                                span.tag.is-info {
+                                    span.icon-text {
+                                        span.icon { i.fas.fa-heart-pulse {} }
+                                        span { "Health" }
+                                    }
+                                }
+                                span.tag title="Fitted state-of-health" {
+                                    (format!("{:.1}%", trend.state_of_health))
+                                }
+                                span.tag title="Capacity change per 30 days" {
+                                    (format!("{:+.2}%", trend.change_per_month))
+                                    " "
+                                    (trend.capacity_change_per_month)
+                                }
+                                @if let Some(threshold_reached_at) = trend.threshold_reached_at {
+                                    span.tag title="Projected to drop below the health threshold" {
+                                        span.icon-text {
+                                            span.icon { i.fas.fa-calendar-xmark {} }
+                                            span { (threshold_reached_at.format("%b %Y")) }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
                     div.control {
                         div.tags.has-addons {
                             span.tag.is-info {
@@ -94,13 +178,25 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                             span.tag {
                                 span.icon-text {
                                     span.icon { i.fas.fa-angle-down {} }
-                                    span { (format!("{:.1}%", 100.0 * energy_profile.battery.efficiency.import)) }
+                                    span title="Mean absolute deviation of the samples" {
+                                        (format!(
+                                            "{:.1}±{:.1}%",
+                                            100.0 * energy_profile.battery.efficiency.import,
+                                            100.0 * energy_profile.battery.efficiency_deviation.import,
+                                        ))
+                                    }
                                 }
                             }
                             span.tag {
                                 span.icon-text {
                                     span.icon { i.fas.fa-angle-up {} }
-                                    span { (format!("{:.1}%", 100.0 * energy_profile.battery.efficiency.export)) }
+                                    span title="Mean absolute deviation of the samples" {
+                                        (format!(
+                                            "{:.1}±{:.1}%",
+                                            100.0 * energy_profile.battery.efficiency.export,
+                                            100.0 * energy_profile.battery.efficiency_deviation.export,
+                                        ))
+                                    }
                                 }
                             }
                         }
@@ -138,11 +234,17 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                                         td { (slot.interval.start().format("%H:%M")) }
                                         td { (slot.interval.end().format("%H:%M")) }
                                         td { (format!("{:.0} min", slot.value.1.duration.0 * 60.0)) }
-                                        td.has-text-right.has-text-weight-medium[slot.value.1.working_mode != WorkingMode::Idle] {
+                                        @let transport_cost = state.transport_costs.at(slot.interval.start());
+                                        td.has-text-right.has-text-weight-medium[slot.value.1.working_mode != WorkingMode::Idle]
+                                            title=[(transport_cost != KilowattHourPrice::ZERO).then(|| format!("Including {transport_cost} transport"))]
+                                        {
                                             (slot.value.0.import)
                                         }
                                         td {
                                             (slot.value.1.working_mode)
+                                            @if slot.value.1.power_level != Percentage::FULL {
+                                                " " (slot.value.1.power_level)
+                                            }
                                         }
                                         td.has-text-right.has-text-weight-medium[slot.value.1.energy_balance.grid.import >= WattHours::ONE] {
                                             span.icon-text.is-flex-wrap-nowrap {
@@ -171,11 +273,94 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
                                         td.has-text-right {
                                             (slot.value.1.residual_energy_after)
                                         }
-                                        td.has-text-right.has-text-weight-medium[slot.value.1.metrics.losses.grid >= Mills::TEN] {
-                                            (slot.value.1.metrics.losses.grid)
+                                        td.has-text-right.has-text-weight-medium[Mills::from(slot.value.1.metrics.losses.grid) >= Mills::TEN] {
+                                            (Mills::from(slot.value.1.metrics.losses.grid))
                                         }
-                                        td.has-text-right.has-text-weight-medium[slot.value.1.metrics.losses.battery >= Mills::TEN] {
-                                            (slot.value.1.metrics.losses.battery)
+                                        td.has-text-right.has-text-weight-medium[Mills::from(slot.value.1.metrics.losses.battery) >= Mills::TEN] {
+                                            (Mills::from(slot.value.1.metrics.losses.battery))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            @if let Some(ev_plan) = &state.ev_plan {
+                section.section.py-0.my-5 {
+                    h2.title.is-5 { "EV charging" }
+                    p.mb-3 { "Deadline: " (ev_plan.deadline.format("%a %H:%M")) }
+                    div.table-container {
+                        table.table.is-striped.is-narrow.is-hoverable {
+                            thead {
+                                tr {
+                                    th { "Start time" }
+                                    th { "End time" }
+                                    th.has-text-right { "Power" }
+                                }
+                            }
+                            tbody {
+                                @for window in &ev_plan.windows {
+                                    tr {
+                                        td { (window.interval.start().format("%a %H:%M")) }
+                                        td { (window.interval.end().format("%H:%M")) }
+                                        td.has-text-right { (window.power) }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            @if state.executions.history().next().is_some() {
+                @let attribution = state
+                    .executions
+                    .history()
+                    .fold(Attribution::ZERO, |sum, execution| sum + execution.attribution());
+                section.section.py-0.my-5 {
+                    h2.title.is-5 { "Planned vs actual" }
+                    div.field.is-grouped.is-grouped-multiline {
+                        (attribution_tag("Realized minus planned", attribution.total(), &state.currency))
+                        (attribution_tag("Consumption error", attribution.consumption, &state.currency))
+                        (attribution_tag("Battery error", attribution.battery, &state.currency))
+                        (attribution_tag("Latency", attribution.latency, &state.currency))
+                    }
+                    div.table-container {
+                        table.table.is-striped.is-narrow.is-hoverable.is-fullwidth {
+                            thead { (executions_table_header()) }
+                            tbody {
+                                @for execution in state.executions.history().rev() {
+                                    tr.(WorkingModeColor(execution.planned.working_mode)) {
+                                        td { (execution.interval.start().format("%H:%M")) }
+                                        td { (execution.interval.end().format("%H:%M")) }
+                                        td { (execution.planned.working_mode) }
+                                        td.has-text-right { (execution.planned.energy_balance.grid.import) }
+                                        td.has-text-right { (execution.actual.grid.import) }
+                                        td.has-text-right { (execution.planned.energy_balance.grid.export) }
+                                        td.has-text-right { (execution.actual.grid.export) }
+                                        td.has-text-right { (execution.planned.energy_balance.battery.import) }
+                                        td.has-text-right { (execution.actual.battery.import) }
+                                        td.has-text-right { (execution.planned.energy_balance.battery.export) }
+                                        td.has-text-right { (execution.actual.battery.export) }
+                                        @let attribution = execution.attribution();
+                                        td.has-text-right { (attribution.consumption) }
+                                        td.has-text-right { (attribution.battery) }
+                                        td.has-text-right { (attribution.latency) }
+                                        td.has-text-right {
+                                            @if let Some(deviation) = execution.battery_deviation() {
+                                                (format!("{:+.0}%", deviation * 100.0))
+                                            } @else {
+                                                "—"
+                                            }
+                                        }
+                                        td.has-text-right {
+                                            @if let Some(drift) = execution.residual_energy_drift() {
+                                                (drift)
+                                            } @else {
+                                                "—"
+                                            }
                                         }
                                     }
                                 }
@@ -188,6 +373,52 @@ pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> Markup {
     )
 }
 
+fn executions_table_header() -> Markup {
+    html! {
+        tr {
+            th { "Start time" }
+            th { "End time" }
+            th { "Working mode" }
+            th.has-text-right { "Planned grid import" }
+            th.has-text-right { "Actual grid import" }
+            th.has-text-right { "Planned grid export" }
+            th.has-text-right { "Actual grid export" }
+            th.has-text-right { "Planned battery import" }
+            th.has-text-right { "Actual battery import" }
+            th.has-text-right { "Planned battery export" }
+            th.has-text-right { "Actual battery export" }
+            th.has-text-right { "Consumption error" }
+            th.has-text-right { "Battery error" }
+            th.has-text-right { "Latency" }
+            th.has-text-right { "Battery deviation" }
+            th.has-text-right { "Residual energy drift" }
+        }
+    }
+}
+
+fn attribution_tag(title: &str, value: Mills, currency: &Currency) -> Markup {
+    html! {
+        div.control {
+            div.tags.has-addons {
+                span.tag.is-info { (title) }
+                span.tag title=(value.to_string()) { (currency.format(value)) }
+            }
+        }
+    }
+}
+
+/// Inverter stand-by consumption over the first day of the plan, valued at the import prices.
+fn standby_daily_cost(plan: &Plan, standby_power: Watts) -> Mills {
+    let Some(until) = plan.schedule.start_index().map(|start| start + TimeDelta::days(1)) else {
+        return Mills::ZERO;
+    };
+    plan.schedule
+        .iter()
+        .take_while(|slot| slot.interval.start() < until)
+        .map(|slot| standby_power * slot.value.1.duration * slot.value.0.import)
+        .sum()
+}
+
 fn steps_table_header() -> Markup {
     html! {
         tr {
@@ -0,0 +1,187 @@
+//! Prometheus exposition of the engine state, so that no separate exporter has to poll the battery.
+
+use std::{
+    fmt::{Display, Write},
+    sync::Arc,
+};
+
+use axum::{extract::State, response::IntoResponse};
+use http::header;
+use tokio::sync::RwLock;
+
+use crate::{engine, ops::health::SourceHealth, prelude::*};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[instrument(skip_all)]
+pub async fn get(State(state): State<Arc<RwLock<engine::State>>>) -> impl IntoResponse {
+    debug!("scrape");
+    let body = render(&*state.read().await);
+    ([(header::CONTENT_TYPE, CONTENT_TYPE)], body)
+}
+
+/// Render the metrics in the Prometheus text format.
+fn render(state: &engine::State) -> String {
+    let mut exposition = Exposition::default();
+
+    exposition
+        .family("fennec_engine_consecutive_failures", "gauge", "Engine iterations failed in a row.")
+        .sample(&[], state.n_consecutive_failures);
+
+    if let Some(reading) = state.battery_reading {
+        exposition
+            .family(
+                "fennec_battery_reading_timestamp_seconds",
+                "gauge",
+                "Time of the most recent battery reading.",
+            )
+            .sample(&[], reading.measured_at.timestamp());
+        exposition
+            .family("fennec_battery_state_of_charge_percent", "gauge", "Battery state-of-charge.")
+            .sample(&[], reading.state_of_charge.0);
+        exposition
+            .family("fennec_battery_state_of_health_percent", "gauge", "Battery state-of-health.")
+            .sample(&[], reading.state_of_health.0);
+        exposition
+            .family(
+                "fennec_battery_allowed_state_of_charge_percent",
+                "gauge",
+                "Allowed state-of-charge range per the battery settings.",
+            )
+            .sample(&[("bound", "min")], reading.min_state_of_charge.0)
+            .sample(&[("bound", "max")], reading.max_state_of_charge.0);
+        exposition
+            .family(
+                "fennec_battery_residual_energy_watt_hours",
+                "gauge",
+                "Residual energy corrected on the state-of-health.",
+            )
+            .sample(&[], reading.residual_energy.0);
+        exposition
+            .family(
+                "fennec_battery_active_power_watts",
+                "gauge",
+                "Battery power, positive when discharging.",
+            )
+            .sample(&[], reading.active_power.0);
+    }
+
+    if let Some(flows) = state.flows {
+        exposition
+            .family("fennec_grid_power_watts", "gauge", "Grid power, positive when importing.")
+            .sample(&[], flows.grid.0);
+        if let Some(pv) = flows.pv {
+            exposition.family("fennec_pv_power_watts", "gauge", "PV production.").sample(&[], pv.0);
+        }
+        if let Some(load) = flows.household_load() {
+            exposition
+                .family("fennec_household_load_watts", "gauge", "Gross household consumption.")
+                .sample(&[], load.0);
+        }
+    }
+
+    if let Some(temperature) = state.battery_temperature {
+        exposition
+            .family("fennec_battery_temperature_celsius", "gauge", "Battery temperature.")
+            .sample(&[], temperature);
+    }
+
+    let sources = [("battery", &state.sources.battery), ("grid", &state.sources.grid)];
+    exposition.source_family(
+        "fennec_source_reads_total",
+        "counter",
+        "Measurement source reads, each including its retries.",
+        &sources,
+        |health| health.n_reads,
+    );
+    exposition.source_family(
+        "fennec_source_failures_total",
+        "counter",
+        "Failed measurement source reads.",
+        &sources,
+        |health| health.n_failures,
+    );
+    exposition.source_family(
+        "fennec_source_consecutive_failures",
+        "gauge",
+        "Measurement source reads failed in a row.",
+        &sources,
+        |health| health.n_consecutive_failures,
+    );
+
+    exposition.0
+}
+
+#[derive(Default)]
+struct Exposition(String);
+
+impl Exposition {
+    /// Start a metric family, the following samples belong to it.
+    fn family(&mut self, name: &'static str, kind: &str, help: &str) -> Family<'_> {
+        write!(self.0, "# HELP {name} {help}\n# TYPE {name} {kind}\n").unwrap();
+        Family { exposition: self, name }
+    }
+
+    fn source_family<V: Display>(
+        &mut self,
+        name: &'static str,
+        kind: &str,
+        help: &str,
+        sources: &[(&str, &SourceHealth)],
+        value_of: impl Fn(&SourceHealth) -> V,
+    ) {
+        let mut family = self.family(name, kind, help);
+        for &(source, health) in sources {
+            family.sample(&[("source", source)], value_of(health));
+        }
+    }
+}
+
+struct Family<'a> {
+    exposition: &'a mut Exposition,
+    name: &'static str,
+}
+
+impl Family<'_> {
+    fn sample(&mut self, labels: &[(&str, &str)], value: impl Display) -> &mut Self {
+        let labels = labels
+            .iter()
+            .map(|(name, value)| format!("{name}=\"{value}\""))
+            .collect::<Vec<_>>()
+            .join(",");
+        let name = self.name;
+        if labels.is_empty() {
+            writeln!(self.exposition.0, "{name} {value}").unwrap();
+        } else {
+            writeln!(self.exposition.0, "{name}{{{labels}}} {value}").unwrap();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exposition_ok() {
+        let mut exposition = Exposition::default();
+        exposition.family("fennec_answer", "gauge", "The answer.").sample(&[], 42);
+        exposition.source_family(
+            "fennec_reads_total",
+            "counter",
+            "Reads.",
+            &[("grid", &SourceHealth { n_reads: 3, ..SourceHealth::default() })],
+            |health| health.n_reads,
+        );
+        assert_eq!(
+            exposition.0,
+            "# HELP fennec_answer The answer.\n\
+             # TYPE fennec_answer gauge\n\
+             fennec_answer 42\n\
+             # HELP fennec_reads_total Reads.\n\
+             # TYPE fennec_reads_total counter\n\
+             fennec_reads_total{source=\"grid\"} 3\n"
+        );
+    }
+}
@@ -1,4 +1,7 @@
 //! Modbus client wrappers for different devices.
 
+pub mod deye;
+pub mod eastron;
 pub mod mini_qube;
 pub mod types;
+pub mod victron;
@@ -0,0 +1,131 @@
+//! Functions for Deye and Sunsynk hybrid inverters.
+//!
+//! The inverter has a built-in time-of-use table of six programs. Each program starts at its
+//! time point and lasts until the next program's time point, the last one wraps around midnight.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fennec_modbus::{contrib::deye::TimeOfUse, protocol::codec::Encode};
+//!
+//! let bytes = TimeOfUse::default().to_bytes();
+//! assert_eq!(bytes.len(), 60);
+//! ```
+
+use bytes::{Buf, BufMut};
+
+use crate::{
+    Error,
+    contrib::types::{Percentage, Watts},
+    protocol::{
+        address,
+        codec::{BitSize, Decode, Encode, order::LowWordFirst},
+        function::{ReadHoldingRegisters, WriteMultipleRegisters},
+    },
+    tcp,
+};
+
+/// Default unit ID ("slave ID") for Deye over a Modbus-TCP gateway.
+pub const UNIT_ID: tcp::UnitId = tcp::UnitId::Significant(1);
+
+/// Number of the time-of-use programs.
+pub const N_PROGRAMS: usize = 6;
+
+/// Read the battery state-of-charge.
+pub type ReadStateOfCharge = ReadHoldingRegisters<address::Const<184>, Percentage<u16>>;
+
+/// Read the battery power.
+///
+/// Positive means discharging, negative means charging.
+pub type ReadBatteryPower = ReadHoldingRegisters<address::Const<190>, Watts<i16>>;
+
+/// Read the total battery charge energy.
+pub type ReadTotalBatteryChargeEnergy = ReadHoldingRegisters<address::Const<72>, TotalEnergy>;
+
+/// Read the total battery discharge energy.
+pub type ReadTotalBatteryDischargeEnergy = ReadHoldingRegisters<address::Const<74>, TotalEnergy>;
+
+/// Read the complete time-of-use table.
+pub type ReadTimeOfUse = ReadHoldingRegisters<address::Const<148>, TimeOfUse>;
+
+/// Write the complete time-of-use table.
+pub type WriteTimeOfUse = WriteMultipleRegisters<address::Const<148>, TimeOfUse>;
+
+/// Total energy counter in hectowatt-hours (0.1 kWh).
+///
+/// Unlike the most of the Modbus devices, Deye puts the *low* word first.
+pub type TotalEnergy = LowWordFirst<u32>;
+
+/// Program start time, encoded as `HHMM` decimal.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[must_use]
+pub struct Time {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl BitSize for Time {
+    const N_BITS: u16 = u16::N_BITS;
+}
+
+impl Encode for Time {
+    fn encode_to(&self, buf: &mut impl BufMut) {
+        buf.put_u16(u16::from(self.hour) * 100 + u16::from(self.minute));
+    }
+}
+
+impl Decode for Time {
+    #[expect(clippy::cast_possible_truncation)]
+    fn decode_from(buf: &mut impl Buf) -> Result<Self, Error> {
+        let value = buf.try_get_u16()?;
+        Ok(Self { hour: (value / 100) as u8, minute: (value % 100) as u8 })
+    }
+}
+
+/// Time-of-use table – registers are grouped by the program parameter, not by the program.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+#[must_use]
+pub struct TimeOfUse {
+    /// Program start times.
+    pub times: [Time; N_PROGRAMS],
+
+    /// Maximum battery power per program.
+    pub powers: [Watts<u16>; N_PROGRAMS],
+
+    /// Battery voltage targets per program, only used in the voltage mode.
+    pub voltages: [u16; N_PROGRAMS],
+
+    /// State-of-charge targets per program: the battery never discharges below the target,
+    /// and charges up to it when grid charging is enabled.
+    pub states_of_charge: [Percentage<u16>; N_PROGRAMS],
+
+    /// Charging flags per program: bit 0 enables grid charging, bit 1 – generator charging.
+    pub charge_flags: [u16; N_PROGRAMS],
+}
+
+impl BitSize for TimeOfUse {
+    #[expect(clippy::cast_possible_truncation)]
+    const N_BITS: u16 = 5 * N_PROGRAMS as u16 * u16::N_BITS;
+}
+
+impl Encode for TimeOfUse {
+    fn encode_to(&self, buf: &mut impl BufMut) {
+        self.times.encode_to(buf);
+        self.powers.encode_to(buf);
+        self.voltages.encode_to(buf);
+        self.states_of_charge.encode_to(buf);
+        self.charge_flags.encode_to(buf);
+    }
+}
+
+impl Decode for TimeOfUse {
+    fn decode_from(buf: &mut impl Buf) -> Result<Self, Error> {
+        Ok(Self {
+            times: Decode::decode_from(buf)?,
+            powers: Decode::decode_from(buf)?,
+            voltages: Decode::decode_from(buf)?,
+            states_of_charge: Decode::decode_from(buf)?,
+            charge_flags: Decode::decode_from(buf)?,
+        })
+    }
+}
@@ -0,0 +1,47 @@
+//! Functions for [Eastron SDM][1] energy meters, such as SDM120 and SDM630.
+//!
+//! The meters expose their measurements as big-endian IEEE 754 floats in the input registers.
+//! The register addresses differ between the models, hence the addresses are runtime values.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fennec_modbus::{
+//!     contrib::eastron::{ReadFloat, SDM630},
+//!     protocol::{Function, codec::Encode},
+//! };
+//!
+//! let args: <ReadFloat as Function>::Args = SDM630.total_power.into();
+//! assert_eq!(args.to_bytes(), [0x00, 0x34, 0x00, 0x02]);
+//! ```
+//!
+//! [1]: https://www.eastroneurope.com/products/category/din-rail-mounted-metering
+
+use crate::{protocol::function::ReadInputRegisters, tcp};
+
+/// Default unit ID ("slave ID") of the meters.
+pub const UNIT_ID: tcp::UnitId = tcp::UnitId::Significant(1);
+
+/// Read a single float measurement at the specified register address.
+pub type ReadFloat = ReadInputRegisters<u16, f32>;
+
+/// Input register addresses of the measurements.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RegisterMap {
+    /// Total active power in watts, positive means import.
+    pub total_power: u16,
+
+    /// Total imported active energy in kilowatt-hours.
+    pub import_energy: u16,
+
+    /// Total exported active energy in kilowatt-hours.
+    pub export_energy: u16,
+}
+
+/// Single-phase SDM120 and SDM230.
+pub const SDM120: RegisterMap =
+    RegisterMap { total_power: 0x000C, import_energy: 0x0048, export_energy: 0x004A };
+
+/// Three-phase SDM630 and SDM72.
+pub const SDM630: RegisterMap =
+    RegisterMap { total_power: 0x0034, import_energy: 0x0048, export_energy: 0x004A };
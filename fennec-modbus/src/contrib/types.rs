@@ -28,11 +28,17 @@ macro_rules! impl_new_type {
     };
 }
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Percentage<T>(pub T);
 
 impl_new_type!(Percentage => u16);
 
+/// Tenths of a percent.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Permille<T>(pub T);
+
+impl_new_type!(Permille => u16);
+
 /// [Decawatt][1]-hours, 1 daWh is equal to 10 [Wh][2].
 ///
 /// [1]: https://en.wiktionary.org/wiki/decawatt
@@ -43,8 +49,9 @@ pub struct DecawattHours<T>(pub T);
 impl_new_type!(DecawattHours => u16);
 impl_new_type!(DecawattHours => u32);
 
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
 pub struct Watts<T>(pub T);
 
 impl_new_type!(Watts => u16);
+impl_new_type!(Watts => i16);
 impl_new_type!(Watts => i32);
@@ -0,0 +1,43 @@
+//! Functions for [Victron Energy GX][1] devices running Venus OS.
+//!
+//! The GX device exposes the connected devices under different unit IDs: the system-wide registers
+//! live under [`SYSTEM_UNIT_ID`], while the VE.Bus inverter/charger unit ID depends on the installation
+//! (see _Settings → Services → Modbus TCP → Available services_).
+//!
+//! The VE.Bus ESS setpoints require the ESS assistant to run in the _external control_ mode.
+//!
+//! [1]: https://www.victronenergy.com/live/ccgx:modbustcp_faq
+
+use crate::{
+    contrib::types::{DecawattHours, Percentage, Permille, Watts},
+    protocol::{
+        address,
+        function::{ReadHoldingRegisters, WriteMultipleRegisters},
+    },
+    tcp,
+};
+
+/// Unit ID of the `com.victronenergy.system` service.
+pub const SYSTEM_UNIT_ID: tcp::UnitId = tcp::UnitId::Significant(100);
+
+/// Read the battery state-of-charge.
+pub type ReadStateOfCharge = ReadHoldingRegisters<address::Const<843>, Percentage<u16>>;
+
+/// Read the battery power.
+///
+/// Positive means charging, negative means discharging.
+pub type ReadBatteryPower = ReadHoldingRegisters<address::Const<842>, Watts<i16>>;
+
+/// Read the ESS minimum state-of-charge (unless the grid fails).
+pub type ReadMinimumStateOfCharge = ReadHoldingRegisters<address::Const<2901>, Permille<u16>>;
+
+/// Read the total VE.Bus energy charged from the AC input 1 to the battery.
+pub type ReadTotalGridImportEnergy = ReadHoldingRegisters<address::Const<76>, DecawattHours<u32>>;
+
+/// Read the total VE.Bus energy discharged from the battery to the AC input 1.
+pub type ReadTotalGridExportEnergy = ReadHoldingRegisters<address::Const<86>, DecawattHours<u32>>;
+
+/// Write the VE.Bus ESS power setpoint on the phase 1.
+///
+/// Positive means charging from the grid, negative means feeding into the grid.
+pub type WriteEssPowerSetpoint = WriteMultipleRegisters<address::Const<37>, Watts<i16>>;
@@ -17,4 +17,13 @@ pub enum Error {
 
     #[error("payload size exceeded ({0} bytes)")]
     PayloadSizeExceeded(usize),
+
+    #[error("frame is too short ({0} bytes)")]
+    FrameTooShort(usize),
+
+    #[error("CRC mismatch: expected 0x{expected:04X}, actual 0x{actual:04X}")]
+    CrcMismatch { expected: u16, actual: u16 },
+
+    #[error("unexpected response unit ID ({0})")]
+    UnexpectedUnitId(u8),
 }
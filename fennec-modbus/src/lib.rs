@@ -6,6 +6,7 @@ extern crate alloc;
 pub mod contrib;
 mod error;
 pub mod protocol;
+pub mod rtu;
 pub mod tcp;
 
 pub use self::error::Error;
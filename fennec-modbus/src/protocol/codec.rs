@@ -4,5 +4,6 @@ pub mod adapters;
 mod bit_size;
 mod decoder;
 mod encoder;
+pub mod order;
 
 pub use self::{bit_size::BitSize, decoder::Decode, encoder::Encode};
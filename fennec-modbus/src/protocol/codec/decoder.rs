@@ -37,3 +37,5 @@ impl_decode!(u64 => try_get_u64);
 impl_decode!(i64 => try_get_i64);
 impl_decode!(u128 => try_get_u128);
 impl_decode!(i128 => try_get_i128);
+impl_decode!(f32 => try_get_f32);
+impl_decode!(f64 => try_get_f64);
@@ -38,3 +38,5 @@ impl_encode!(u64 => put_u64);
 impl_encode!(i64 => put_i64);
 impl_encode!(u128 => put_u128);
 impl_encode!(i128 => put_i128);
+impl_encode!(f32 => put_f32);
+impl_encode!(f64 => put_f64);
@@ -0,0 +1,106 @@
+//! Word and byte order adapters for the devices deviating from the big-endian Modbus convention.
+//!
+//! 32-bit and wider values span several registers, and the Modbus specification
+//! says nothing about their order. Most devices put the high word first,
+//! which is what the plain integer types decode, but some do not.
+//!
+//! # Example
+//!
+//! ```rust
+//! use fennec_modbus::protocol::codec::{
+//!     Decode,
+//!     Encode,
+//!     order::{LowWordFirst, SwappedBytes},
+//! };
+//!
+//! let bytes = [0xFF, 0xFE, 0xFF, 0xFF];
+//! let value = LowWordFirst::<i32>::decode_from(&mut bytes.as_slice()).unwrap();
+//! assert_eq!(value, LowWordFirst(-2));
+//! assert_eq!(value.to_bytes(), bytes);
+//!
+//! let bytes = [0x34, 0x12, 0x78, 0x56];
+//! let value = SwappedBytes::<u32>::decode_from(&mut bytes.as_slice()).unwrap();
+//! assert_eq!(value, SwappedBytes(0x1234_5678));
+//! assert_eq!(value.to_bytes(), bytes);
+//! ```
+
+use alloc::vec;
+
+use bytes::{Buf, BufMut};
+
+use crate::{
+    Error,
+    protocol::codec::{BitSize, Decode, Encode},
+};
+
+/// Multi-register value with the *low* word first, the bytes within the words are intact.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct LowWordFirst<T>(pub T);
+
+/// Value with the bytes swapped within each word, the words are in the usual order.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SwappedBytes<T>(pub T);
+
+impl<T: BitSize> BitSize for LowWordFirst<T> {
+    const N_BITS: u16 = T::N_BITS;
+}
+
+impl<T: BitSize> BitSize for SwappedBytes<T> {
+    const N_BITS: u16 = T::N_BITS;
+}
+
+impl<T: BitSize + Decode> Decode for LowWordFirst<T> {
+    fn decode_from(buf: &mut impl Buf) -> Result<Self, Error> {
+        decode_reordered(buf, reverse_words).map(Self)
+    }
+}
+
+impl<T: BitSize + Decode> Decode for SwappedBytes<T> {
+    fn decode_from(buf: &mut impl Buf) -> Result<Self, Error> {
+        decode_reordered(buf, swap_bytes).map(Self)
+    }
+}
+
+impl<T: Encode> Encode for LowWordFirst<T> {
+    fn encode_to(&self, buf: &mut impl BufMut) {
+        encode_reordered(&self.0, buf, reverse_words);
+    }
+}
+
+impl<T: Encode> Encode for SwappedBytes<T> {
+    fn encode_to(&self, buf: &mut impl BufMut) {
+        encode_reordered(&self.0, buf, swap_bytes);
+    }
+}
+
+/// Read the value's bytes, put them into the big-endian order, and decode the value.
+fn decode_reordered<T: BitSize + Decode>(
+    buf: &mut impl Buf,
+    reorder: fn(&mut [u8]),
+) -> Result<T, Error> {
+    let mut bytes = vec![0; usize::from(T::N_BYTES)];
+    buf.try_copy_to_slice(&mut bytes)?;
+    reorder(&mut bytes);
+    T::decode_from(&mut bytes.as_slice())
+}
+
+/// Encode the value in the big-endian order, and put its bytes into the device order.
+fn encode_reordered<T: Encode>(value: &T, buf: &mut impl BufMut, reorder: fn(&mut [u8])) {
+    let mut bytes = value.to_bytes();
+    reorder(&mut bytes);
+    buf.put_slice(&bytes);
+}
+
+/// Reverse the order of the words, keeping the byte order within each word.
+///
+/// The reordering is its own inverse, so it serves both decoding and encoding.
+fn reverse_words(bytes: &mut [u8]) {
+    bytes.reverse();
+    swap_bytes(bytes);
+}
+
+fn swap_bytes(bytes: &mut [u8]) {
+    for word in bytes.chunks_exact_mut(2) {
+        word.swap(0, 1);
+    }
+}
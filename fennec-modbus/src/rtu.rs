@@ -0,0 +1,128 @@
+//! Sans-IO Modbus RTU framing.
+//!
+//! RTU frame is the unit ID, followed by the PDU and the [CRC-16/MODBUS][1], low byte first.
+//! Unlike Modbus-over-TCP, there is no length field, so the response length is derived
+//! from the function code, see [`remaining_length`].
+//!
+//! [1]: https://reveng.sourceforge.io/crc-catalogue/16.htm#crc.cat.crc-16-modbus
+
+pub mod tokio;
+
+use alloc::vec::Vec;
+
+use bytes::BufMut;
+
+use crate::{Error, protocol::codec::Encode, tcp::UnitId};
+
+/// Number of bytes needed to call [`remaining_length`]: unit ID, function code, and the next byte.
+pub const N_HEADER_BYTES: usize = 3;
+
+/// Number of the trailing CRC bytes.
+pub const N_CRC_BYTES: usize = 2;
+
+/// Wrap the payload, normally a [`crate::protocol::Request`], into an RTU frame.
+///
+/// # Example
+///
+/// ```rust
+/// use fennec_modbus::{protocol::Request, rtu, tcp::UnitId};
+///
+/// // Read 10 holding registers starting at 0:
+/// let request = Request { function_code: 0x03, args: [0x0000_u16, 0x000A] };
+/// let mut frame = Vec::new();
+/// rtu::encode(UnitId::Significant(1), &request, &mut frame);
+/// assert_eq!(
+///     frame,
+///     [
+///         0x01, // unit ID
+///         0x03, 0x00, 0x00, 0x00, 0x0A, // request
+///         0xC5, 0xCD, // CRC: low, high
+///     ]
+/// );
+/// ```
+pub fn encode<P: Encode>(unit_id: UnitId, payload: &P, buf: &mut impl BufMut) {
+    let mut frame = Vec::new();
+    frame.put_u8(unit_id.into());
+    payload.encode_to(&mut frame);
+    buf.put(&*frame);
+    buf.put_u16_le(crc16(&frame));
+}
+
+/// Number of the frame bytes following the [`N_HEADER_BYTES`] received ones, including the CRC.
+///
+/// # Example
+///
+/// ```rust
+/// use fennec_modbus::rtu;
+///
+/// // Read holding registers, 4 bytes follow:
+/// assert_eq!(rtu::remaining_length(&[0x01, 0x03, 0x04]).unwrap(), 6);
+///
+/// // Write multiple registers, address and quantity follow:
+/// assert_eq!(rtu::remaining_length(&[0x01, 0x10, 0x00]).unwrap(), 5);
+///
+/// // Exception, only the CRC follows:
+/// assert_eq!(rtu::remaining_length(&[0x01, 0x83, 0x02]).unwrap(), 2);
+/// ```
+pub fn remaining_length(header: &[u8; N_HEADER_BYTES]) -> Result<usize, Error> {
+    let [_, function_code, next] = *header;
+    match function_code {
+        function_code if function_code >= 0x80 => Ok(N_CRC_BYTES),
+
+        // Read coils, discrete inputs, holding or input registers, and read/write multiple –
+        // the next byte is the byte count:
+        0x01..=0x04 | 0x17 => Ok(usize::from(next) + N_CRC_BYTES),
+
+        // Write single or multiple coils or registers – the address and value or quantity:
+        0x05 | 0x06 | 0x0F | 0x10 => Ok(3 + N_CRC_BYTES),
+
+        function_code => Err(Error::UnexpectedFunctionCode(function_code)),
+    }
+}
+
+/// Verify the frame CRC and split the frame into the unit ID and PDU.
+///
+/// # Example
+///
+/// ```rust
+/// use fennec_modbus::{rtu, tcp::UnitId};
+///
+/// let frame = [0x01, 0x03, 0x02, 0x00, 0x2A, 0x39, 0x9B];
+/// let (unit_id, pdu) = rtu::decode(&frame).unwrap();
+/// assert_eq!(unit_id, UnitId::Significant(1));
+/// assert_eq!(pdu, [0x03, 0x02, 0x00, 0x2A]);
+///
+/// assert!(rtu::decode(&[0x01, 0x03, 0x02, 0x00, 0x2B, 0x39, 0x9B]).is_err());
+/// ```
+pub fn decode(frame: &[u8]) -> Result<(UnitId, &[u8]), Error> {
+    let Some((data, crc)) = frame.split_last_chunk::<N_CRC_BYTES>() else {
+        return Err(Error::FrameTooShort(frame.len()));
+    };
+    let Some((unit_id, pdu)) = data.split_first() else {
+        return Err(Error::FrameTooShort(frame.len()));
+    };
+    let expected = u16::from_le_bytes(*crc);
+    let actual = crc16(data);
+    if actual != expected {
+        return Err(Error::CrcMismatch { expected, actual });
+    }
+    Ok(((*unit_id).into(), pdu))
+}
+
+/// [CRC-16/MODBUS][1].
+///
+/// # Example
+///
+/// ```rust
+/// assert_eq!(fennec_modbus::rtu::crc16(b"123456789"), 0x4B37);
+/// ```
+///
+/// [1]: https://reveng.sourceforge.io/crc-catalogue/16.htm#crc.cat.crc-16-modbus
+#[must_use]
+pub fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0xFFFF, |crc, byte| {
+        (0..8).fold(crc ^ u16::from(*byte), |crc, _| {
+            if crc & 1 == 0 { crc >> 1 } else { (crc >> 1) ^ 0xA001 }
+        })
+    })
+}
@@ -0,0 +1,143 @@
+//! Modbus RTU-over-TCP implementation for [`tokio`].
+//!
+//! This is meant for serial RS485 devices exposed via a transparent gateway, such as `ser2net`
+//! or an Ethernet–RS485 converter in «transparent» mode. The serial line settings – baud rate,
+//! parity, and stop bits – are configured on the gateway.
+
+#![cfg(feature = "tokio")]
+
+use alloc::{vec, vec::Vec};
+use core::time::Duration;
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::ToSocketAddrs,
+    sync::Mutex,
+    time::timeout,
+};
+
+use crate::{
+    protocol::{Function, Request, Response, codec::Decode, function::IntoValue},
+    rtu,
+    tcp,
+    tcp::tokio::{Connection, Error},
+};
+
+/// Modbus RTU-over-TCP client for [`tokio`].
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use anyhow::Result;
+/// use fennec_modbus::{
+///     protocol::{address, function::ReadHoldingRegisters},
+///     rtu::tokio::Client,
+///     tcp::UnitId,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<()> {
+///     let unit_id = UnitId::Significant(1);
+///     let client = Client::new("rs485-gateway.iot.home.arpa:4196");
+///     let decivolts = client.call::<ReadHoldingRegisters<_, u16>>(unit_id, 39201).await?;
+///     Ok(())
+/// }
+/// ```
+///
+/// # Connection management
+///
+/// Same as for [`tcp::tokio::Client`]. Since a serial line cannot tell
+/// a stale response from the current one, the connection is also dropped on a mismatching unit ID.
+#[must_use]
+pub struct Client<E> {
+    connection: Connection<E>,
+    round_trip_timeout: Duration,
+}
+
+impl<E> Client<E> {
+    pub fn new(endpoint: E) -> Self {
+        Self {
+            connection: Connection {
+                endpoint,
+                connect_timeout: Duration::from_secs(5),
+                stream: Mutex::new(None),
+            },
+
+            // Serial lines are slow, 9600 baud is still common:
+            round_trip_timeout: Duration::from_secs(2),
+        }
+    }
+
+    pub const fn with_connect_timeout(mut self, duration: Duration) -> Self {
+        self.connection.connect_timeout = duration;
+        self
+    }
+
+    pub const fn with_round_trip_timeout(mut self, duration: Duration) -> Self {
+        self.round_trip_timeout = duration;
+        self
+    }
+
+    /// Disconnect the client.
+    ///
+    /// Subsequent call will re-establish a connection.
+    pub async fn disconnect(&self) {
+        *self.connection.stream.lock().await = None;
+    }
+}
+
+impl<E> Client<E>
+where
+    E: Clone + ToSocketAddrs,
+{
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "trace"))]
+    pub async fn call<F: Function>(
+        &self,
+        unit_id: tcp::UnitId,
+        args: impl Into<F::Args>,
+    ) -> Result<<F::Output as IntoValue>::Value, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(?unit_id, code = ?F::CODE, "calling function…");
+
+        let mut request_frame = Vec::new();
+        rtu::encode(unit_id, &Request::wrap::<F>(args.into()), &mut request_frame);
+
+        let mut connection = self.connection.get().await?;
+
+        let future = async {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(len = request_frame.len(), "writing frame…");
+            connection.get_mut().write_all(&request_frame).await?;
+
+            let mut header = [0; rtu::N_HEADER_BYTES];
+            connection.get_mut().read_exact(&mut header).await?;
+            let remaining_length = rtu::remaining_length(&header)?;
+
+            #[cfg(feature = "tracing")]
+            tracing::trace!(remaining_length, "reading the rest of the frame…");
+
+            let mut response_frame = vec![0; rtu::N_HEADER_BYTES + remaining_length];
+            response_frame[..rtu::N_HEADER_BYTES].copy_from_slice(&header);
+            connection.get_mut().read_exact(&mut response_frame[rtu::N_HEADER_BYTES..]).await?;
+
+            let (response_unit_id, _) = rtu::decode(&response_frame)?;
+            if response_unit_id != unit_id {
+                return Err(crate::Error::UnexpectedUnitId(response_unit_id.into()).into());
+            }
+            Ok::<_, Error>(response_frame)
+        };
+
+        let response_frame = timeout(self.round_trip_timeout, future)
+            .await
+            .map_err(Error::TransactionTimeout)
+            .flatten()
+            .inspect_err(|error| {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("invalidating connection because of error: {error:#}");
+
+                connection.invalidate();
+            })?;
+        let (_, mut pdu) = rtu::decode(&response_frame)?;
+        Ok(Response::<F>::decode_from(&mut pdu)?.into_result()?.into_value())
+    }
+}
@@ -20,16 +20,16 @@ use crate::{
 };
 
 #[must_use]
-struct Connection<E> {
-    endpoint: E,
-    connect_timeout: Duration,
-    stream: Mutex<Option<TcpStream>>,
+pub(crate) struct Connection<E> {
+    pub(crate) endpoint: E,
+    pub(crate) connect_timeout: Duration,
+    pub(crate) stream: Mutex<Option<TcpStream>>,
 }
 
 impl<E> Connection<E> {
     /// Lazily establish a connection when needed and return the TCP stream.
     #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, level = "debug"))]
-    async fn get(&self) -> Result<ConnectionGuard<'_>, Error>
+    pub(crate) async fn get(&self) -> Result<ConnectionGuard<'_>, Error>
     where
         E: Clone + ToSocketAddrs,
     {
@@ -52,14 +52,14 @@ impl<E> Connection<E> {
     }
 }
 
-struct ConnectionGuard<'a>(MutexGuard<'a, Option<TcpStream>>);
+pub(crate) struct ConnectionGuard<'a>(MutexGuard<'a, Option<TcpStream>>);
 
 impl ConnectionGuard<'_> {
-    fn get_mut(&mut self) -> &mut TcpStream {
+    pub(crate) fn get_mut(&mut self) -> &mut TcpStream {
         self.0.as_mut().unwrap()
     }
 
-    fn invalidate(mut self) {
+    pub(crate) fn invalidate(mut self) {
         *self.0 = None;
     }
 }